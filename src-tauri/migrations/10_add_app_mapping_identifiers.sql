@@ -0,0 +1,8 @@
+-- Migration: 10_add_app_mapping_identifiers.sql
+-- Lets app mappings also match macOS bundle ids, Windows executables and Linux WM_CLASS/desktop ids
+
+BEGIN TRANSACTION;
+
+ALTER TABLE app_mappings ADD COLUMN identifiers TEXT;
+
+COMMIT;
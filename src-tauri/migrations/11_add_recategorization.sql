@@ -0,0 +1,29 @@
+-- Migration: 11_add_recategorization.sql
+-- Keeps the category the user chose by hand and records bulk recategorizations so they can
+-- be undone
+
+BEGIN TRANSACTION;
+
+-- Set when the user recategorizes an activity; reapplying mappings leaves those alone
+ALTER TABLE activity_entries ADD COLUMN category_override TEXT;
+ALTER TABLE activity_entries ADD COLUMN category_overridden_at TEXT;
+
+CREATE TABLE IF NOT EXISTS recategorization_runs (
+    id TEXT PRIMARY KEY,
+    start_time TEXT NOT NULL,
+    end_time TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    undone_at TEXT
+);
+
+CREATE TABLE IF NOT EXISTS recategorization_changes (
+    run_id TEXT NOT NULL,
+    activity_id TEXT NOT NULL,
+    old_category TEXT NOT NULL,
+    new_category TEXT NOT NULL,
+    PRIMARY KEY (run_id, activity_id),
+    FOREIGN KEY (run_id) REFERENCES recategorization_runs (id),
+    FOREIGN KEY (activity_id) REFERENCES activity_entries (id)
+);
+
+COMMIT;
@@ -0,0 +1,18 @@
+-- Migration: 12_add_category_provenance.sql
+-- Keeps the category computed from mappings apart from the user's override. `category`
+-- stays the effective category (the override if there is one, the computed one otherwise).
+
+BEGIN TRANSACTION;
+
+ALTER TABLE activity_entries ADD COLUMN computed_category TEXT;
+ALTER TABLE activity_entries ADD COLUMN computed_source TEXT;
+ALTER TABLE activity_entries ADD COLUMN computed_mapping_id TEXT;
+
+-- What was computed for hand-corrected activities wasn't kept, so it stays NULL
+UPDATE activity_entries SET computed_category = category WHERE category_override IS NULL;
+
+-- Undoing a recategorization also restores where the computed category came from
+ALTER TABLE recategorization_changes ADD COLUMN old_source TEXT;
+ALTER TABLE recategorization_changes ADD COLUMN old_mapping_id TEXT;
+
+COMMIT;
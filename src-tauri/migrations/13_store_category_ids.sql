@@ -0,0 +1,73 @@
+-- Migration: 13_store_category_ids.sql
+-- Activity categories are stored as the `user_categories` id instead of the serialized
+-- enum: "Development" becomes development, {"Custom":"<id>"} becomes <id>.
+-- Mappings and focus mode categories were compared case-insensitively with the enum, so
+-- their built-in ids are lowercased as well.
+
+BEGIN TRANSACTION;
+
+-- Every stored value with its id, converted once here and looked up by each table below
+CREATE TEMP TABLE legacy_category_ids (
+    legacy TEXT PRIMARY KEY,
+    category_id TEXT NOT NULL
+);
+
+INSERT INTO legacy_category_ids (legacy, category_id)
+SELECT legacy, CASE
+    WHEN json_valid(legacy) AND json_type(legacy) = 'object'
+        THEN COALESCE(json_extract(legacy, '$.Custom'), 'unknown')
+    WHEN json_valid(legacy) AND json_type(legacy) = 'text'
+        THEN lower(json_extract(legacy, '$'))
+    WHEN lower(legacy) IN ('development', 'productive', 'communication', 'social',
+        'entertainment', 'utility', 'design', 'education', 'finance', 'unknown')
+        THEN lower(legacy)
+    ELSE legacy
+END
+FROM (
+    SELECT category AS legacy FROM activity_entries
+    UNION SELECT computed_category FROM activity_entries
+    UNION SELECT category_override FROM activity_entries
+    UNION SELECT old_category FROM recategorization_changes
+    UNION SELECT new_category FROM recategorization_changes
+    UNION SELECT category_id FROM app_mappings
+    UNION SELECT category_id FROM url_mappings
+    UNION SELECT category_id FROM title_mappings
+    UNION SELECT category_id FROM focus_mode_allowed_categories
+)
+WHERE legacy IS NOT NULL;
+
+UPDATE activity_entries SET
+    category = (SELECT l.category_id FROM legacy_category_ids l
+        WHERE l.legacy = activity_entries.category),
+    computed_category = (SELECT l.category_id FROM legacy_category_ids l
+        WHERE l.legacy = activity_entries.computed_category),
+    category_override = (SELECT l.category_id FROM legacy_category_ids l
+        WHERE l.legacy = activity_entries.category_override);
+
+UPDATE recategorization_changes SET
+    old_category = (SELECT l.category_id FROM legacy_category_ids l
+        WHERE l.legacy = recategorization_changes.old_category),
+    new_category = (SELECT l.category_id FROM legacy_category_ids l
+        WHERE l.legacy = recategorization_changes.new_category);
+
+UPDATE app_mappings SET category_id = (SELECT l.category_id FROM legacy_category_ids l
+    WHERE l.legacy = app_mappings.category_id);
+
+UPDATE url_mappings SET category_id = (SELECT l.category_id FROM legacy_category_ids l
+    WHERE l.legacy = url_mappings.category_id);
+
+UPDATE title_mappings SET category_id = (SELECT l.category_id FROM legacy_category_ids l
+    WHERE l.legacy = title_mappings.category_id);
+
+-- category_id is the primary key here, so insert the rewritten ids and drop the old rows
+INSERT OR IGNORE INTO focus_mode_allowed_categories (category_id)
+SELECT l.category_id FROM legacy_category_ids l
+JOIN focus_mode_allowed_categories f ON f.category_id = l.legacy;
+
+DELETE FROM focus_mode_allowed_categories WHERE category_id IN (
+    SELECT legacy FROM legacy_category_ids WHERE legacy != category_id
+);
+
+DROP TABLE legacy_category_ids;
+
+COMMIT;
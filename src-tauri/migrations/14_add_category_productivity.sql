@@ -0,0 +1,10 @@
+-- Migration: 14_add_category_productivity.sql
+-- How productive time in a category is, from -2 (very distracting) to 2 (very productive).
+-- Everything starts neutral; the seeded categories get their weights from categories.json
+-- when the initial data is loaded.
+
+BEGIN TRANSACTION;
+
+ALTER TABLE user_categories ADD COLUMN productivity INTEGER NOT NULL DEFAULT 0;
+
+COMMIT;
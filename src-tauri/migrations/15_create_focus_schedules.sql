@@ -0,0 +1,24 @@
+-- Migration: 15_create_focus_schedules.sql
+-- Weekly windows that turn focus mode on by themselves, each with its own allowed categories
+
+BEGIN TRANSACTION;
+
+CREATE TABLE IF NOT EXISTS focus_schedules (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    days TEXT NOT NULL, -- Comma-separated weekdays, 0 = Monday
+    start_time TEXT NOT NULL, -- Local HH:MM
+    end_time TEXT NOT NULL,
+    enabled INTEGER NOT NULL DEFAULT 1,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS focus_schedule_categories (
+    schedule_id TEXT NOT NULL,
+    category_id TEXT NOT NULL,
+    PRIMARY KEY (schedule_id, category_id),
+    FOREIGN KEY (schedule_id) REFERENCES focus_schedules (id) ON DELETE CASCADE
+);
+
+COMMIT;
@@ -0,0 +1,8 @@
+-- Migration: 6_add_idle_threshold.sql
+-- Adds the idle threshold (seconds without input before tracking pauses) to focus_mode_settings
+
+BEGIN TRANSACTION;
+
+INSERT OR IGNORE INTO focus_mode_settings (key, value) VALUES ('idle_threshold_seconds', '300');
+
+COMMIT;
@@ -0,0 +1,13 @@
+-- Migration: 7_add_mapping_match_kind.sql
+-- Adds how each app/URL mapping pattern is matched (exact, substring, glob, regex, domain_suffix)
+
+BEGIN TRANSACTION;
+
+ALTER TABLE app_mappings ADD COLUMN match_kind TEXT NOT NULL DEFAULT 'substring';
+ALTER TABLE url_mappings ADD COLUMN match_kind TEXT NOT NULL DEFAULT 'substring';
+
+-- Seeded patterns are app names and domains; user mappings keep matching as before
+UPDATE app_mappings SET match_kind = 'exact' WHERE is_custom = 0;
+UPDATE url_mappings SET match_kind = 'domain_suffix' WHERE is_custom = 0;
+
+COMMIT;
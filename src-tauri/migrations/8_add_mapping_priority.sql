@@ -0,0 +1,9 @@
+-- Migration: 8_add_mapping_priority.sql
+-- Adds an explicit priority to app/URL mappings; higher priorities win when several mappings match
+
+BEGIN TRANSACTION;
+
+ALTER TABLE app_mappings ADD COLUMN priority INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE url_mappings ADD COLUMN priority INTEGER NOT NULL DEFAULT 0;
+
+COMMIT;
@@ -0,0 +1,18 @@
+-- Migration: 9_create_title_mappings.sql
+-- Window-title rules, checked after URL mappings and before app mappings
+
+BEGIN TRANSACTION;
+
+CREATE TABLE IF NOT EXISTS title_mappings (
+    id TEXT PRIMARY KEY,
+    title_pattern TEXT NOT NULL,
+    app_pattern TEXT, -- NULL applies to every app, otherwise exact app names separated by |
+    category_id TEXT NOT NULL,
+    is_custom INTEGER NOT NULL DEFAULT 0,
+    match_kind TEXT NOT NULL DEFAULT 'substring',
+    priority INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
+
+COMMIT;
@@ -1,4 +1,4 @@
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use tauri::{AppHandle, Emitter, Manager, State};
 use tokio::time::{interval, Duration, Instant};
 use uuid::Uuid;
@@ -6,7 +6,13 @@ use uuid::Uuid;
 use crate::commands;
 use crate::database::Database;
 use crate::focus_mode::FocusMode;
-use crate::models::{ActivityCategory, ActivityEntry};
+use crate::mappings;
+use crate::models::{
+    ActivityCategory, ActivityEntry, ActivitySegment, CategorizationExplanation,
+    CategorizationSource, CategoryProvenance, CategoryTransition, MappingRef,
+    RecategorizationReport, SegmentType,
+};
+use crate::tracker::{CurrentActivity, SegmentInfo};
 use crate::AppState;
 
 /// Helper to handle pause operations initiated from the tray/menu.
@@ -22,63 +28,496 @@ pub async fn handle_pause_operation(
     }
 }
 
-/// Helper function to categorize activity based on app name and URL with database mappings only
+/// Helper function to categorize activity based on URL, window title and app name or
+/// identifier with database mappings only
 pub async fn categorize_activity(
     db: &Database,
     app_name: &str,
-    _bundle_id: Option<&str>,
+    bundle_id: Option<&str>,
+    window_title: &str,
     url: Option<&str>,
 ) -> ActivityCategory {
-    // First, try URL-based categorization if URL is available
-    if let Some(url_str) = url {
-        if let Ok(url_mappings) = db.get_url_mappings().await {
-            for mapping in url_mappings {
-                // Split pattern by "|" and check if any pattern matches
-                let patterns: Vec<&str> = mapping.url_pattern.split('|').collect();
-                let url_lower = url_str.to_lowercase();
-
-                for pattern in patterns {
-                    if url_lower.contains(&pattern.trim().to_lowercase()) {
-                        // Convert category_id to ActivityCategory
-                        return match mapping.category_id.to_lowercase().as_str() {
-                            "development" => ActivityCategory::Development,
-                            "communication" => ActivityCategory::Communication,
-                            "social" => ActivityCategory::Social,
-                            "entertainment" => ActivityCategory::Entertainment,
-                            "productive" => ActivityCategory::Productive,
-                            _ => ActivityCategory::Custom(mapping.category_id),
-                        };
-                    }
+    categorize(db, app_name, bundle_id, window_title, url)
+        .await
+        .category
+}
+
+/// The computed category of an observed window and the mapping that decided it
+pub struct Categorization {
+    pub category: ActivityCategory,
+    pub source: CategorizationSource,
+    pub mapping_id: Option<Uuid>,
+}
+
+/// Categorize on every tick: the same stages as `explain_categorization`, but without
+/// building the explanation
+pub async fn categorize(
+    db: &Database,
+    app_name: &str,
+    bundle_id: Option<&str>,
+    window_title: &str,
+    url: Option<&str>,
+) -> Categorization {
+    let decided = match db.get_compiled_mappings().await {
+        Ok(compiled) => url
+            .and_then(|url| compiled.best_url(url))
+            .map(|mapping| (CategorizationSource::UrlMapping, mapping))
+            .or_else(|| {
+                Some(window_title)
+                    .filter(|title| !title.trim().is_empty())
+                    .and_then(|title| compiled.best_title(app_name, title))
+                    .map(|mapping| (CategorizationSource::TitleMapping, mapping))
+            })
+            .or_else(|| {
+                compiled
+                    .best_app(app_name, bundle_id)
+                    .map(|mapping| (CategorizationSource::AppMapping, mapping))
+            })
+            .map(|(source, mapping)| Categorization {
+                category: ActivityCategory::new(&mapping.category_id),
+                source,
+                mapping_id: Some(mapping.id),
+            }),
+        Err(e) => {
+            eprintln!("Mappings could not be loaded: {}", e);
+            None
+        }
+    };
+
+    decided.unwrap_or(Categorization {
+        category: ActivityCategory::unknown(),
+        source: CategorizationSource::Fallback,
+        mapping_id: None,
+    })
+}
+
+/// Categorize like `categorize_activity` and record which mapping decided it and why
+pub async fn explain_categorization(
+    db: &Database,
+    app_name: &str,
+    bundle_id: Option<&str>,
+    window_title: Option<&str>,
+    url: Option<&str>,
+) -> CategorizationExplanation {
+    let mut fallback_path = Vec::new();
+
+    // Compiled once and kept until a mapping changes, so a tick doesn't query SQLite
+    let compiled = match db.get_compiled_mappings().await {
+        Ok(compiled) => Some(compiled),
+        Err(e) => {
+            fallback_path.push(format!("Mappings could not be loaded: {}", e));
+            None
+        }
+    };
+
+    if let Some(compiled) = compiled {
+        // First, try URL-based categorization if URL is available
+        match url {
+            Some(url_str) => {
+                if let Some(explanation) = explain_match(
+                    CategorizationSource::UrlMapping,
+                    compiled.match_url(url_str),
+                    &mut fallback_path,
+                ) {
+                    return explanation;
                 }
+                fallback_path.push(format!("No URL mapping matched {}", url_str));
             }
+            None => fallback_path.push("No URL, URL mappings skipped".to_string()),
         }
-    }
 
-    // Try app-based categorization from database mappings
-    if let Ok(app_mappings) = db.get_app_mappings().await {
-        for mapping in app_mappings {
-            // Split pattern by "|" and check if any pattern matches
-            let patterns: Vec<&str> = mapping.app_pattern.split('|').collect();
-            let app_lower = app_name.to_lowercase();
-
-            for pattern in patterns {
-                if app_lower.contains(&pattern.trim().to_lowercase()) {
-                    // Convert category_id to ActivityCategory
-                    return match mapping.category_id.to_lowercase().as_str() {
-                        "development" => ActivityCategory::Development,
-                        "communication" => ActivityCategory::Communication,
-                        "social" => ActivityCategory::Social,
-                        "entertainment" => ActivityCategory::Entertainment,
-                        "productive" => ActivityCategory::Productive,
-                        _ => ActivityCategory::Custom(mapping.category_id),
-                    };
+        // Then window-title rules: for terminals, editors and chat apps the title says what
+        // the app is being used for
+        match window_title.filter(|title| !title.trim().is_empty()) {
+            Some(title) => {
+                if let Some(explanation) = explain_match(
+                    CategorizationSource::TitleMapping,
+                    compiled.match_title(app_name, title),
+                    &mut fallback_path,
+                ) {
+                    return explanation;
                 }
+                fallback_path.push(format!("No title rule matched '{}'", title));
             }
+            None => fallback_path.push("No window title, title rules skipped".to_string()),
         }
+
+        // Try app-based categorization from database mappings
+        if let Some(explanation) = explain_match(
+            CategorizationSource::AppMapping,
+            compiled.match_app(app_name, bundle_id),
+            &mut fallback_path,
+        ) {
+            return explanation;
+        }
+        fallback_path.push(match bundle_id {
+            Some(bundle_id) => format!(
+                "No app mapping matched '{}' or identifier '{}'",
+                app_name, bundle_id
+            ),
+            None => format!("No app mapping matched '{}'", app_name),
+        });
     }
 
     // No database mappings found, return Unknown
-    ActivityCategory::Unknown
+    fallback_path.push("Fell back to Unknown".to_string());
+    CategorizationExplanation {
+        category: ActivityCategory::unknown(),
+        source: CategorizationSource::Fallback,
+        matched_mapping: None,
+        precedence_rank: None,
+        overridden: Vec::new(),
+        fallback_path,
+    }
+}
+
+/// The first of `matching` (in precedence order, with its index) wins; the rest are overridden
+fn explain_match(
+    source: CategorizationSource,
+    matching: Vec<(usize, &MappingRef)>,
+    fallback_path: &mut Vec<String>,
+) -> Option<CategorizationExplanation> {
+    let mut matching = matching.into_iter();
+    let (rank, winner) = matching.next()?;
+
+    let stage = match source {
+        CategorizationSource::UrlMapping => "URL mapping",
+        CategorizationSource::TitleMapping => "Title rule",
+        _ => "App mapping",
+    };
+    fallback_path.push(format!(
+        "{} '{}' ({}) matched, category {}",
+        stage,
+        winner.pattern,
+        winner.match_kind.as_str(),
+        winner.category_id
+    ));
+
+    Some(CategorizationExplanation {
+        category: ActivityCategory::new(&winner.category_id),
+        source,
+        overridden: matching
+            .map(|(_, loser)| mappings::explain_win(winner, loser))
+            .collect(),
+        matched_mapping: Some(winner.clone()),
+        precedence_rank: Some(rank + 1),
+        fallback_path: std::mem::take(fallback_path),
+    })
+}
+
+/// Reapply the current mappings to activities overlapping `[start, end)`.
+///
+/// Activities the user recategorized by hand are skipped. The changes are recorded as one
+/// run that `Database::undo_recategorization` can revert.
+pub async fn reapply_mappings(
+    db: &Database,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Result<RecategorizationReport, sqlx::Error> {
+    let mut report = RecategorizationReport {
+        run_id: None,
+        start_time: start,
+        end_time: end,
+        changed: 0,
+        unchanged: 0,
+        skipped_manual: 0,
+        transitions: Vec::new(),
+    };
+
+    let mut changes = Vec::new();
+    for activity in db.get_activities_for_recategorization(start, end).await? {
+        if activity.provenance.override_category.is_some() {
+            report.skipped_manual += 1;
+            continue;
+        }
+
+        let computed = explain_categorization(
+            db,
+            &activity.app_name,
+            activity.app_bundle_id.as_deref(),
+            Some(&activity.window_title),
+            activity.url.as_deref(),
+        )
+        .await;
+        if computed.category == activity.category {
+            report.unchanged += 1;
+            continue;
+        }
+
+        match report
+            .transitions
+            .iter_mut()
+            .find(|t| t.from == activity.category && t.to == computed.category)
+        {
+            Some(transition) => transition.activities += 1,
+            None => report.transitions.push(CategoryTransition {
+                from: activity.category.clone(),
+                to: computed.category.clone(),
+                activities: 1,
+            }),
+        }
+        changes.push((activity, computed));
+    }
+
+    report.changed = changes.len();
+    report
+        .transitions
+        .sort_by(|a, b| b.activities.cmp(&a.activities));
+    if !changes.is_empty() {
+        report.run_id = Some(db.record_recategorization(start, end, &changes).await?);
+    }
+
+    Ok(report)
+}
+
+/// What a single tracking tick did with the observed window
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TickOutcome {
+    /// Tracking is disabled/paused or the user is inactive
+    Skipped,
+    /// Nothing was ongoing, a new activity was started
+    Started,
+    /// The app or its category changed, the ongoing activity was ended and a new one started
+    Switched,
+    /// The ongoing activity is still in front
+    Continued,
+    /// Same activity, but the tab, file or window inside it changed and a new segment started
+    SegmentChanged,
+    /// No window was observed, the ongoing activity was ended
+    Ended,
+    /// No window observed and nothing ongoing
+    Idle,
+    /// The user went idle, the ongoing activity was ended when input stopped
+    WentIdle,
+}
+
+/// Read the foreground window through the tracker's `WindowSource`
+pub fn poll_current_activity(state: &AppState) -> Option<CurrentActivity> {
+    let mut tracker = state.tracker.lock().unwrap();
+    tracker.get_current_activity()
+}
+
+/// Apply one observation to the activity log, honouring the tracking toggle and idle state.
+///
+/// This is everything the background loop does after the focus mode check, so tests can
+/// drive it with a scripted `WindowSource` and a fixed clock.
+pub async fn track_activity(
+    state: &AppState,
+    current_activity: Option<CurrentActivity>,
+    now: DateTime<Utc>,
+) -> TickOutcome {
+    // Check if tracking is enabled
+    let is_tracking = {
+        let tracking_guard = state.is_tracking.lock().unwrap();
+        *tracking_guard
+    };
+    println!("🔍 Is tracking enabled: {}", is_tracking);
+
+    if !is_tracking {
+        println!("Tracking is disabled, skipping activity tracking...");
+        return TickOutcome::Skipped;
+    }
+
+    // Check if user is active (as of the idle check done while polling the window)
+    let (should_track, idle_duration) = {
+        let tracker = state.tracker.lock().unwrap();
+        (tracker.is_user_active(), tracker.idle_duration())
+    };
+
+    if !should_track {
+        println!("User is inactive, skipping...");
+        let idle = idle_duration.unwrap_or_default();
+        return match end_activity_at_idle_start(&state.db, now, idle).await {
+            Ok(true) => TickOutcome::WentIdle,
+            Ok(false) => TickOutcome::Skipped,
+            Err(e) => {
+                eprintln!("Failed to end activity after going idle: {}", e);
+                TickOutcome::Skipped
+            }
+        };
+    }
+
+    match record_activity(&state.db, current_activity.as_ref(), now).await {
+        Ok(outcome) => outcome,
+        Err(e) => {
+            eprintln!("Failed to record activity: {}", e);
+            TickOutcome::Skipped
+        }
+    }
+}
+
+/// End the ongoing activity at the moment the user stopped giving input, not when the
+/// idle threshold was crossed. Returns whether an activity was ended.
+pub async fn end_activity_at_idle_start(
+    db: &Database,
+    now: DateTime<Utc>,
+    idle: std::time::Duration,
+) -> Result<bool, sqlx::Error> {
+    let Some(ongoing_activity) = db.get_current_activity().await? else {
+        return Ok(false);
+    };
+
+    let idle = chrono::Duration::from_std(idle).unwrap_or_default();
+    // Never end before the activity started (e.g. the app was switched to while idle)
+    let idle_start = (now - idle).max(ongoing_activity.start_time);
+    db.end_current_activity(idle_start).await?;
+    println!("Activity ended at idle start: {}", idle_start);
+
+    Ok(true)
+}
+
+/// Start, continue, switch or end activities in the database for the observed window
+pub async fn record_activity(
+    db: &Database,
+    current_activity: Option<&CurrentActivity>,
+    now: DateTime<Utc>,
+) -> Result<TickOutcome, sqlx::Error> {
+    let ongoing_activity = db.get_current_activity().await?;
+
+    let Some(current) = current_activity else {
+        // No current activity detected, end any ongoing activity
+        if ongoing_activity.is_some() {
+            println!("Ending ongoing activity due to inactivity");
+            db.end_current_activity(now).await?;
+            return Ok(TickOutcome::Ended);
+        }
+        println!("No ongoing activity to end");
+        return Ok(TickOutcome::Idle);
+    };
+
+    let computed = categorize(
+        db,
+        &current.app_name,
+        current.app_bundle_id.as_deref(),
+        &current.window_title,
+        current.url.as_deref(),
+    )
+    .await;
+    let category = computed.category;
+
+    let outcome = match ongoing_activity {
+        Some(ongoing_activity) => {
+            // Title and URL changes inside the same app are tracked as segments, so only
+            // a different app or a different category starts a new activity. The computed
+            // category is compared, so a user override doesn't end the activity.
+            let ongoing_category = ongoing_activity
+                .provenance
+                .computed_category
+                .as_ref()
+                .unwrap_or(&ongoing_activity.category);
+            let is_same_activity =
+                ongoing_activity.app_name == current.app_name && *ongoing_category == category;
+
+            if is_same_activity {
+                let opened = record_segment(db, ongoing_activity.id, current, now).await?;
+                if opened {
+                    println!("Same activity continuing in a new segment");
+                    db.update_activity_window(
+                        &ongoing_activity.id,
+                        &current.window_title,
+                        current.url.as_deref(),
+                        computed.source,
+                        computed.mapping_id,
+                    )
+                    .await?;
+                    return Ok(TickOutcome::SegmentChanged);
+                }
+                println!("Same activity continuing, no action needed");
+                return Ok(TickOutcome::Continued);
+            }
+
+            println!("Activity changed! Ending previous activity and starting new one");
+            db.end_current_activity(now).await?;
+            TickOutcome::Switched
+        }
+        None => {
+            println!("No ongoing activity, starting new one");
+            TickOutcome::Started
+        }
+    };
+
+    let new_entry = ActivityEntry {
+        id: Uuid::new_v4(),
+        start_time: now,
+        end_time: None,
+        app_name: current.app_name.clone(),
+        app_bundle_id: current.app_bundle_id.clone(),
+        window_title: current.window_title.clone(),
+        url: current.url.clone(),
+        category,
+        provenance: CategoryProvenance {
+            computed_source: Some(computed.source),
+            mapping_id: computed.mapping_id,
+            ..Default::default()
+        },
+        segments: vec![],
+    };
+
+    db.start_activity(&new_entry).await?;
+    record_segment(db, new_entry.id, current, now).await?;
+    println!(
+        "New activity started: {} - {}",
+        new_entry.app_name, new_entry.window_title
+    );
+
+    Ok(outcome)
+}
+
+/// Segment details for the observed window.
+///
+/// Backends that don't understand the app leave `segment_info` empty; the window itself
+/// is then the segment.
+fn segment_info_for(current: &CurrentActivity) -> SegmentInfo {
+    current.segment_info.clone().unwrap_or_else(|| SegmentInfo {
+        segment_type: SegmentType::AppWindow,
+        title: current.window_title.clone(),
+        url: current.url.clone(),
+        file_path: None,
+        metadata: None,
+    })
+}
+
+/// Keep the open segment of `activity_id` if it still matches the observed window,
+/// otherwise close it and open a new one. Returns whether a segment was opened.
+async fn record_segment(
+    db: &Database,
+    activity_id: Uuid,
+    current: &CurrentActivity,
+    now: DateTime<Utc>,
+) -> Result<bool, sqlx::Error> {
+    let info = segment_info_for(current);
+
+    if let Some(open_segment) = db.get_current_segment().await? {
+        let is_same_segment = open_segment.activity_id == activity_id
+            && open_segment.segment_type == info.segment_type
+            && open_segment.title == info.title
+            && open_segment.url == info.url
+            && open_segment.file_path == info.file_path;
+
+        if is_same_segment {
+            return Ok(false);
+        }
+        db.end_current_segment(now).await?;
+    }
+
+    let segment = ActivitySegment {
+        id: Uuid::new_v4(),
+        activity_id,
+        start_time: now,
+        end_time: None,
+        segment_type: info.segment_type,
+        title: info.title,
+        url: info.url,
+        file_path: info.file_path,
+        metadata: info.metadata,
+    };
+    db.start_segment(&segment).await?;
+    println!(
+        "New segment started: {:?} - {}",
+        segment.segment_type, segment.title
+    );
+
+    Ok(true)
 }
 
 /// Main activity tracking loop that runs continuously in the background
@@ -132,10 +571,7 @@ pub async fn start_activity_tracking(app_handle: AppHandle) {
         }
 
         // Get current activity (needed for both tracking and focus mode)
-        let current_activity = {
-            let mut tracker = state.tracker.lock().unwrap();
-            tracker.get_current_activity()
-        };
+        let current_activity = poll_current_activity(&state);
         println!("Raw current_activity result: {:?}", current_activity);
 
         if let Some(current) = &current_activity {
@@ -186,140 +622,7 @@ pub async fn start_activity_tracking(app_handle: AppHandle) {
         }
 
         // Continue with normal activity tracking logic (after focus mode check)
-        // Check if tracking is enabled
-        let is_tracking = {
-            let tracking_guard = state.is_tracking.lock().unwrap();
-            *tracking_guard
-        };
-        println!("🔍 Is tracking enabled: {}", is_tracking);
-
-        if !is_tracking {
-            println!("Tracking is disabled, skipping activity tracking...");
-            continue;
-        }
-
-        // Check if user is active
-        let should_track = {
-            let mut tracker = state.tracker.lock().unwrap();
-            tracker.should_track()
-        };
-
-        if !should_track {
-            println!("User is inactive, skipping...");
-            continue;
-        }
-
-        if let Some(current) = current_activity {
-            // Check if there's already an ongoing activity in the database
-            match state.db.get_current_activity().await {
-                Ok(Some(ongoing_activity)) => {
-                    // There's an ongoing activity, check if it's the same
-                    let is_same_activity = ongoing_activity.app_name == current.app_name
-                        && ongoing_activity.window_title == current.window_title
-                        && ongoing_activity.url == current.url;
-
-                    if !is_same_activity {
-                        println!("Activity changed! Ending previous activity and starting new one");
-
-                        // End the current activity
-                        let now = Utc::now();
-                        if let Err(e) = state.db.end_current_activity(now).await {
-                            eprintln!("Failed to end current activity: {}", e);
-                        } else {
-                            println!("Previous activity ended successfully");
-                        }
-
-                        // Start new activity
-                        let category = categorize_activity(
-                            &state.db,
-                            &current.app_name,
-                            current.app_bundle_id.as_deref(),
-                            current.url.as_deref(),
-                        )
-                        .await;
-
-                        let new_entry = ActivityEntry {
-                            id: Uuid::new_v4(),
-                            start_time: now,
-                            end_time: None,
-                            app_name: current.app_name.clone(),
-                            app_bundle_id: current.app_bundle_id.clone(),
-                            window_title: current.window_title.clone(),
-                            url: current.url.clone(),
-                            category,
-                            segments: vec![], // TODO: Extract and store segments
-                        };
-
-                        if let Err(e) = state.db.start_activity(&new_entry).await {
-                            eprintln!("Failed to start new activity: {}", e);
-                        } else {
-                            println!(
-                                "New activity started: {} - {}",
-                                new_entry.app_name, new_entry.window_title
-                            );
-                        }
-                    } else {
-                        println!("Same activity continuing, no action needed");
-                    }
-                }
-                Ok(None) => {
-                    // No ongoing activity, start a new one
-                    println!("No ongoing activity, starting new one");
-
-                    let category = categorize_activity(
-                        &state.db,
-                        &current.app_name,
-                        current.app_bundle_id.as_deref(),
-                        current.url.as_deref(),
-                    )
-                    .await;
-
-                    let new_entry = ActivityEntry {
-                        id: Uuid::new_v4(),
-                        start_time: Utc::now(),
-                        end_time: None,
-                        app_name: current.app_name.clone(),
-                        app_bundle_id: current.app_bundle_id.clone(),
-                        window_title: current.window_title.clone(),
-                        url: current.url.clone(),
-                        category,
-                        segments: vec![], // TODO: Extract and store segments
-                    };
-
-                    if let Err(e) = state.db.start_activity(&new_entry).await {
-                        eprintln!("Failed to start new activity: {}", e);
-                    } else {
-                        println!(
-                            "New activity started: {} - {}",
-                            new_entry.app_name, new_entry.window_title
-                        );
-                    }
-                }
-                Err(e) => {
-                    eprintln!("Failed to get current activity from database: {}", e);
-                }
-            }
-        } else {
-            println!("No current activity detected, checking if we need to end ongoing activity");
-
-            // No current activity detected, end any ongoing activity
-            match state.db.get_current_activity().await {
-                Ok(Some(_ongoing_activity)) => {
-                    println!("Ending ongoing activity due to inactivity");
-                    let now = Utc::now();
-                    if let Err(e) = state.db.end_current_activity(now).await {
-                        eprintln!("Failed to end activity due to inactivity: {}", e);
-                    } else {
-                        println!("Activity ended due to inactivity");
-                    }
-                }
-                Ok(None) => {
-                    println!("No ongoing activity to end");
-                }
-                Err(e) => {
-                    eprintln!("Failed to check for ongoing activity: {}", e);
-                }
-            }
-        }
+        let outcome = track_activity(&state, current_activity, Utc::now()).await;
+        println!("Tracking tick outcome: {:?}", outcome);
     }
 }
@@ -160,6 +160,14 @@ impl CacheManager {
         Ok(())
     }
 
+    /// Drop the mappings compiled for categorization so the next tick rebuilds them
+    pub fn clear_compiled_mappings_cache(&self) {
+        let state: tauri::State<AppState> = self.app_handle.state();
+
+        state.db.invalidate_compiled_mappings();
+        println!("🗑️ Cleared compiled mappings cache");
+    }
+
     /// Update focus mode enabled cache
     pub fn update_focus_mode_enabled_cache(&self, enabled: bool) -> Result<(), String> {
         let state: tauri::State<AppState> = self.app_handle.state();
@@ -237,6 +245,11 @@ async fn handle_cache_invalidation(app_handle: AppHandle, payload: &str) -> Resu
             // Clear app mappings cache since category changes affect app blocking
             cache_manager.clear_app_mappings_cache()?;
         }
+        "mappings_changed" => {
+            println!("🔄 Cache invalidation: mappings changed");
+            cache_manager.clear_app_mappings_cache()?;
+            cache_manager.clear_compiled_mappings_cache();
+        }
         "allowed_apps_changed" => {
             println!("🔄 Cache invalidation: allowed apps changed");
             println!("📄 Event data: {}", event_data);
@@ -1,10 +1,18 @@
-use chrono::NaiveDate;
+use chrono::{DateTime, NaiveDate, Utc};
 use tauri::{AppHandle, Emitter, State};
+use uuid::Uuid;
 
-use crate::models::{ActivityEntry, ActivitySummary, TimelineData};
+use crate::models::{
+    ActivityEntry, ActivityReport, ActivitySummary, ProductivityTrend, RecategorizationReport,
+    RecategorizationRun, ReportPeriod, TimelineData, UncategorizedReport,
+};
+use crate::timezone::{date_range_bounds, day_bounds};
 use crate::tracker::CurrentActivity;
 use crate::AppState;
 
+const DEFAULT_TREND_DAYS: u32 = 7;
+const MAX_TREND_DAYS: u32 = 366;
+
 #[tauri::command]
 pub async fn get_activities_by_date(
     state: State<'_, AppState>,
@@ -13,9 +21,10 @@ pub async fn get_activities_by_date(
     let parsed_date = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
         .map_err(|e| format!("Invalid date format: {}", e))?;
 
+    let tz = state.db.get_timezone().await.map_err(|e| e.to_string())?;
     state
         .db
-        .get_activities_by_date(parsed_date)
+        .get_activities_by_date(parsed_date, &tz)
         .await
         .map_err(|e| e.to_string())
 }
@@ -28,9 +37,65 @@ pub async fn get_activity_summary(
     let parsed_date = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
         .map_err(|e| format!("Invalid date format: {}", e))?;
 
+    let tz = state.db.get_timezone().await.map_err(|e| e.to_string())?;
+    state
+        .db
+        .get_activity_summary(parsed_date, &tz)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Daily productivity over the `days` days (7 by default) ending on `date`, and whether
+/// `date` was a good day compared to the ones before it
+#[tauri::command]
+pub async fn get_productivity_trend(
+    state: State<'_, AppState>,
+    date: String,
+    days: Option<u32>,
+) -> Result<ProductivityTrend, String> {
+    let last = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+        .map_err(|e| format!("Invalid date format: {}", e))?;
+    let days = days.unwrap_or(DEFAULT_TREND_DAYS).clamp(1, MAX_TREND_DAYS);
+    let first = last - chrono::Duration::days(i64::from(days) - 1);
+
+    let tz = state.db.get_timezone().await.map_err(|e| e.to_string())?;
+    state
+        .db
+        .get_productivity_trend(first, last, &tz)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Monday-to-Sunday report for the week containing `date`
+#[tauri::command]
+pub async fn get_weekly_report(
+    state: State<'_, AppState>,
+    date: String,
+) -> Result<ActivityReport, String> {
+    get_activity_report(&state, ReportPeriod::Week, &date).await
+}
+
+/// Report for the calendar month containing `date`
+#[tauri::command]
+pub async fn get_monthly_report(
+    state: State<'_, AppState>,
+    date: String,
+) -> Result<ActivityReport, String> {
+    get_activity_report(&state, ReportPeriod::Month, &date).await
+}
+
+async fn get_activity_report(
+    state: &AppState,
+    period: ReportPeriod,
+    date: &str,
+) -> Result<ActivityReport, String> {
+    let parsed_date = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|e| format!("Invalid date format: {}", e))?;
+
+    let tz = state.db.get_timezone().await.map_err(|e| e.to_string())?;
     state
         .db
-        .get_activity_summary(parsed_date)
+        .get_activity_report(period, parsed_date, &tz)
         .await
         .map_err(|e| e.to_string())
 }
@@ -40,14 +105,39 @@ pub async fn get_timeline_data(
     state: State<'_, AppState>,
     date: String,
 ) -> Result<TimelineData, String> {
-    // For now, we'll get recent timeline for the last 24 hours
-    // TODO: Use the date parameter to filter timeline data
-    let _parsed_date = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+    let parsed_date = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
         .map_err(|e| format!("Invalid date format: {}", e))?;
 
+    let tz = state.db.get_timezone().await.map_err(|e| e.to_string())?;
+    let (start, end) = day_bounds(parsed_date, &tz);
+    state
+        .db
+        .get_timeline(start, end)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Timeline for an arbitrary `[start, end)` range given as RFC 3339 timestamps
+#[tauri::command]
+pub async fn get_timeline_range(
+    state: State<'_, AppState>,
+    start: String,
+    end: String,
+) -> Result<TimelineData, String> {
+    let start = DateTime::parse_from_rfc3339(&start)
+        .map_err(|e| format!("Invalid start time: {}", e))?
+        .with_timezone(&Utc);
+    let end = DateTime::parse_from_rfc3339(&end)
+        .map_err(|e| format!("Invalid end time: {}", e))?
+        .with_timezone(&Utc);
+
+    if start >= end {
+        return Err("Start time must be before end time".to_string());
+    }
+
     state
         .db
-        .get_recent_timeline(1440) // 24 hours in minutes
+        .get_timeline(start, end)
         .await
         .map_err(|e| e.to_string())
 }
@@ -90,9 +180,10 @@ pub async fn get_activities_by_date_range(
     let end_parsed = NaiveDate::parse_from_str(&end_date, "%Y-%m-%d")
         .map_err(|e| format!("Invalid end date format: {}", e))?;
 
+    let tz = state.db.get_timezone().await.map_err(|e| e.to_string())?;
     state
         .db
-        .get_activities_by_date_range(start_parsed, end_parsed)
+        .get_activities_by_date_range(start_parsed, end_parsed, &tz)
         .await
         .map_err(|e| e.to_string())
 }
@@ -103,16 +194,13 @@ pub async fn update_activity_category(
     activity_id: String,
     category_id: String,
 ) -> Result<(), String> {
-    // Convert category_id to ActivityCategory
-    let category = match category_id.as_str() {
-        "productive" => crate::models::ActivityCategory::Productive,
-        "social" => crate::models::ActivityCategory::Social,
-        "entertainment" => crate::models::ActivityCategory::Entertainment,
-        "development" => crate::models::ActivityCategory::Development,
-        "communication" => crate::models::ActivityCategory::Communication,
-        "unknown" => crate::models::ActivityCategory::Unknown,
-        custom_id => crate::models::ActivityCategory::Custom(custom_id.to_string()),
-    };
+    state
+        .db
+        .get_user_category_by_id(&category_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Category '{}' not found", category_id))?;
+    let category = crate::models::ActivityCategory::new(category_id);
 
     state
         .db
@@ -120,3 +208,93 @@ pub async fn update_activity_category(
         .await
         .map_err(|e| e.to_string())
 }
+
+/// Drop the user's category for an activity so the one computed from mappings applies again
+#[tauri::command]
+pub async fn reset_activity_category(
+    state: State<'_, AppState>,
+    activity_id: String,
+) -> Result<(), String> {
+    state
+        .db
+        .clear_activity_category_override(&activity_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Reapply the current mappings to the local days `start_date..=end_date`, skipping
+/// activities that were recategorized by hand
+#[tauri::command]
+pub async fn reapply_mappings(
+    state: State<'_, AppState>,
+    start_date: String,
+    end_date: String,
+) -> Result<RecategorizationReport, String> {
+    let (start, end) = local_period(&state, &start_date, &end_date).await?;
+    crate::activity::reapply_mappings(&state.db, start, end)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Apps, domains and window titles that ended up Unknown on the local days
+/// `start_date..=end_date`, with the most time first
+#[tauri::command]
+pub async fn get_uncategorized_activity(
+    state: State<'_, AppState>,
+    start_date: String,
+    end_date: String,
+    limit: Option<usize>,
+) -> Result<UncategorizedReport, String> {
+    let (start, end) = local_period(&state, &start_date, &end_date).await?;
+    state
+        .db
+        .get_uncategorized_activity(start, end, limit.unwrap_or(20))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// `[start, end)` in UTC for the local days `start_date..=end_date` (YYYY-MM-DD)
+pub(crate) async fn local_period(
+    state: &AppState,
+    start_date: &str,
+    end_date: &str,
+) -> Result<(DateTime<Utc>, DateTime<Utc>), String> {
+    let start_parsed = NaiveDate::parse_from_str(start_date, "%Y-%m-%d")
+        .map_err(|e| format!("Invalid start date format: {}", e))?;
+    let end_parsed = NaiveDate::parse_from_str(end_date, "%Y-%m-%d")
+        .map_err(|e| format!("Invalid end date format: {}", e))?;
+    if start_parsed > end_parsed {
+        return Err("Start date must not be after end date".to_string());
+    }
+
+    let tz = state.db.get_timezone().await.map_err(|e| e.to_string())?;
+    Ok(date_range_bounds(start_parsed, end_parsed, &tz))
+}
+
+/// Undo a `reapply_mappings` run, returning how many activities were restored
+#[tauri::command]
+pub async fn undo_recategorization(
+    state: State<'_, AppState>,
+    run_id: String,
+) -> Result<u64, String> {
+    let run_id = Uuid::parse_str(&run_id).map_err(|e| format!("Invalid run ID: {}", e))?;
+    state
+        .db
+        .undo_recategorization(&run_id)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => "Recategorization not found or already undone".to_string(),
+            e => e.to_string(),
+        })
+}
+
+#[tauri::command]
+pub async fn get_recategorization_runs(
+    state: State<'_, AppState>,
+) -> Result<Vec<RecategorizationRun>, String> {
+    state
+        .db
+        .get_recategorization_runs()
+        .await
+        .map_err(|e| e.to_string())
+}
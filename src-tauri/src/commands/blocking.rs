@@ -1,5 +1,6 @@
 use tauri::{AppHandle, Emitter, State};
 
+use crate::models::MatchKind;
 use crate::AppState;
 
 #[tauri::command]
@@ -288,9 +289,11 @@ async fn start_website_blocking_internal(
     }
 
     // Filter for distracting categories (social and entertainment)
+    // The proxy blocks by domain, so glob and regex mappings can't be handed to it
     let mut urls_to_block: Vec<String> = url_mappings
         .into_iter()
         .filter(|mapping| mapping.category_id == "social" || mapping.category_id == "entertainment")
+        .filter(|mapping| !matches!(mapping.match_kind, MatchKind::Glob | MatchKind::Regex))
         .map(|mapping| mapping.url_pattern)
         .collect();
 
@@ -352,4 +355,4 @@ async fn stop_website_blocking_internal(state: &State<'_, AppState>) -> Result<(
     } else {
         Err("Website blocker not initialized at startup".to_string())
     }
-}
\ No newline at end of file
+}
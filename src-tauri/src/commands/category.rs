@@ -1,8 +1,9 @@
 use chrono::Utc;
-use tauri::State;
+use tauri::{AppHandle, State};
 use uuid::Uuid;
 
-use crate::models::UserCategory;
+use super::focus_mode::notify_allowed_categories_changed;
+use crate::models::{CategoryDeletionReport, Productivity, UserCategory};
 use crate::AppState;
 
 #[tauri::command]
@@ -25,16 +26,28 @@ pub async fn load_categories(state: State<'_, AppState>) -> Result<Vec<UserCateg
 
 #[tauri::command]
 pub async fn add_category(
+    app_handle: AppHandle,
     state: State<'_, AppState>,
     name: String,
     color: String,
+    parent_id: Option<String>,
+    productivity: Option<Productivity>,
 ) -> Result<UserCategory, String> {
+    let parent = match &parent_id {
+        Some(parent_id) => Some(check_parent(&state, None, parent_id).await?),
+        None => None,
+    };
+
     let now = Utc::now();
     let category = UserCategory {
         id: Uuid::new_v4().to_string(),
         name,
         color,
-        parent_id: None,
+        parent_id,
+        // A subcategory counts like its parent unless told otherwise
+        productivity: productivity
+            .or(parent.map(|parent| parent.productivity))
+            .unwrap_or_default(),
         created_at: now,
         updated_at: now,
     };
@@ -45,6 +58,11 @@ pub async fn add_category(
         .await
         .map_err(|e| e.to_string())?;
 
+    // A subcategory of an allowed category is allowed in focus mode too
+    if category.parent_id.is_some() {
+        notify_allowed_categories_changed(&state, &app_handle).await?;
+    }
+
     Ok(category)
 }
 
@@ -54,15 +72,15 @@ pub async fn update_category(
     id: String,
     name: String,
     color: String,
+    productivity: Option<Productivity>,
 ) -> Result<(), String> {
-    let category = UserCategory {
-        id: id.clone(),
-        name,
-        color,
-        parent_id: None,
-        created_at: Utc::now(),
-        updated_at: Utc::now(),
-    };
+    let mut category = get_category(&state, &id).await?;
+    category.name = name;
+    category.color = color;
+    if let Some(productivity) = productivity {
+        category.productivity = productivity;
+    }
+    category.updated_at = Utc::now();
 
     state
         .db
@@ -71,21 +89,112 @@ pub async fn update_category(
         .map_err(|e| e.to_string())
 }
 
+/// Make a category a subcategory of `parent_id`, or a top-level category with None
 #[tauri::command]
-pub async fn delete_category(state: State<'_, AppState>, id: String) -> Result<(), String> {
-    // Check if this is the "Unknown" category
-    if let Ok(Some(category)) = state.db.get_user_category_by_id(&id).await {
-        if category.name.to_lowercase() == "unknown" {
-            return Err(
-                "Cannot delete the 'Unknown' category as it is required for the system."
-                    .to_string(),
-            );
-        }
+pub async fn move_category(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+    parent_id: Option<String>,
+) -> Result<UserCategory, String> {
+    let mut category = get_category(&state, &id).await?;
+    if let Some(parent_id) = &parent_id {
+        check_parent(&state, Some(&id), parent_id).await?;
     }
+    category.parent_id = parent_id;
+    category.updated_at = Utc::now();
 
     state
         .db
-        .delete_user_category(&id)
+        .update_user_category(&category)
         .await
-        .map_err(|e| format!("Failed to delete category: {}", e))
-}
\ No newline at end of file
+        .map_err(|e| e.to_string())?;
+
+    notify_allowed_categories_changed(&state, &app_handle).await?;
+
+    Ok(category)
+}
+
+async fn get_category(state: &AppState, id: &str) -> Result<UserCategory, String> {
+    state
+        .db
+        .get_user_category_by_id(id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Category '{}' not found", id))
+}
+
+/// The parent must exist and can't be the category itself or one of its subcategories
+async fn check_parent(
+    state: &AppState,
+    id: Option<&str>,
+    parent_id: &str,
+) -> Result<UserCategory, String> {
+    let parent = get_category(state, parent_id).await?;
+
+    if let Some(id) = id {
+        let descendants = state
+            .db
+            .get_category_descendants(id)
+            .await
+            .map_err(|e| e.to_string())?;
+        if parent.id == id {
+            return Err("A category can't be its own parent".to_string());
+        }
+        if descendants.contains(&parent.id) {
+            return Err(format!(
+                "'{}' is inside this category and can't be its parent",
+                parent.name
+            ));
+        }
+    }
+
+    Ok(parent)
+}
+
+/// Delete a category, reassigning its mappings, activities, focus mode setting and
+/// subcategories to `target_id` (Unknown by default). With `dry_run` nothing changes and
+/// the report only counts what would be reassigned.
+#[tauri::command]
+pub async fn delete_category(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+    target_id: Option<String>,
+    dry_run: Option<bool>,
+) -> Result<CategoryDeletionReport, String> {
+    let category = get_category(&state, &id).await?;
+    // Check if this is the "Unknown" category
+    if category.name.to_lowercase() == "unknown" {
+        return Err(
+            "Cannot delete the 'Unknown' category as it is required for the system.".to_string(),
+        );
+    }
+
+    let target = get_category(&state, target_id.as_deref().unwrap_or("unknown")).await?;
+    let descendants = state
+        .db
+        .get_category_descendants(&id)
+        .await
+        .map_err(|e| e.to_string())?;
+    if target.id == id || descendants.contains(&target.id) {
+        return Err(format!(
+            "Can't move '{}' into '{}', which is being deleted",
+            category.name, target.name
+        ));
+    }
+
+    let dry_run = dry_run.unwrap_or(false);
+    let report = state
+        .db
+        .delete_user_category(&id, &target.id, dry_run)
+        .await
+        .map_err(|e| format!("Failed to delete category: {}", e))?;
+
+    if !dry_run {
+        super::mapping::notify_mappings_changed(&app_handle)?;
+        notify_allowed_categories_changed(&state, &app_handle).await?;
+    }
+
+    Ok(report)
+}
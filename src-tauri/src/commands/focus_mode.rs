@@ -1,6 +1,8 @@
 use tauri::{AppHandle, Emitter, State};
 use tokio::time::Instant;
 
+use crate::models::MatchKind;
+use crate::pattern;
 use crate::tray::TrayManager;
 use crate::AppState;
 
@@ -196,6 +198,16 @@ pub async fn set_focus_mode_categories(
         .await
         .map_err(|e| e.to_string())?;
 
+    notify_allowed_categories_changed(&state, &app_handle).await
+}
+
+/// Refresh the cached allowed categories, which include subcategories of the chosen ones
+pub(crate) async fn notify_allowed_categories_changed(
+    state: &AppState,
+    app_handle: &AppHandle,
+) -> Result<(), String> {
+    let categories = effective_allowed_categories(state).await?;
+
     // Emit cache invalidation event instead of updating cache directly
     app_handle
         .emit(
@@ -210,6 +222,27 @@ pub async fn set_focus_mode_categories(
     Ok(())
 }
 
+/// The categories focus mode allows right now with their subcategories: those of the active
+/// focus schedule if it names any, otherwise the user's focus mode categories
+pub(crate) async fn effective_allowed_categories(state: &AppState) -> Result<Vec<String>, String> {
+    let scheduled = {
+        let schedule = state
+            .active_focus_schedule
+            .lock()
+            .map_err(|e| e.to_string())?;
+        schedule
+            .as_ref()
+            .map(|schedule| schedule.allowed_categories.clone())
+            .filter(|categories| !categories.is_empty())
+    };
+
+    match scheduled {
+        Some(categories) => state.db.expand_category_ids(&categories).await,
+        None => state.db.get_focus_mode_effective_categories().await,
+    }
+    .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn get_focus_mode_categories(state: State<'_, AppState>) -> Result<Vec<String>, String> {
     // Get from database (authoritative source)
@@ -240,7 +273,7 @@ pub async fn check_app_focus_allowed(
     if app_name.to_lowercase().contains("velosi")
         || bundle_id
             .as_ref()
-            .map_or(false, |bid| bid.to_lowercase().contains("velosi"))
+            .is_some_and(|bid| bid.to_lowercase().contains("velosi"))
     {
         return Ok(true); // Velosi app is always allowed
     }
@@ -278,18 +311,9 @@ pub async fn check_app_focus_allowed(
 
     // Find the category for this app
     for mapping in app_mappings {
-        let patterns: Vec<&str> = mapping.app_pattern.split('|').collect();
-        for pattern in patterns {
-            if app_name
-                .to_lowercase()
-                .contains(&pattern.trim().to_lowercase())
-                || bundle_id.as_ref().map_or(false, |bid| {
-                    bid.to_lowercase().contains(&pattern.trim().to_lowercase())
-                })
-            {
-                // App matches this category
-                return Ok(allowed_categories.contains(&mapping.category_id));
-            }
+        if pattern::app_mapping_matches(&mapping, &app_name, bundle_id.as_deref()) {
+            // App matches this category
+            return Ok(allowed_categories.contains(&mapping.category_id));
         }
     }
 
@@ -309,11 +333,8 @@ pub async fn allow_app(
         app_name, duration_minutes
     );
 
-    let expires_at = if let Some(duration) = duration_minutes {
-        Some(chrono::Utc::now().timestamp() + (duration as i64 * 60))
-    } else {
-        None // Allow indefinitely
-    };
+    let expires_at =
+        duration_minutes.map(|duration| chrono::Utc::now().timestamp() + (duration as i64 * 60));
 
     println!("📅 Expires at timestamp: {:?}", expires_at);
 
@@ -548,7 +569,7 @@ pub async fn show_focus_overlay(
                 &window_label,
                 tauri::WebviewUrl::App(url.clone().into()),
             )
-            .title(&format!("Focus Mode - App Blocked (Monitor {})", index + 1))
+            .title(format!("Focus Mode - App Blocked (Monitor {})", index + 1))
             .position(monitor.position().x as f64, monitor.position().y as f64)
             .inner_size(monitor.size().width as f64, monitor.size().height as f64)
             .always_on_top(true)
@@ -720,9 +741,11 @@ async fn start_website_blocking_internal(
     }
 
     // Filter for distracting categories (social and entertainment)
+    // The proxy blocks by domain, so glob and regex mappings can't be handed to it
     let mut urls_to_block: Vec<String> = url_mappings
         .into_iter()
         .filter(|mapping| mapping.category_id == "social" || mapping.category_id == "entertainment")
+        .filter(|mapping| !matches!(mapping.match_kind, MatchKind::Glob | MatchKind::Regex))
         .map(|mapping| mapping.url_pattern)
         .collect();
 
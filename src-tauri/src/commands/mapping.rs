@@ -1,7 +1,13 @@
 use serde_json::Value;
-use tauri::State;
+use tauri::{AppHandle, Emitter, State};
 use uuid::Uuid;
 
+use crate::mappings::DEFAULT_SUGGESTION_THRESHOLD;
+use crate::models::{
+    AppMapping, CategorizationExplanation, MappingConflict, MappingRef, MappingSuggestion,
+    MappingTarget, MatchKind, TitleMapping, TriageResult, UncategorizedItem,
+};
+use crate::pattern;
 use crate::AppState;
 
 #[tauri::command]
@@ -31,7 +37,7 @@ pub async fn get_app_mappings(state: State<'_, AppState>) -> Result<Value, Strin
     for mapping in mappings {
         category_mappings
             .entry(mapping.category_id)
-            .or_insert_with(Vec::new)
+            .or_default()
             .push(mapping.app_pattern);
     }
 
@@ -82,7 +88,7 @@ pub async fn get_url_mappings(state: State<'_, AppState>) -> Result<Value, Strin
     for mapping in mappings {
         category_mappings
             .entry(mapping.category_id)
-            .or_insert_with(Vec::new)
+            .or_default()
             .push(mapping.url_pattern);
     }
 
@@ -108,40 +114,111 @@ pub async fn get_url_mappings(state: State<'_, AppState>) -> Result<Value, Strin
 
 #[tauri::command]
 pub async fn add_app_mapping(
+    app_handle: AppHandle,
     state: State<'_, AppState>,
     app_name: String,
     category_id: String,
+    match_kind: Option<MatchKind>,
+    priority: Option<i32>,
+    identifiers: Option<String>,
 ) -> Result<(), String> {
+    let match_kind = match_kind.unwrap_or_default();
+    pattern::validate_app_pattern(match_kind, &app_name)?;
+    let identifiers = identifiers.filter(|ids| !ids.trim().is_empty());
+    if let Some(identifiers) = &identifiers {
+        pattern::validate_identifiers(identifiers)?;
+    }
+
+    let mapping = custom_app_mapping(app_name, category_id, match_kind, priority, identifiers);
     state
         .db
-        .add_simple_app_mapping(&category_id, &app_name, true)
+        .add_app_mapping(&mapping)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())?;
+
+    notify_mappings_changed(&app_handle)
 }
 
 #[tauri::command]
 pub async fn update_app_mapping(
+    app_handle: AppHandle,
     state: State<'_, AppState>,
     app_name: String,
     category_id: String,
+    match_kind: Option<MatchKind>,
+    priority: Option<i32>,
+    identifiers: Option<String>,
 ) -> Result<(), String> {
-    // For update, we need to remove the old mapping and add a new one
-    // since the app_name might have changed
-    state
+    let mappings = state
         .db
-        .remove_app_mapping(&category_id, &app_name)
+        .get_app_mappings()
         .await
         .map_err(|e| e.to_string())?;
+    let (mapping, replaced) = updated_app_mapping(
+        &mappings,
+        app_name,
+        category_id,
+        match_kind,
+        priority,
+        identifiers,
+    );
+    pattern::validate_app_pattern(mapping.match_kind, &mapping.app_pattern)?;
+    if let Some(identifiers) = &mapping.identifiers {
+        pattern::validate_identifiers(identifiers)?;
+    }
+
+    // For update, we need to remove the old mapping and add a new one
+    // since the category might have changed
+    if let Some(replaced) = replaced {
+        state
+            .db
+            .remove_app_mapping(&replaced.category_id, &replaced.app_pattern)
+            .await
+            .map_err(|e| e.to_string())?;
+    }
 
     state
         .db
-        .add_simple_app_mapping(&category_id, &app_name, true)
+        .add_app_mapping(&mapping)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())?;
+
+    notify_mappings_changed(&app_handle)
+}
+
+/// The mapping an update writes and the stored one it replaces: the pattern's mapping in
+/// `category_id`, or in another category if the update moves it. Settings left out keep
+/// what's stored, so changing only the category doesn't reset an exact match to substring.
+pub(crate) fn updated_app_mapping(
+    mappings: &[AppMapping],
+    app_name: String,
+    category_id: String,
+    match_kind: Option<MatchKind>,
+    priority: Option<i32>,
+    identifiers: Option<String>,
+) -> (AppMapping, Option<&AppMapping>) {
+    let replaced = mappings
+        .iter()
+        .filter(|mapping| mapping.app_pattern == app_name)
+        .max_by_key(|mapping| mapping.category_id == category_id);
+
+    let match_kind = match_kind
+        .or(replaced.map(|mapping| mapping.match_kind))
+        .unwrap_or_default();
+    let priority = priority.or(replaced.map(|mapping| mapping.priority));
+    // An empty string clears the identifiers
+    let identifiers = match identifiers {
+        Some(identifiers) => Some(identifiers).filter(|ids| !ids.trim().is_empty()),
+        None => replaced.and_then(|mapping| mapping.identifiers.clone()),
+    };
+
+    let mapping = custom_app_mapping(app_name, category_id, match_kind, priority, identifiers);
+    (mapping, replaced)
 }
 
 #[tauri::command]
 pub async fn delete_app_mapping(
+    app_handle: AppHandle,
     state: State<'_, AppState>,
     app_name: String,
 ) -> Result<(), String> {
@@ -154,11 +231,12 @@ pub async fn delete_app_mapping(
 
     for mapping in mappings {
         if mapping.app_pattern == app_name {
-            return state
+            state
                 .db
                 .remove_app_mapping(&mapping.category_id, &app_name)
                 .await
-                .map_err(|e| e.to_string());
+                .map_err(|e| e.to_string())?;
+            return notify_mappings_changed(&app_handle);
         }
     }
 
@@ -167,23 +245,32 @@ pub async fn delete_app_mapping(
 
 #[tauri::command]
 pub async fn remove_app_mapping(
+    app_handle: AppHandle,
     state: State<'_, AppState>,
     app_name: String,
 ) -> Result<(), String> {
-    delete_app_mapping(state, app_name).await
+    delete_app_mapping(app_handle, state, app_name).await
 }
 
 #[tauri::command]
 pub async fn add_url_mapping(
+    app_handle: AppHandle,
     state: State<'_, AppState>,
     url_pattern: String,
     category_id: String,
+    match_kind: Option<MatchKind>,
+    priority: Option<i32>,
 ) -> Result<(), String> {
+    let match_kind = match_kind.unwrap_or_default();
+    pattern::validate_url_pattern(match_kind, &url_pattern)?;
+
     let mapping = crate::models::UrlMapping {
         id: Uuid::new_v4(),
         url_pattern,
         category_id,
         is_custom: true,
+        match_kind,
+        priority: priority.unwrap_or_default(),
         created_at: chrono::Utc::now(),
         updated_at: chrono::Utc::now(),
     };
@@ -192,11 +279,14 @@ pub async fn add_url_mapping(
         .db
         .add_url_mapping(&mapping)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())?;
+
+    notify_mappings_changed(&app_handle)
 }
 
 #[tauri::command]
 pub async fn remove_url_mapping(
+    app_handle: AppHandle,
     state: State<'_, AppState>,
     url_pattern: String,
     category_id: String,
@@ -205,5 +295,297 @@ pub async fn remove_url_mapping(
         .db
         .remove_url_mapping(&category_id, &url_pattern)
         .await
+        .map_err(|e| e.to_string())?;
+
+    notify_mappings_changed(&app_handle)
+}
+
+#[tauri::command]
+pub async fn get_title_mappings(state: State<'_, AppState>) -> Result<Vec<TitleMapping>, String> {
+    state
+        .db
+        .get_title_mappings()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Add a window-title rule; `app_name` limits it to those apps (exact names separated by `|`)
+#[tauri::command]
+pub async fn add_title_mapping(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    title_pattern: String,
+    category_id: String,
+    app_name: Option<String>,
+    match_kind: Option<MatchKind>,
+    priority: Option<i32>,
+) -> Result<TitleMapping, String> {
+    let match_kind = match_kind.unwrap_or_default();
+    let app_name = app_name.filter(|name| !name.trim().is_empty());
+    pattern::validate_title_pattern(match_kind, &title_pattern, app_name.as_deref())?;
+
+    let mapping = TitleMapping {
+        id: Uuid::new_v4(),
+        title_pattern,
+        app_pattern: app_name,
+        category_id,
+        is_custom: true,
+        match_kind,
+        priority: priority.unwrap_or_default(),
+        created_at: chrono::Utc::now(),
+        updated_at: chrono::Utc::now(),
+    };
+
+    state
+        .db
+        .add_title_mapping(&mapping)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    notify_mappings_changed(&app_handle)?;
+    Ok(mapping)
+}
+
+#[tauri::command]
+pub async fn delete_title_mapping(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    id: String,
+) -> Result<(), String> {
+    let id = Uuid::parse_str(&id).map_err(|e| format!("Invalid mapping ID: {}", e))?;
+    state
+        .db
+        .delete_title_mapping(&id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    notify_mappings_changed(&app_handle)
+}
+
+/// Mappings that match the same app or URL, with which one wins and why
+#[tauri::command]
+pub async fn get_mapping_conflicts(
+    state: State<'_, AppState>,
+) -> Result<Vec<MappingConflict>, String> {
+    state
+        .db
+        .get_mapping_conflicts()
+        .await
         .map_err(|e| e.to_string())
-}
\ No newline at end of file
+}
+
+/// Mappings for apps and domains the user recategorized the same way at least
+/// `min_corrections` times (3 by default)
+#[tauri::command]
+pub async fn get_mapping_suggestions(
+    state: State<'_, AppState>,
+    min_corrections: Option<usize>,
+) -> Result<Vec<MappingSuggestion>, String> {
+    state
+        .db
+        .get_mapping_suggestions(
+            min_corrections
+                .unwrap_or(DEFAULT_SUGGESTION_THRESHOLD)
+                .max(1),
+        )
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Create the mapping a suggestion proposes
+#[tauri::command]
+pub async fn accept_mapping_suggestion(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    suggestion: MappingSuggestion,
+) -> Result<MappingRef, String> {
+    match suggestion.target {
+        MappingTarget::App => {
+            pattern::validate_app_pattern(suggestion.match_kind, &suggestion.pattern)?
+        }
+        MappingTarget::Url => {
+            pattern::validate_url_pattern(suggestion.match_kind, &suggestion.pattern)?
+        }
+        MappingTarget::Title => return Err("Only app and URL mappings are suggested".to_string()),
+    }
+
+    let mapping = state
+        .db
+        .accept_mapping_suggestion(&suggestion)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    notify_mappings_changed(&app_handle)?;
+    Ok(mapping)
+}
+
+/// Categorize an entry of the uncategorized queue. Apps get an exact mapping, domains a
+/// domain suffix mapping and titles a rule limited to their app. With both backfill dates
+/// the mappings are reapplied to those local days, which can be undone like any other run.
+#[tauri::command]
+pub async fn assign_uncategorized(
+    app_handle: AppHandle,
+    state: State<'_, AppState>,
+    item: UncategorizedItem,
+    category_id: String,
+    backfill_start_date: Option<String>,
+    backfill_end_date: Option<String>,
+) -> Result<TriageResult, String> {
+    let backfill = match (backfill_start_date, backfill_end_date) {
+        (Some(start_date), Some(end_date)) => {
+            Some(super::activity::local_period(&state, &start_date, &end_date).await?)
+        }
+        (None, None) => None,
+        _ => return Err("Backfill needs both a start and an end date".to_string()),
+    };
+
+    let (match_kind, pattern) = triage_pattern(&item)?;
+    let priority = state
+        .db
+        .priority_to_win(item.target, &item.value, item.app_name.as_deref())
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mapping = match item.target {
+        MappingTarget::App => {
+            pattern::validate_app_pattern(match_kind, &pattern)?;
+            let mapping =
+                custom_app_mapping(pattern, category_id, match_kind, Some(priority), None);
+            state
+                .db
+                .add_app_mapping(&mapping)
+                .await
+                .map_err(|e| e.to_string())?;
+            MappingRef::from(&mapping)
+        }
+        MappingTarget::Url => {
+            pattern::validate_url_pattern(match_kind, &pattern)?;
+            let mapping = crate::models::UrlMapping {
+                id: Uuid::new_v4(),
+                url_pattern: pattern,
+                category_id,
+                is_custom: true,
+                match_kind,
+                priority,
+                created_at: chrono::Utc::now(),
+                updated_at: chrono::Utc::now(),
+            };
+            state
+                .db
+                .add_url_mapping(&mapping)
+                .await
+                .map_err(|e| e.to_string())?;
+            MappingRef::from(&mapping)
+        }
+        MappingTarget::Title => {
+            pattern::validate_title_pattern(match_kind, &pattern, item.app_name.as_deref())?;
+            let mapping = TitleMapping {
+                id: Uuid::new_v4(),
+                title_pattern: pattern,
+                app_pattern: item.app_name,
+                category_id,
+                is_custom: true,
+                match_kind,
+                priority,
+                created_at: chrono::Utc::now(),
+                updated_at: chrono::Utc::now(),
+            };
+            state
+                .db
+                .add_title_mapping(&mapping)
+                .await
+                .map_err(|e| e.to_string())?;
+            MappingRef::from(&mapping)
+        }
+    };
+    notify_mappings_changed(&app_handle)?;
+
+    let backfill = match backfill {
+        Some((start, end)) => Some(
+            crate::activity::reapply_mappings(&state.db, start, end)
+                .await
+                .map_err(|e| e.to_string())?,
+        ),
+        None => None,
+    };
+
+    Ok(TriageResult { mapping, backfill })
+}
+
+/// The match kind and pattern of the rule that categorizes `item` exactly.
+///
+/// `|` separates alternatives, so values containing it are matched literally by regex. A
+/// title rule's app scope has no regex form, so an app name containing `|` is rejected
+/// rather than widening the rule to other apps.
+pub(crate) fn triage_pattern(item: &UncategorizedItem) -> Result<(MatchKind, String), String> {
+    if let Some(app_name) = item.app_name.as_deref().filter(|name| name.contains('|')) {
+        if item.target == MappingTarget::Title {
+            return Err(format!(
+                "Can't limit a title rule to '{}' because the app name contains '|'",
+                app_name
+            ));
+        }
+    }
+
+    Ok(match item.target {
+        MappingTarget::Url => (MatchKind::DomainSuffix, item.value.clone()),
+        MappingTarget::App if item.value.contains('|') => (
+            MatchKind::Regex,
+            format!("^{}$", regex::escape(&item.value)),
+        ),
+        MappingTarget::App => (MatchKind::Exact, item.value.clone()),
+        MappingTarget::Title if item.value.contains('|') => {
+            (MatchKind::Regex, regex::escape(&item.value))
+        }
+        MappingTarget::Title => (MatchKind::Substring, item.value.clone()),
+    })
+}
+
+/// Run categorization for the given window and report which mapping decided it
+#[tauri::command]
+pub async fn explain_categorization(
+    state: State<'_, AppState>,
+    app_name: String,
+    bundle_id: Option<String>,
+    window_title: Option<String>,
+    url: Option<String>,
+) -> Result<CategorizationExplanation, String> {
+    Ok(crate::activity::explain_categorization(
+        &state.db,
+        &app_name,
+        bundle_id.as_deref(),
+        window_title.as_deref(),
+        url.as_deref(),
+    )
+    .await)
+}
+
+fn custom_app_mapping(
+    app_name: String,
+    category_id: String,
+    match_kind: MatchKind,
+    priority: Option<i32>,
+    identifiers: Option<String>,
+) -> AppMapping {
+    AppMapping {
+        id: Uuid::new_v4(),
+        app_pattern: app_name,
+        category_id,
+        is_custom: true,
+        match_kind,
+        priority: priority.unwrap_or_default(),
+        identifiers,
+        created_at: chrono::Utc::now(),
+        updated_at: chrono::Utc::now(),
+    }
+}
+
+/// Tell the cache listeners that mappings changed so cached copies are rebuilt
+pub(crate) fn notify_mappings_changed(app_handle: &AppHandle) -> Result<(), String> {
+    app_handle
+        .emit(
+            "focus-cache-invalidate",
+            serde_json::json!({ "type": "mappings_changed" }),
+        )
+        .map_err(|e| e.to_string())
+}
@@ -7,6 +7,7 @@ pub mod focus_mode;
 pub mod blocking;
 pub mod permissions;
 pub mod pomodoro;
+pub mod schedule;
 
 // Re-export all commands for easy access
 pub use tracking::*;
@@ -17,4 +18,5 @@ pub use window::*;
 pub use focus_mode::*;
 pub use blocking::*;
 pub use permissions::*;
-pub use pomodoro::*;
\ No newline at end of file
+pub use pomodoro::*;
+pub use schedule::*;
\ No newline at end of file
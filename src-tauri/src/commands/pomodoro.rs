@@ -1,10 +1,18 @@
-use chrono::Utc;
+use chrono::{NaiveDate, Utc};
 use tauri::{AppHandle, Emitter, State};
 use uuid::Uuid;
 
 use crate::models::{PomodoroSession, PomodoroSessionType, PomodoroSettings, PomodoroSummary};
 use crate::AppState;
 
+// Helper to parse optional YYYY-MM-DD filters from the frontend
+fn parse_optional_date(date: Option<String>) -> Result<Option<NaiveDate>, String> {
+    date.map(|d| {
+        NaiveDate::parse_from_str(&d, "%Y-%m-%d").map_err(|e| format!("Invalid date format: {}", e))
+    })
+    .transpose()
+}
+
 #[tauri::command]
 pub async fn save_pomodoro_session(
     state: State<'_, AppState>,
@@ -73,9 +81,13 @@ pub async fn get_pomodoro_sessions(
         None => None,
     };
 
+    let start_date = parse_optional_date(start_date)?;
+    let end_date = parse_optional_date(end_date)?;
+    let tz = state.db.get_timezone().await.map_err(|e| e.to_string())?;
+
     state
         .db
-        .get_pomodoro_sessions(start_date, end_date, session_type_enum, limit)
+        .get_pomodoro_sessions(start_date, end_date, session_type_enum, limit, &tz)
         .await
         .map_err(|e| format!("Failed to get pomodoro sessions: {}", e))
 }
@@ -86,9 +98,13 @@ pub async fn get_pomodoro_summary(
     start_date: Option<String>,
     end_date: Option<String>,
 ) -> Result<PomodoroSummary, String> {
+    let start_date = parse_optional_date(start_date)?;
+    let end_date = parse_optional_date(end_date)?;
+    let tz = state.db.get_timezone().await.map_err(|e| e.to_string())?;
+
     state
         .db
-        .get_pomodoro_summary(start_date, end_date)
+        .get_pomodoro_summary(start_date, end_date, &tz)
         .await
         .map_err(|e| format!("Failed to get pomodoro summary: {}", e))
 }
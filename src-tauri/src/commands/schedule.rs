@@ -0,0 +1,105 @@
+use chrono::{NaiveTime, Timelike, Utc};
+use tauri::{AppHandle, Emitter, State};
+use uuid::Uuid;
+
+use crate::models::{FocusSchedule, FocusTransition};
+use crate::schedule;
+use crate::AppState;
+
+#[tauri::command]
+pub async fn get_focus_schedules(state: State<'_, AppState>) -> Result<Vec<FocusSchedule>, String> {
+    state
+        .db
+        .get_focus_schedules()
+        .await
+        .map_err(|e| format!("Failed to get focus schedules: {}", e))
+}
+
+/// Create or update a weekly focus schedule. The background loop picks up the change on its
+/// next check.
+#[tauri::command]
+pub async fn save_focus_schedule(
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+    mut schedule: FocusSchedule,
+) -> Result<FocusSchedule, String> {
+    schedule.name = schedule.name.trim().to_string();
+    if schedule.name.is_empty() {
+        return Err("Schedule name can't be empty".to_string());
+    }
+    schedule.days.sort_by_key(|day| day.num_days_from_monday());
+    schedule.days.dedup();
+    if schedule.days.is_empty() {
+        return Err("Pick at least one day for the schedule".to_string());
+    }
+
+    // Windows are checked to the minute
+    let to_minute =
+        |time: NaiveTime| NaiveTime::from_hms_opt(time.hour(), time.minute(), 0).unwrap_or(time);
+    schedule.start_time = to_minute(schedule.start_time);
+    schedule.end_time = to_minute(schedule.end_time);
+    if schedule.start_time == schedule.end_time {
+        return Err("Schedule start and end times must differ".to_string());
+    }
+
+    for category_id in &schedule.allowed_categories {
+        state
+            .db
+            .get_user_category_by_id(category_id)
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Category '{}' not found", category_id))?;
+    }
+
+    schedule.updated_at = Utc::now();
+    state
+        .db
+        .save_focus_schedule(&schedule)
+        .await
+        .map_err(|e| format!("Failed to save focus schedule: {}", e))?;
+
+    app_handle
+        .emit("focus-schedules-changed", ())
+        .map_err(|e| format!("Failed to emit event: {}", e))?;
+
+    Ok(schedule)
+}
+
+#[tauri::command]
+pub async fn delete_focus_schedule(
+    state: State<'_, AppState>,
+    app_handle: AppHandle,
+    id: String,
+) -> Result<(), String> {
+    let id = Uuid::parse_str(&id).map_err(|e| format!("Invalid schedule id: {}", e))?;
+    state
+        .db
+        .delete_focus_schedule(&id)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => "Focus schedule not found".to_string(),
+            e => format!("Failed to delete focus schedule: {}", e),
+        })?;
+
+    app_handle
+        .emit("focus-schedules-changed", ())
+        .map_err(|e| format!("Failed to emit event: {}", e))?;
+
+    Ok(())
+}
+
+/// When a schedule next turns focus mode on or off, if any schedule is enabled
+#[tauri::command]
+pub async fn get_next_focus_transition(
+    state: State<'_, AppState>,
+) -> Result<Option<FocusTransition>, String> {
+    let schedules = state
+        .db
+        .get_focus_schedules()
+        .await
+        .map_err(|e| format!("Failed to get focus schedules: {}", e))?;
+    let tz = state.db.get_timezone().await.map_err(|e| e.to_string())?;
+    let now = Utc::now().with_timezone(&tz).naive_local();
+
+    Ok(schedule::next_transition(&schedules, now, &tz))
+}
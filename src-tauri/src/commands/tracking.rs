@@ -31,7 +31,10 @@ async fn get_pause_info(state: &AppState, is_tracking: bool) -> Option<(u64, boo
 }
 
 // Helper function to update tray menu with pause info and focus mode status
-async fn update_tray_with_state(app_handle: &AppHandle, is_tracking: bool) -> Result<(), String> {
+pub(crate) async fn update_tray_with_state(
+    app_handle: &AppHandle,
+    is_tracking: bool,
+) -> Result<(), String> {
     let state: State<'_, AppState> = app_handle.state();
     let pause_info = get_pause_info(&state, is_tracking).await;
     let focus_mode_enabled = {
@@ -245,3 +248,60 @@ pub async fn get_pause_status(state: State<'_, AppState>) -> Result<serde_json::
         }))
     }
 }
+
+#[tauri::command]
+pub async fn get_idle_threshold(state: State<'_, AppState>) -> Result<u64, String> {
+    state
+        .db
+        .get_idle_threshold_seconds()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn set_idle_threshold(
+    state: State<'_, AppState>,
+    threshold_seconds: u64,
+) -> Result<(), String> {
+    // Between one minute and four hours
+    if !(60..=4 * 60 * 60).contains(&threshold_seconds) {
+        return Err("Idle threshold must be between 1 and 240 minutes".to_string());
+    }
+
+    state
+        .db
+        .set_idle_threshold_seconds(threshold_seconds)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut tracker = state.tracker.lock().map_err(|e| e.to_string())?;
+    tracker.set_inactive_threshold(Duration::from_secs(threshold_seconds));
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_timezone(state: State<'_, AppState>) -> Result<String, String> {
+    state
+        .db
+        .get_timezone()
+        .await
+        .map(|tz| tz.name().to_string())
+        .map_err(|e| e.to_string())
+}
+
+/// Set the IANA timezone (e.g. "Europe/Berlin") used for day boundaries; `None` follows the system
+#[tauri::command]
+pub async fn set_timezone(
+    state: State<'_, AppState>,
+    timezone: Option<String>,
+) -> Result<(), String> {
+    let tz = timezone
+        .map(|name| {
+            name.parse::<chrono_tz::Tz>()
+                .map_err(|_| format!("Unknown timezone: {}", name))
+        })
+        .transpose()?;
+
+    state.db.set_timezone(tz).await.map_err(|e| e.to_string())
+}
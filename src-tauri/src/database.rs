@@ -1,13 +1,84 @@
+use crate::mappings;
+use crate::matcher::CompiledMappings;
 use crate::models::{
-    ActivityCategory, ActivityEntry, ActivitySummary, AppMapping, AppSummary, CategorySummary,
-    TimelineActivity, TimelineData, UrlMapping, UserCategory,
+    ActivityCategory, ActivityEntry, ActivityReport, ActivitySegment, ActivitySummary, AppMapping,
+    AppSummary, CategorizationExplanation, CategorizationSource, CategoryCorrection,
+    CategoryDeletionReport, CategoryProvenance, CategorySummary, CategoryTrend,
+    DailyCategoryTotals, DailyProductivity, DetailedActivity, FocusSchedule, FocusStreak,
+    MappingConflict, MappingRef, MappingSuggestion, MappingTarget, MatchKind, Productivity,
+    ProductivityScore, ProductivityTrend, RecategorizationRun, ReportPeriod, SegmentSummary,
+    SegmentType, TimelineActivity, TimelineData, TimelineSegment, TitleMapping, UncategorizedItem,
+    UncategorizedReport, UrlMapping, UserCategory, UNKNOWN_CATEGORY_ID,
 };
-use chrono::{DateTime, Duration, NaiveDate, Utc};
+use crate::pattern;
+use crate::timezone::{date_range_bounds, day_bounds, local_date, system_timezone};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc, Weekday};
+use chrono_tz::Tz;
+use sqlx::sqlite::SqliteRow;
 use sqlx::{Row, SqlitePool};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
 use uuid::Uuid;
 
+/// SQLite caps the number of bound parameters per statement, so `IN (...)` lookups are chunked
+const SEGMENT_LOOKUP_CHUNK: usize = 500;
+
+/// How many apps, and how many files/tabs per app, the daily summary breaks down
+const DETAILED_ACTIVITY_LIMIT: usize = 10;
+
+/// Longest pause between two activities in the same category that still counts as one streak
+const FOCUS_STREAK_MAX_GAP_SECONDS: i64 = 120;
+
+/// How many of the longest focus streaks a report lists
+const FOCUS_STREAK_LIMIT: usize = 5;
+
+/// How many recently tracked app names/URLs are tested against mappings for conflicts
+const CONFLICT_SAMPLE_LIMIT: i64 = 500;
+
+/// An empty triage entry, filled in as matching time is found
+fn uncategorized(target: MappingTarget, value: &str, app_name: Option<&str>) -> UncategorizedItem {
+    UncategorizedItem {
+        target,
+        value: value.to_string(),
+        app_name: app_name.map(str::to_string),
+        duration_seconds: 0,
+        times_accessed: 0,
+        last_seen: DateTime::<Utc>::MIN_UTC,
+    }
+}
+
+/// Clip `[start_time, end_time)` to `[window_start, window_end)`.
+///
+/// Returns `None` when nothing is left. An open span stays open only if the window reaches
+/// past `now`; otherwise it is closed at the window edge.
+fn clip_span(
+    start_time: DateTime<Utc>,
+    end_time: Option<DateTime<Utc>>,
+    window_start: DateTime<Utc>,
+    window_end: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> Option<(DateTime<Utc>, Option<DateTime<Utc>>)> {
+    let start_time = start_time.max(window_start);
+    let end_time = match end_time {
+        Some(end_time) => Some(end_time.min(window_end)),
+        None if window_end <= now => Some(window_end),
+        None => None,
+    };
+
+    match end_time {
+        Some(end_time) if end_time <= start_time => None,
+        None if start_time >= window_end => None,
+        _ => Some((start_time, end_time)),
+    }
+}
+
 pub struct Database {
     pool: SqlitePool,
+    /// Mappings compiled for categorization, rebuilt after any mapping changes
+    compiled_mappings: RwLock<Option<Arc<CompiledMappings>>>,
+    /// Bumped on invalidation so a build that raced with a write isn't cached
+    mappings_generation: AtomicU64,
 }
 
 impl Database {
@@ -20,14 +91,19 @@ impl Database {
         // Apply migrations using our custom migration system
         crate::migrations::apply_migrations(&pool).await?;
 
-        Ok(Self { pool })
+        Ok(Self {
+            pool,
+            compiled_mappings: RwLock::new(None),
+            mappings_generation: AtomicU64::new(0),
+        })
     }
 
+    /// Record a new activity; its category is stored as the computed one
     pub async fn start_activity(&self, entry: &ActivityEntry) -> Result<(), sqlx::Error> {
         sqlx::query(
             r#"
-            INSERT INTO activity_entries (id, start_time, end_time, app_name, app_bundle_id, window_title, url, category)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            INSERT INTO activity_entries (id, start_time, end_time, app_name, app_bundle_id, window_title, url, category, computed_category, computed_source, computed_mapping_id)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?8, ?9, ?10)
             "#,
         )
         .bind(entry.id.to_string())
@@ -37,7 +113,37 @@ impl Database {
         .bind(&entry.app_bundle_id)
         .bind(&entry.window_title)
         .bind(&entry.url)
-        .bind(serde_json::to_string(&entry.category).unwrap())
+        .bind(entry.category.id())
+        .bind(entry.provenance.computed_source.map(|s| s.as_str()))
+        .bind(entry.provenance.mapping_id.map(|id| id.to_string()))
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Point an ongoing activity at the window it's in now, after its tab, file or window
+    /// title changed, so its title, URL and deciding mapping describe what was last seen
+    pub async fn update_activity_window(
+        &self,
+        activity_id: &Uuid,
+        window_title: &str,
+        url: Option<&str>,
+        source: CategorizationSource,
+        mapping_id: Option<Uuid>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE activity_entries
+            SET window_title = ?1, url = ?2, computed_source = ?3, computed_mapping_id = ?4
+            WHERE id = ?5
+            "#,
+        )
+        .bind(window_title)
+        .bind(url)
+        .bind(source.as_str())
+        .bind(mapping_id.map(|id| id.to_string()))
+        .bind(activity_id.to_string())
         .execute(&self.pool)
         .await?;
 
@@ -45,6 +151,9 @@ impl Database {
     }
 
     pub async fn end_current_activity(&self, end_time: DateTime<Utc>) -> Result<(), sqlx::Error> {
+        // Segments never outlive their activity
+        self.end_current_segment(end_time).await?;
+
         sqlx::query(
             r#"
             UPDATE activity_entries 
@@ -59,10 +168,131 @@ impl Database {
         Ok(())
     }
 
+    pub async fn start_segment(&self, segment: &ActivitySegment) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO activity_segments (id, activity_id, start_time, end_time, segment_type, title, url, file_path, metadata)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+            "#,
+        )
+        .bind(segment.id.to_string())
+        .bind(segment.activity_id.to_string())
+        .bind(segment.start_time.to_rfc3339())
+        .bind(segment.end_time.as_ref().map(|dt| dt.to_rfc3339()))
+        .bind(serde_json::to_string(&segment.segment_type).unwrap())
+        .bind(&segment.title)
+        .bind(&segment.url)
+        .bind(&segment.file_path)
+        .bind(&segment.metadata)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn end_current_segment(&self, end_time: DateTime<Utc>) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE activity_segments
+            SET end_time = ?1
+            WHERE end_time IS NULL
+            "#,
+        )
+        .bind(end_time.to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_current_segment(&self) -> Result<Option<ActivitySegment>, sqlx::Error> {
+        let row = sqlx::query(
+            r#"
+            SELECT id, activity_id, start_time, end_time, segment_type, title, url, file_path, metadata
+            FROM activity_segments
+            WHERE end_time IS NULL
+            ORDER BY start_time DESC
+            LIMIT 1
+            "#,
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| Self::segment_from_row(&row)))
+    }
+
+    /// Load the segments of the given activities, keyed by activity id and ordered by start time
+    pub async fn get_segments_for_activities(
+        &self,
+        activity_ids: &[Uuid],
+    ) -> Result<HashMap<Uuid, Vec<ActivitySegment>>, sqlx::Error> {
+        let mut segments: HashMap<Uuid, Vec<ActivitySegment>> = HashMap::new();
+
+        for chunk in activity_ids.chunks(SEGMENT_LOOKUP_CHUNK) {
+            let placeholders = vec!["?"; chunk.len()].join(", ");
+            let sql = format!(
+                r#"
+                SELECT id, activity_id, start_time, end_time, segment_type, title, url, file_path, metadata
+                FROM activity_segments
+                WHERE activity_id IN ({})
+                ORDER BY start_time ASC
+                "#,
+                placeholders
+            );
+
+            let mut query = sqlx::query(&sql);
+            for id in chunk {
+                query = query.bind(id.to_string());
+            }
+
+            for row in query.fetch_all(&self.pool).await? {
+                let segment = Self::segment_from_row(&row);
+                segments
+                    .entry(segment.activity_id)
+                    .or_default()
+                    .push(segment);
+            }
+        }
+
+        Ok(segments)
+    }
+
+    fn segment_from_row(row: &SqliteRow) -> ActivitySegment {
+        ActivitySegment {
+            id: Uuid::parse_str(&row.get::<String, _>("id")).unwrap(),
+            activity_id: Uuid::parse_str(&row.get::<String, _>("activity_id")).unwrap(),
+            start_time: DateTime::parse_from_rfc3339(&row.get::<String, _>("start_time"))
+                .unwrap()
+                .with_timezone(&Utc),
+            end_time: row.get::<Option<String>, _>("end_time").map(|s| {
+                DateTime::parse_from_rfc3339(&s)
+                    .unwrap()
+                    .with_timezone(&Utc)
+            }),
+            segment_type: serde_json::from_str(&row.get::<String, _>("segment_type"))
+                .unwrap_or(SegmentType::Unknown),
+            title: row.get("title"),
+            url: row.get("url"),
+            file_path: row.get("file_path"),
+            metadata: row.get("metadata"),
+        }
+    }
+
+    /// Fill in `segments` for each activity with a single lookup
+    async fn attach_segments(&self, activities: &mut [ActivityEntry]) -> Result<(), sqlx::Error> {
+        let ids: Vec<Uuid> = activities.iter().map(|a| a.id).collect();
+        let mut segments = self.get_segments_for_activities(&ids).await?;
+        for activity in activities.iter_mut() {
+            activity.segments = segments.remove(&activity.id).unwrap_or_default();
+        }
+        Ok(())
+    }
+
     pub async fn get_current_activity(&self) -> Result<Option<ActivityEntry>, sqlx::Error> {
         let row = sqlx::query(
             r#"
-            SELECT id, start_time, end_time, app_name, app_bundle_id, window_title, url, category
+            SELECT id, start_time, end_time, app_name, app_bundle_id, window_title, url, category,
+                   computed_category, computed_source, computed_mapping_id, category_override, category_overridden_at
             FROM activity_entries
             WHERE end_time IS NULL
             ORDER BY start_time DESC
@@ -73,147 +303,169 @@ impl Database {
         .await?;
 
         if let Some(row) = row {
-            let entry = ActivityEntry {
-                id: Uuid::parse_str(&row.get::<String, _>("id")).unwrap(),
-                start_time: DateTime::parse_from_rfc3339(&row.get::<String, _>("start_time"))
-                    .unwrap()
-                    .with_timezone(&Utc),
-                end_time: row.get::<Option<String>, _>("end_time").map(|s| {
-                    DateTime::parse_from_rfc3339(&s)
-                        .unwrap()
-                        .with_timezone(&Utc)
-                }),
-                app_name: row.get("app_name"),
-                app_bundle_id: row.get("app_bundle_id"),
-                window_title: row.get("window_title"),
-                url: row.get("url"),
-                category: serde_json::from_str(&row.get::<String, _>("category")).unwrap(),
-                segments: vec![], // TODO: Load segments separately
-            };
+            let mut entry = Self::activity_from_row(&row);
+            self.attach_segments(std::slice::from_mut(&mut entry))
+                .await?;
             Ok(Some(entry))
         } else {
             Ok(None)
         }
     }
 
+    /// Activities on a local calendar day, split at its boundaries.
+    ///
+    /// Activities that cross midnight are returned for both days, each clipped to the
+    /// part that falls inside that day.
     pub async fn get_activities_by_date(
         &self,
         date: NaiveDate,
+        tz: &Tz,
     ) -> Result<Vec<ActivityEntry>, sqlx::Error> {
-        let start_of_day = date.and_hms_opt(0, 0, 0).unwrap().and_utc();
-        let end_of_day = date.and_hms_opt(23, 59, 59).unwrap().and_utc();
-
-        let rows = sqlx::query(
-            r#"
-            SELECT id, start_time, end_time, app_name, app_bundle_id, window_title, url, category
-            FROM activity_entries
-            WHERE start_time >= ?1 AND start_time <= ?2
-            ORDER BY start_time ASC
-            "#,
-        )
-        .bind(start_of_day.to_rfc3339())
-        .bind(end_of_day.to_rfc3339())
-        .fetch_all(&self.pool)
-        .await?;
-
-        let mut activities = Vec::new();
-        for row in rows {
-            let category: ActivityCategory =
-                serde_json::from_str(&row.get::<String, _>("category"))
-                    .unwrap_or(ActivityCategory::Unknown);
-
-            activities.push(ActivityEntry {
-                id: Uuid::parse_str(&row.get::<String, _>("id")).unwrap(),
-                start_time: DateTime::parse_from_rfc3339(&row.get::<String, _>("start_time"))
-                    .unwrap()
-                    .with_timezone(&Utc),
-                end_time: row.get::<Option<String>, _>("end_time").map(|s| {
-                    DateTime::parse_from_rfc3339(&s)
-                        .unwrap()
-                        .with_timezone(&Utc)
-                }),
-                app_name: row.get("app_name"),
-                app_bundle_id: row.get("app_bundle_id"),
-                window_title: row.get("window_title"),
-                url: row.get("url"),
-                category,
-                segments: vec![], // TODO: Load segments separately
-            });
-        }
+        self.get_activities_by_date_at(date, tz, Utc::now()).await
+    }
 
-        Ok(activities)
+    /// Activities on the local day `date` as of `now`, which ongoing activities run until
+    pub async fn get_activities_by_date_at(
+        &self,
+        date: NaiveDate,
+        tz: &Tz,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<ActivityEntry>, sqlx::Error> {
+        let (start_of_day, end_of_day) = day_bounds(date, tz);
+        self.get_activities_in_window(start_of_day, end_of_day, now)
+            .await
     }
 
+    /// Activities on the local days `start_date..=end_date`, clipped to that period
     pub async fn get_activities_by_date_range(
         &self,
         start_date: NaiveDate,
         end_date: NaiveDate,
+        tz: &Tz,
     ) -> Result<Vec<ActivityEntry>, sqlx::Error> {
-        let start_of_period = start_date.and_hms_opt(0, 0, 0).unwrap().and_utc();
-        let end_of_period = end_date.and_hms_opt(23, 59, 59).unwrap().and_utc();
+        let (start_of_period, end_of_period) = date_range_bounds(start_date, end_date, tz);
+        self.get_activities_in_window(start_of_period, end_of_period, Utc::now())
+            .await
+    }
 
+    /// Activities and their segments overlapping `[start, end)`, clipped to the window
+    async fn get_activities_in_window(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<ActivityEntry>, sqlx::Error> {
         let rows = sqlx::query(
             r#"
-            SELECT id, start_time, end_time, app_name, app_bundle_id, window_title, url, category
+            SELECT id, start_time, end_time, app_name, app_bundle_id, window_title, url, category,
+                   computed_category, computed_source, computed_mapping_id, category_override, category_overridden_at
             FROM activity_entries
-            WHERE start_time >= ?1 AND start_time <= ?2
+            WHERE start_time < ?1 AND (end_time IS NULL OR end_time > ?2)
             ORDER BY start_time ASC
             "#,
         )
-        .bind(start_of_period.to_rfc3339())
-        .bind(end_of_period.to_rfc3339())
+        .bind(end.to_rfc3339())
+        .bind(start.to_rfc3339())
         .fetch_all(&self.pool)
         .await?;
 
-        let mut activities = Vec::new();
-        for row in rows {
-            let category: ActivityCategory =
-                serde_json::from_str(&row.get::<String, _>("category"))
-                    .unwrap_or(ActivityCategory::Unknown);
+        let mut activities: Vec<ActivityEntry> = rows.iter().map(Self::activity_from_row).collect();
+        self.attach_segments(&mut activities).await?;
 
-            activities.push(ActivityEntry {
-                id: Uuid::parse_str(&row.get::<String, _>("id")).unwrap(),
-                start_time: DateTime::parse_from_rfc3339(&row.get::<String, _>("start_time"))
+        Ok(activities
+            .into_iter()
+            .filter_map(|activity| Self::clip_activity(activity, start, end, now))
+            .collect())
+    }
+
+    fn activity_from_row(row: &SqliteRow) -> ActivityEntry {
+        ActivityEntry {
+            id: Uuid::parse_str(&row.get::<String, _>("id")).unwrap(),
+            start_time: DateTime::parse_from_rfc3339(&row.get::<String, _>("start_time"))
+                .unwrap()
+                .with_timezone(&Utc),
+            end_time: row.get::<Option<String>, _>("end_time").map(|s| {
+                DateTime::parse_from_rfc3339(&s)
                     .unwrap()
-                    .with_timezone(&Utc),
-                end_time: row.get::<Option<String>, _>("end_time").map(|s| {
-                    DateTime::parse_from_rfc3339(&s)
-                        .unwrap()
-                        .with_timezone(&Utc)
-                }),
-                app_name: row.get("app_name"),
-                app_bundle_id: row.get("app_bundle_id"),
-                window_title: row.get("window_title"),
-                url: row.get("url"),
-                category,
-                segments: vec![], // TODO: Load segments separately
-            });
+                    .with_timezone(&Utc)
+            }),
+            app_name: row.get("app_name"),
+            app_bundle_id: row.get("app_bundle_id"),
+            window_title: row.get("window_title"),
+            url: row.get("url"),
+            category: ActivityCategory::new(row.get::<String, _>("category")),
+            provenance: CategoryProvenance {
+                computed_category: row
+                    .get::<Option<String>, _>("computed_category")
+                    .map(ActivityCategory::new),
+                computed_source: row
+                    .get::<Option<String>, _>("computed_source")
+                    .and_then(|s| CategorizationSource::from_db(&s)),
+                mapping_id: row
+                    .get::<Option<String>, _>("computed_mapping_id")
+                    .and_then(|id| Uuid::parse_str(&id).ok()),
+                override_category: row
+                    .get::<Option<String>, _>("category_override")
+                    .map(ActivityCategory::new),
+                overridden_at: row
+                    .get::<Option<String>, _>("category_overridden_at")
+                    .and_then(|s| DateTime::parse_from_rfc3339(&s).ok())
+                    .map(|dt| dt.with_timezone(&Utc)),
+            },
+            segments: vec![],
         }
+    }
 
-        Ok(activities)
+    /// Trim an activity and its segments to `[start, end)`, dropping whatever falls outside
+    fn clip_activity(
+        mut activity: ActivityEntry,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> Option<ActivityEntry> {
+        (activity.start_time, activity.end_time) =
+            clip_span(activity.start_time, activity.end_time, start, end, now)?;
+
+        activity.segments = std::mem::take(&mut activity.segments)
+            .into_iter()
+            .filter_map(|mut segment| {
+                (segment.start_time, segment.end_time) =
+                    clip_span(segment.start_time, segment.end_time, start, end, now)?;
+                Some(segment)
+            })
+            .collect();
+
+        Some(activity)
     }
 
     pub async fn get_activity_summary(
         &self,
         date: NaiveDate,
+        tz: &Tz,
+    ) -> Result<ActivitySummary, sqlx::Error> {
+        self.get_activity_summary_at(date, tz, Utc::now()).await
+    }
+
+    /// The summary for the local day `date` as of `now`
+    pub async fn get_activity_summary_at(
+        &self,
+        date: NaiveDate,
+        tz: &Tz,
+        now: DateTime<Utc>,
     ) -> Result<ActivitySummary, sqlx::Error> {
-        let activities = self.get_activities_by_date(date).await?;
+        let activities = self.get_activities_by_date_at(date, tz, now).await?;
 
-        // Calculate duration for each activity
+        // Activities are already clipped to the day, so only ongoing ones need `now`
         let activities_with_duration: Vec<(ActivityEntry, i64)> = activities
             .into_iter()
-            .filter_map(|activity| {
-                if let Some(end_time) = activity.end_time {
-                    let duration = end_time
-                        .signed_duration_since(activity.start_time)
-                        .num_seconds();
-                    Some((activity, duration))
-                } else {
-                    // For ongoing activities, calculate duration from start_time to now
-                    let now = Utc::now();
-                    let duration = now.signed_duration_since(activity.start_time).num_seconds();
-                    Some((activity, duration))
-                }
+            .map(|activity| {
+                let duration = activity
+                    .end_time
+                    .unwrap_or(now)
+                    .signed_duration_since(activity.start_time)
+                    .num_seconds()
+                    .max(0);
+                (activity, duration)
             })
             .collect();
 
@@ -230,10 +482,23 @@ impl Database {
                 .or_insert(0) += duration;
         }
 
-        let categories: Vec<CategorySummary> = category_durations
+        let productivity = Self::productivity_score(
+            &category_durations,
+            &self.get_category_productivity().await?,
+        );
+        let parents = self.get_category_parents().await?;
+        let categories = Self::category_summaries(category_durations, total_active_time, &parents);
+
+        // Calculate app summaries
+        let mut app_durations = std::collections::HashMap::new();
+        for (activity, duration) in &activities_with_duration {
+            *app_durations.entry(activity.app_name.clone()).or_insert(0) += duration;
+        }
+
+        let mut top_apps: Vec<AppSummary> = app_durations
             .into_iter()
-            .map(|(category, duration)| CategorySummary {
-                category,
+            .map(|(app_name, duration)| AppSummary {
+                app_name,
                 duration_seconds: duration,
                 percentage: if total_active_time > 0 {
                     (duration as f64 / total_active_time as f64) * 100.0
@@ -243,16 +508,200 @@ impl Database {
             })
             .collect();
 
-        // Calculate app summaries
-        let mut app_durations = std::collections::HashMap::new();
-        for (activity, duration) in &activities_with_duration {
-            *app_durations.entry(activity.app_name.clone()).or_insert(0) += duration;
+        top_apps.sort_by_key(|a| std::cmp::Reverse(a.duration_seconds));
+        top_apps.truncate(10); // Top 10 apps
+
+        let detailed_activities = Self::detailed_activities(&activities_with_duration, now);
+        let corrections = Self::category_corrections(
+            activities_with_duration
+                .iter()
+                .map(|(activity, duration)| (activity, *duration)),
+        );
+
+        Ok(ActivitySummary {
+            date: date.to_string(),
+            total_active_time,
+            categories,
+            top_apps,
+            detailed_activities,
+            corrections,
+            productivity,
+        })
+    }
+
+    /// Roll segments up per app: which files, tabs and sessions took the time, and how
+    /// often each was returned to.
+    ///
+    /// Segments are grouped by type, title and file path, so revisiting a file or site
+    /// counts as another access of the same entry. Ongoing segments count up to `now`.
+    fn detailed_activities(
+        activities_with_duration: &[(ActivityEntry, i64)],
+        now: DateTime<Utc>,
+    ) -> Vec<DetailedActivity> {
+        let mut apps: HashMap<&str, (i64, Vec<SegmentSummary>)> = HashMap::new();
+
+        for (activity, duration) in activities_with_duration {
+            let (app_duration, summaries) = apps.entry(&activity.app_name).or_default();
+            *app_duration += duration;
+
+            for segment in &activity.segments {
+                let segment_duration = segment
+                    .end_time
+                    .unwrap_or(now)
+                    .signed_duration_since(segment.start_time)
+                    .num_seconds()
+                    .max(0);
+
+                let existing = summaries.iter_mut().find(|s| {
+                    s.segment_type == segment.segment_type
+                        && s.title == segment.title
+                        && s.file_path == segment.file_path
+                });
+                match existing {
+                    Some(summary) => {
+                        summary.duration_seconds += segment_duration;
+                        summary.times_accessed += 1;
+                    }
+                    None => summaries.push(SegmentSummary {
+                        segment_type: segment.segment_type.clone(),
+                        title: segment.title.clone(),
+                        duration_seconds: segment_duration,
+                        url: segment.url.clone(),
+                        file_path: segment.file_path.clone(),
+                        times_accessed: 1,
+                    }),
+                }
+            }
+        }
+
+        let mut detailed: Vec<DetailedActivity> = apps
+            .into_iter()
+            .map(|(app_name, (duration_seconds, mut segments))| {
+                segments.sort_by_key(|s| std::cmp::Reverse(s.duration_seconds));
+                segments.truncate(DETAILED_ACTIVITY_LIMIT);
+                DetailedActivity {
+                    app_name: app_name.to_string(),
+                    duration_seconds,
+                    segments,
+                }
+            })
+            .collect();
+
+        detailed.sort_by_key(|a| std::cmp::Reverse(a.duration_seconds));
+        detailed.truncate(DETAILED_ACTIVITY_LIMIT);
+        detailed
+    }
+
+    /// Week or month report for the period containing `date`, compared with the period before
+    pub async fn get_activity_report(
+        &self,
+        period: ReportPeriod,
+        date: NaiveDate,
+        tz: &Tz,
+    ) -> Result<ActivityReport, sqlx::Error> {
+        self.get_activity_report_at(period, date, tz, Utc::now())
+            .await
+    }
+
+    /// The report for the period containing `date` as of `now`.
+    ///
+    /// While the period is in progress it's compared with the same elapsed span of the
+    /// previous one, so a week that has only had two days isn't set against seven.
+    pub async fn get_activity_report_at(
+        &self,
+        period: ReportPeriod,
+        date: NaiveDate,
+        tz: &Tz,
+        now: DateTime<Utc>,
+    ) -> Result<ActivityReport, sqlx::Error> {
+        let (first, last) = period.dates_containing(date);
+        let (previous_first, previous_last) = period.previous_dates(date);
+        let (start, end) = date_range_bounds(first, last, tz);
+        let (previous_start, mut previous_end) =
+            date_range_bounds(previous_first, previous_last, tz);
+        if start < now && now < end {
+            previous_end = previous_end.min(previous_start + (now - start));
+        }
+
+        let activities = self.get_activities_in_window(start, end, now).await?;
+        let previous = self
+            .get_activities_in_window(previous_start, previous_end, now)
+            .await?;
+        let parents = self.get_category_parents().await?;
+
+        let duration = |activity: &ActivityEntry| {
+            activity
+                .end_time
+                .unwrap_or(now)
+                .signed_duration_since(activity.start_time)
+                .num_seconds()
+                .max(0)
+        };
+
+        // Per-day category totals, splitting activities that cross local midnight
+        let mut days = Vec::new();
+        let mut day = first;
+        while day <= last {
+            let category_durations = Self::day_category_durations(&activities, day, tz, now);
+            let total_active_time = category_durations.values().sum();
+            days.push(DailyCategoryTotals {
+                date: day.to_string(),
+                total_active_time,
+                categories: Self::category_summaries(
+                    category_durations,
+                    total_active_time,
+                    &parents,
+                ),
+            });
+
+            day = match day.succ_opt() {
+                Some(next) => next,
+                None => break,
+            };
+        }
+
+        // Category totals against the previous period
+        let mut category_durations: HashMap<ActivityCategory, (i64, i64)> = HashMap::new();
+        for activity in &activities {
+            category_durations
+                .entry(activity.category.clone())
+                .or_default()
+                .0 += duration(activity);
+        }
+        for activity in &previous {
+            category_durations
+                .entry(activity.category.clone())
+                .or_default()
+                .1 += duration(activity);
+        }
+
+        let mut categories: Vec<CategoryTrend> = category_durations
+            .into_iter()
+            .map(|(category, (current, previous))| CategoryTrend {
+                category,
+                duration_seconds: current,
+                previous_duration_seconds: previous,
+                change_percentage: if previous > 0 {
+                    Some((current - previous) as f64 / previous as f64 * 100.0)
+                } else {
+                    None
+                },
+            })
+            .collect();
+        categories.sort_by_key(|c| std::cmp::Reverse(c.duration_seconds));
+
+        let total_active_time: i64 = activities.iter().map(duration).sum();
+        let previous_total_active_time: i64 = previous.iter().map(duration).sum();
+
+        let mut app_durations: HashMap<&str, i64> = HashMap::new();
+        for activity in &activities {
+            *app_durations.entry(&activity.app_name).or_insert(0) += duration(activity);
         }
 
         let mut top_apps: Vec<AppSummary> = app_durations
             .into_iter()
             .map(|(app_name, duration)| AppSummary {
-                app_name,
+                app_name: app_name.to_string(),
                 duration_seconds: duration,
                 percentage: if total_active_time > 0 {
                     (duration as f64 / total_active_time as f64) * 100.0
@@ -262,21 +711,278 @@ impl Database {
             })
             .collect();
 
-        top_apps.sort_by(|a, b| b.duration_seconds.cmp(&a.duration_seconds));
+        top_apps.sort_by_key(|a| std::cmp::Reverse(a.duration_seconds));
         top_apps.truncate(10); // Top 10 apps
 
-        Ok(ActivitySummary {
-            date: date.to_string(),
+        Ok(ActivityReport {
+            period,
+            start_date: first.to_string(),
+            end_date: last.to_string(),
             total_active_time,
+            previous_total_active_time,
+            days,
             categories,
             top_apps,
-            detailed_activities: vec![], // TODO: Implement detailed activities with segments
+            focus_streaks: Self::focus_streaks(&activities, now),
+            corrections: Self::category_corrections(
+                activities
+                    .iter()
+                    .map(|activity| (activity, duration(activity))),
+            ),
         })
     }
 
-    pub async fn get_recent_timeline(&self, minutes: i64) -> Result<TimelineData, sqlx::Error> {
+    /// Productivity for each local day from `first` to `last`, judging `last` against the
+    /// average of the days before it
+    pub async fn get_productivity_trend(
+        &self,
+        first: NaiveDate,
+        last: NaiveDate,
+        tz: &Tz,
+    ) -> Result<ProductivityTrend, sqlx::Error> {
+        let activities = self.get_activities_by_date_range(first, last, tz).await?;
+        let weights = self.get_category_productivity().await?;
+        let now = Utc::now();
+
+        let mut days = Vec::new();
+        let mut day = first;
+        while day <= last {
+            let category_durations = Self::day_category_durations(&activities, day, tz, now);
+            days.push(DailyProductivity {
+                date: day.to_string(),
+                productivity: Self::productivity_score(&category_durations, &weights),
+            });
+
+            day = match day.succ_opt() {
+                Some(next) => next,
+                None => break,
+            };
+        }
+
+        let earlier: Vec<f64> = days
+            .iter()
+            .take(days.len().saturating_sub(1))
+            .filter_map(|day| day.productivity.score)
+            .collect();
+        let average_score =
+            (!earlier.is_empty()).then(|| earlier.iter().sum::<f64>() / earlier.len() as f64);
+        let good_day = days
+            .last()
+            .and_then(|day| day.productivity.score)
+            .zip(average_score)
+            .map(|(score, average)| score >= average);
+
+        Ok(ProductivityTrend {
+            start_date: first.to_string(),
+            end_date: last.to_string(),
+            days,
+            average_score,
+            good_day,
+        })
+    }
+
+    /// Time per computed/corrected category pair for activities the user recategorized,
+    /// largest first. Overrides that match the computed category aren't corrections.
+    fn category_corrections<'a>(
+        activities: impl Iterator<Item = (&'a ActivityEntry, i64)>,
+    ) -> Vec<CategoryCorrection> {
+        let mut corrections: Vec<CategoryCorrection> = Vec::new();
+        for (activity, duration) in activities {
+            let provenance = &activity.provenance;
+            let (Some(computed), Some(corrected)) =
+                (&provenance.computed_category, &provenance.override_category)
+            else {
+                continue;
+            };
+            if computed == corrected {
+                continue;
+            }
+
+            match corrections
+                .iter_mut()
+                .find(|c| &c.computed == computed && &c.corrected == corrected)
+            {
+                Some(correction) => {
+                    correction.activities += 1;
+                    correction.duration_seconds += duration;
+                }
+                None => corrections.push(CategoryCorrection {
+                    computed: computed.clone(),
+                    corrected: corrected.clone(),
+                    activities: 1,
+                    duration_seconds: duration,
+                }),
+            }
+        }
+
+        corrections.sort_by_key(|c| std::cmp::Reverse(c.duration_seconds));
+        corrections
+    }
+
+    /// Time per category on the local day `day`, splitting activities that cross midnight
+    fn day_category_durations(
+        activities: &[ActivityEntry],
+        day: NaiveDate,
+        tz: &Tz,
+        now: DateTime<Utc>,
+    ) -> HashMap<ActivityCategory, i64> {
+        let (start_of_day, end_of_day) = day_bounds(day, tz);
+        let mut category_durations = HashMap::new();
+        for activity in activities {
+            if let Some((start_time, end_time)) = clip_span(
+                activity.start_time,
+                activity.end_time,
+                start_of_day,
+                end_of_day,
+                now,
+            ) {
+                let seconds = (end_time.unwrap_or(now) - start_time).num_seconds().max(0);
+                *category_durations
+                    .entry(activity.category.clone())
+                    .or_insert(0) += seconds;
+            }
+        }
+        category_durations
+    }
+
+    /// Productive, neutral and distracting time, and the time-weighted score on a 0-100
+    /// scale. Categories without a stored weight count as neutral.
+    fn productivity_score(
+        category_durations: &HashMap<ActivityCategory, i64>,
+        weights: &HashMap<String, Productivity>,
+    ) -> ProductivityScore {
+        let mut productivity = ProductivityScore::default();
+        let mut weighted = 0;
+        for (category, duration) in category_durations {
+            let weight = weights
+                .get(category.id())
+                .copied()
+                .unwrap_or_default()
+                .weight();
+            weighted += weight * duration;
+            match weight {
+                w if w > 0 => productivity.productive_seconds += duration,
+                0 => productivity.neutral_seconds += duration,
+                _ => productivity.distracting_seconds += duration,
+            }
+        }
+
+        let total = productivity.productive_seconds
+            + productivity.neutral_seconds
+            + productivity.distracting_seconds;
+        if total > 0 {
+            // Weights run from -2 to 2
+            productivity.score = Some((weighted as f64 / total as f64 + 2.0) * 25.0);
+        }
+        productivity
+    }
+
+    /// Time per category, with each category's total also counting its subcategories.
+    ///
+    /// Parents of categories with time are listed even when they have none of their own.
+    fn category_summaries(
+        category_durations: HashMap<ActivityCategory, i64>,
+        total_active_time: i64,
+        parents: &HashMap<String, String>,
+    ) -> Vec<CategorySummary> {
+        let percentage = |duration: i64| {
+            if total_active_time > 0 {
+                (duration as f64 / total_active_time as f64) * 100.0
+            } else {
+                0.0
+            }
+        };
+
+        let mut totals: HashMap<ActivityCategory, (i64, i64)> = HashMap::new();
+        for (category, duration) in category_durations {
+            let mut ancestors = HashSet::new();
+            let mut parent_id = category.category_id().and_then(|id| parents.get(id));
+            while let Some(id) = parent_id {
+                // A cycle in the stored hierarchy must not loop forever
+                if !ancestors.insert(id) {
+                    break;
+                }
+                totals.entry(ActivityCategory::new(id)).or_default().1 += duration;
+                parent_id = parents.get(id);
+            }
+
+            let entry = totals.entry(category).or_default();
+            entry.0 += duration;
+            entry.1 += duration;
+        }
+
+        let mut categories: Vec<CategorySummary> = totals
+            .into_iter()
+            .map(|(category, (duration, total))| CategorySummary {
+                parent_id: category
+                    .category_id()
+                    .and_then(|id| parents.get(id))
+                    .cloned(),
+                category,
+                duration_seconds: duration,
+                percentage: percentage(duration),
+                total_duration_seconds: total,
+                total_percentage: percentage(total),
+            })
+            .collect();
+        categories.sort_by_key(|c| {
+            (
+                std::cmp::Reverse(c.total_duration_seconds),
+                std::cmp::Reverse(c.duration_seconds),
+            )
+        });
+        categories
+    }
+
+    /// Longest stretches spent in a single category.
+    ///
+    /// Back-to-back activities in the same category (switching between an editor and a
+    /// terminal, say) extend the streak as long as the gap between them stays short.
+    /// Uncategorized time never counts as focus.
+    fn focus_streaks(activities: &[ActivityEntry], now: DateTime<Utc>) -> Vec<FocusStreak> {
+        let mut streaks: Vec<FocusStreak> = Vec::new();
+        let mut current: Option<FocusStreak> = None;
+
+        for activity in activities {
+            let end_time = activity.end_time.unwrap_or(now);
+
+            if let Some(streak) = current.as_mut() {
+                let gap = (activity.start_time - streak.end_time).num_seconds();
+                if streak.category == activity.category && gap <= FOCUS_STREAK_MAX_GAP_SECONDS {
+                    streak.end_time = streak.end_time.max(end_time);
+                    continue;
+                }
+                streaks.extend(current.take());
+            }
+
+            if !activity.category.is_unknown() {
+                current = Some(FocusStreak {
+                    start_time: activity.start_time,
+                    end_time,
+                    duration_seconds: 0,
+                    category: activity.category.clone(),
+                });
+            }
+        }
+        streaks.extend(current);
+
+        for streak in &mut streaks {
+            streak.duration_seconds = (streak.end_time - streak.start_time).num_seconds();
+        }
+        streaks.sort_by_key(|s| std::cmp::Reverse(s.duration_seconds));
+        streaks.truncate(FOCUS_STREAK_LIMIT);
+        streaks
+    }
+
+    /// Activities overlapping `[start, end)`, clipped to the range edges.
+    ///
+    /// Ongoing activities run until now, so a range reaching into the future ends at now.
+    pub async fn get_timeline(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<TimelineData, sqlx::Error> {
         let now = Utc::now();
-        let start_time = now - Duration::minutes(minutes);
 
         let rows = sqlx::query(
             r#"
@@ -284,16 +990,21 @@ impl Database {
                 id, start_time, end_time, app_name, app_bundle_id, 
                 window_title, url, category
             FROM activity_entries 
-            WHERE start_time >= ? OR (start_time < ? AND (end_time IS NULL OR end_time >= ?))
+            WHERE start_time < ?1 AND (end_time IS NULL OR end_time > ?2)
             ORDER BY start_time ASC
             "#,
         )
-        .bind(start_time.to_rfc3339())
-        .bind(start_time.to_rfc3339())
-        .bind(start_time.to_rfc3339())
+        .bind(end.to_rfc3339())
+        .bind(start.to_rfc3339())
         .fetch_all(&self.pool)
         .await?;
 
+        let ids: Vec<Uuid> = rows
+            .iter()
+            .map(|row| Uuid::parse_str(&row.get::<String, _>("id")).unwrap())
+            .collect();
+        let mut segments = self.get_segments_for_activities(&ids).await?;
+
         let mut activities = Vec::new();
         for row in rows {
             let start_time_parsed =
@@ -308,19 +1019,25 @@ impl Database {
             });
 
             // Calculate actual start and end times within the timeline window
-            let timeline_start = start_time_parsed.max(start_time);
-            let timeline_end = end_time_parsed.unwrap_or(now).min(now);
+            let timeline_start = start_time_parsed.max(start);
+            let timeline_end = end_time_parsed.unwrap_or(now).min(end);
 
             // Only include if there's actual overlap with our timeline
             if timeline_start < timeline_end {
                 let duration_seconds = (timeline_end - timeline_start).num_seconds();
 
-                let category: ActivityCategory =
-                    serde_json::from_str(&row.get::<String, _>("category"))
-                        .unwrap_or(ActivityCategory::Unknown);
+                let category = ActivityCategory::new(row.get::<String, _>("category"));
+
+                let id = Uuid::parse_str(&row.get::<String, _>("id")).unwrap();
+                let timeline_segments = segments
+                    .remove(&id)
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter_map(|segment| Self::clip_segment(segment, timeline_start, timeline_end))
+                    .collect();
 
                 activities.push(TimelineActivity {
-                    id: Uuid::parse_str(&row.get::<String, _>("id")).unwrap(),
+                    id,
                     start_time: timeline_start,
                     end_time: Some(timeline_end),
                     duration_seconds,
@@ -329,18 +1046,45 @@ impl Database {
                     window_title: row.get("window_title"),
                     url: row.get("url"),
                     category,
-                    segments: vec![], // TODO: Load segments
+                    segments: timeline_segments,
                 });
             }
         }
 
         Ok(TimelineData {
-            start_time,
-            end_time: now,
+            start_time: start,
+            end_time: end,
             activities,
         })
     }
 
+    /// Clip a segment to the timeline window, dropping it if nothing is left
+    fn clip_segment(
+        segment: ActivitySegment,
+        window_start: DateTime<Utc>,
+        window_end: DateTime<Utc>,
+    ) -> Option<TimelineSegment> {
+        let start_time = segment.start_time.max(window_start);
+        let end_time = segment.end_time.unwrap_or(window_end).min(window_end);
+        if start_time >= end_time {
+            return None;
+        }
+
+        Some(TimelineSegment {
+            id: segment.id,
+            start_time,
+            end_time: Some(end_time),
+            duration_seconds: (end_time - start_time).num_seconds(),
+            segment_type: segment.segment_type,
+            title: segment.title,
+            url: segment.url,
+            file_path: segment.file_path,
+            metadata: segment.metadata,
+        })
+    }
+
+    /// Override an activity's category by hand; the computed category is kept alongside and
+    /// reapplying mappings leaves the activity alone
     pub async fn update_activity_category(
         &self,
         activity_id: &str,
@@ -349,11 +1093,12 @@ impl Database {
         sqlx::query(
             r#"
             UPDATE activity_entries 
-            SET category = ?1 
-            WHERE id = ?2
+            SET category = ?1, category_override = ?1, category_overridden_at = ?2
+            WHERE id = ?3
             "#,
         )
-        .bind(serde_json::to_string(category).unwrap())
+        .bind(category.id())
+        .bind(Utc::now().to_rfc3339())
         .bind(activity_id)
         .execute(&self.pool)
         .await?;
@@ -361,18 +1106,207 @@ impl Database {
         Ok(())
     }
 
+    /// Drop the user's override so the computed category applies again
+    pub async fn clear_activity_category_override(
+        &self,
+        activity_id: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            UPDATE activity_entries
+            SET category = COALESCE(computed_category, category),
+                category_override = NULL,
+                category_overridden_at = NULL
+            WHERE id = ?1
+            "#,
+        )
+        .bind(activity_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Activities overlapping `[start, end)`, without segments
+    pub async fn get_activities_for_recategorization(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<ActivityEntry>, sqlx::Error> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, start_time, end_time, app_name, app_bundle_id, window_title, url, category,
+                   computed_category, computed_source, computed_mapping_id, category_override, category_overridden_at
+            FROM activity_entries
+            WHERE start_time < ?1 AND (end_time IS NULL OR end_time > ?2)
+            ORDER BY start_time ASC
+            "#,
+        )
+        .bind(end.to_rfc3339())
+        .bind(start.to_rfc3339())
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(Self::activity_from_row).collect())
+    }
+
+    /// Give each activity its newly computed category and record the changes as one
+    /// undoable run covering `[start, end)`
+    pub async fn record_recategorization(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        changes: &[(ActivityEntry, CategorizationExplanation)],
+    ) -> Result<Uuid, sqlx::Error> {
+        let run_id = Uuid::new_v4();
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO recategorization_runs (id, start_time, end_time, created_at)
+            VALUES (?1, ?2, ?3, ?4)
+            "#,
+        )
+        .bind(run_id.to_string())
+        .bind(start.to_rfc3339())
+        .bind(end.to_rfc3339())
+        .bind(Utc::now().to_rfc3339())
+        .execute(&mut *tx)
+        .await?;
+
+        for (activity, computed) in changes {
+            let old_category = activity.category.id();
+            let new_category = computed.category.id();
+
+            sqlx::query(
+                r#"
+                INSERT INTO recategorization_changes (run_id, activity_id, old_category, new_category, old_source, old_mapping_id)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                "#,
+            )
+            .bind(run_id.to_string())
+            .bind(activity.id.to_string())
+            .bind(old_category)
+            .bind(new_category)
+            .bind(activity.provenance.computed_source.map(|s| s.as_str()))
+            .bind(activity.provenance.mapping_id.map(|id| id.to_string()))
+            .execute(&mut *tx)
+            .await?;
+
+            // Skip rows the user recategorized by hand in the meantime
+            sqlx::query(
+                r#"
+                UPDATE activity_entries
+                SET category = ?1, computed_category = ?1, computed_source = ?2, computed_mapping_id = ?3
+                WHERE id = ?4 AND category = ?5 AND category_override IS NULL
+                "#,
+            )
+            .bind(new_category)
+            .bind(computed.source.as_str())
+            .bind(computed.matched_mapping.as_ref().map(|m| m.id.to_string()))
+            .bind(activity.id.to_string())
+            .bind(old_category)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(run_id)
+    }
+
+    /// Revert a recategorization run, returning how many activities were restored.
+    ///
+    /// Activities recategorized again since (by hand or by a later run) keep their current
+    /// category. Unknown or already undone runs are `RowNotFound`.
+    pub async fn undo_recategorization(&self, run_id: &Uuid) -> Result<u64, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let marked = sqlx::query(
+            "UPDATE recategorization_runs SET undone_at = ?1 WHERE id = ?2 AND undone_at IS NULL",
+        )
+        .bind(Utc::now().to_rfc3339())
+        .bind(run_id.to_string())
+        .execute(&mut *tx)
+        .await?;
+        if marked.rows_affected() == 0 {
+            return Err(sqlx::Error::RowNotFound);
+        }
+
+        let restored = sqlx::query(
+            r#"
+            UPDATE activity_entries
+            SET category = c.old_category,
+                computed_category = c.old_category,
+                computed_source = c.old_source,
+                computed_mapping_id = c.old_mapping_id
+            FROM recategorization_changes c
+            WHERE c.run_id = ?1
+              AND c.activity_id = activity_entries.id
+              AND c.new_category = activity_entries.category
+              AND activity_entries.category_override IS NULL
+            "#,
+        )
+        .bind(run_id.to_string())
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(restored.rows_affected())
+    }
+
+    /// Recorded recategorization runs, newest first
+    pub async fn get_recategorization_runs(&self) -> Result<Vec<RecategorizationRun>, sqlx::Error> {
+        let rows = sqlx::query(
+            r#"
+            SELECT r.id, r.start_time, r.end_time, r.created_at, r.undone_at,
+                   COUNT(c.activity_id) AS changed
+            FROM recategorization_runs r
+            LEFT JOIN recategorization_changes c ON c.run_id = r.id
+            GROUP BY r.id
+            ORDER BY r.created_at DESC
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let parse_time = |value: String| {
+            DateTime::parse_from_rfc3339(&value)
+                .map(|time| time.with_timezone(&Utc))
+                .map_err(|_| sqlx::Error::Decode("Invalid timestamp format".into()))
+        };
+
+        let mut runs = Vec::new();
+        for row in rows {
+            runs.push(RecategorizationRun {
+                id: Uuid::parse_str(&row.get::<String, _>("id"))
+                    .map_err(|_| sqlx::Error::Decode("Invalid UUID format".into()))?,
+                start_time: parse_time(row.get("start_time"))?,
+                end_time: parse_time(row.get("end_time"))?,
+                created_at: parse_time(row.get("created_at"))?,
+                undone_at: row
+                    .get::<Option<String>, _>("undone_at")
+                    .map(parse_time)
+                    .transpose()?,
+                changed: row.get::<i64, _>("changed") as usize,
+            });
+        }
+
+        Ok(runs)
+    }
+
     // User category management
     pub async fn add_user_category(&self, category: &UserCategory) -> Result<(), sqlx::Error> {
         sqlx::query(
             r#"
-            INSERT INTO user_categories (id, name, color, parent_id, created_at, updated_at)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            INSERT INTO user_categories (id, name, color, parent_id, productivity, created_at, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
             "#,
         )
         .bind(&category.id)
         .bind(&category.name)
         .bind(&category.color)
         .bind(&category.parent_id)
+        .bind(category.productivity.weight())
         .bind(category.created_at.to_rfc3339())
         .bind(category.updated_at.to_rfc3339())
         .execute(&self.pool)
@@ -384,7 +1318,7 @@ impl Database {
     pub async fn get_user_categories(&self) -> Result<Vec<UserCategory>, sqlx::Error> {
         let rows = sqlx::query(
             r#"
-            SELECT id, name, color, parent_id, created_at, updated_at
+            SELECT id, name, color, parent_id, productivity, created_at, updated_at
             FROM user_categories
             ORDER BY name
             "#,
@@ -406,6 +1340,7 @@ impl Database {
                 name: row.get("name"),
                 color: row.get("color"),
                 parent_id: row.get("parent_id"),
+                productivity: Productivity::from_weight(row.get("productivity")),
                 created_at,
                 updated_at,
             });
@@ -418,7 +1353,7 @@ impl Database {
         sqlx::query(
             r#"
             UPDATE user_categories 
-            SET name = ?2, color = ?3, parent_id = ?4, updated_at = ?5
+            SET name = ?2, color = ?3, parent_id = ?4, productivity = ?5, updated_at = ?6
             WHERE id = ?1
             "#,
         )
@@ -426,6 +1361,7 @@ impl Database {
         .bind(&category.name)
         .bind(&category.color)
         .bind(&category.parent_id)
+        .bind(category.productivity.weight())
         .bind(category.updated_at.to_rfc3339())
         .execute(&self.pool)
         .await?;
@@ -439,7 +1375,7 @@ impl Database {
     ) -> Result<Option<UserCategory>, sqlx::Error> {
         let row = sqlx::query(
             r#"
-            SELECT id, name, color, parent_id, created_at, updated_at
+            SELECT id, name, color, parent_id, productivity, created_at, updated_at
             FROM user_categories
             WHERE id = ?1
             "#,
@@ -462,6 +1398,7 @@ impl Database {
                     name: row.get("name"),
                     color: row.get("color"),
                     parent_id: row.get("parent_id"),
+                    productivity: Productivity::from_weight(row.get("productivity")),
                     created_at,
                     updated_at,
                 }))
@@ -470,79 +1407,235 @@ impl Database {
         }
     }
 
-    pub async fn delete_user_category(&self, id: &str) -> Result<(), sqlx::Error> {
-        // First, get the category to check if it's "Unknown"
-        let category = self.get_user_category_by_id(id).await?;
-        if let Some(cat) = category {
-            if cat.name.to_lowercase() == "unknown" {
-                return Err(sqlx::Error::RowNotFound); // Return error for "Unknown" category
-            }
-
-            // Reassign all activities with this category to "Unknown"
-            let unknown_category = crate::models::ActivityCategory::Unknown;
-            sqlx::query(
-                r#"
-                UPDATE activity_entries 
-                SET category = ?1 
-                WHERE category = ?2
-                "#,
-            )
-            .bind(serde_json::to_string(&unknown_category).unwrap())
-            .bind(
-                serde_json::to_string(&crate::models::ActivityCategory::Custom(cat.id.clone()))
-                    .unwrap(),
+    /// Ids of the subcategories of `id` at any depth
+    pub async fn get_category_descendants(&self, id: &str) -> Result<Vec<String>, sqlx::Error> {
+        let rows = sqlx::query(
+            r#"
+            WITH RECURSIVE descendants(id) AS (
+                SELECT id FROM user_categories WHERE parent_id = ?1
+                UNION
+                SELECT c.id FROM user_categories c JOIN descendants d ON c.parent_id = d.id
             )
-            .execute(&self.pool)
-            .await?;
+            SELECT id FROM descendants ORDER BY id
+            "#,
+        )
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await?;
 
-            // Delete app mappings for this category
-            sqlx::query("DELETE FROM app_mappings WHERE category_id = ?1")
-                .bind(&cat.id)
-                .execute(&self.pool)
-                .await?;
+        Ok(rows.into_iter().map(|row| row.get("id")).collect())
+    }
 
-            // Delete URL mappings for this category
-            sqlx::query("DELETE FROM url_mappings WHERE category_id = ?1")
-                .bind(&cat.id)
-                .execute(&self.pool)
+    /// Parent id of every subcategory, keyed by the subcategory's id
+    pub async fn get_category_parents(&self) -> Result<HashMap<String, String>, sqlx::Error> {
+        let rows =
+            sqlx::query("SELECT id, parent_id FROM user_categories WHERE parent_id IS NOT NULL")
+                .fetch_all(&self.pool)
                 .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.get("id"), row.get("parent_id")))
+            .collect())
+    }
+
+    /// Productivity of every category, keyed by id
+    pub async fn get_category_productivity(
+        &self,
+    ) -> Result<HashMap<String, Productivity>, sqlx::Error> {
+        let rows = sqlx::query("SELECT id, productivity FROM user_categories")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                (
+                    row.get("id"),
+                    Productivity::from_weight(row.get("productivity")),
+                )
+            })
+            .collect())
+    }
+
+    /// Delete a category, first reassigning everything that refers to it to `target_id`:
+    /// mappings, activities (computed categories and overrides alike), recorded
+    /// recategorizations, focus mode's allowed categories and subcategories. When the
+    /// target is Unknown its mappings are deleted and subcategories become top-level.
+    ///
+    /// All steps run in one transaction. A dry run rolls it back and only reports the rows
+    /// each step touched. The target must not be the category or one of its subcategories.
+    pub async fn delete_user_category(
+        &self,
+        id: &str,
+        target_id: &str,
+        dry_run: bool,
+    ) -> Result<CategoryDeletionReport, sqlx::Error> {
+        let category = self
+            .get_user_category_by_id(id)
+            .await?
+            .ok_or(sqlx::Error::RowNotFound)?;
+        if category.name.to_lowercase() == "unknown" {
+            return Err(sqlx::Error::RowNotFound); // Return error for "Unknown" category
         }
+        self.get_user_category_by_id(target_id)
+            .await?
+            .ok_or(sqlx::Error::RowNotFound)?;
+
+        let mut report = CategoryDeletionReport {
+            category_id: category.id.clone(),
+            target_category_id: target_id.to_string(),
+            dry_run,
+            ..Default::default()
+        };
+        let mut tx = self.pool.begin().await?;
+
+        for (table, touched) in [
+            ("app_mappings", &mut report.app_mappings),
+            ("url_mappings", &mut report.url_mappings),
+            ("title_mappings", &mut report.title_mappings),
+        ] {
+            // A mapping to Unknown categorizes nothing, so it goes with the category
+            if target_id == UNKNOWN_CATEGORY_ID {
+                report.deleted_mappings +=
+                    sqlx::query(&format!("DELETE FROM {} WHERE category_id = ?1", table))
+                        .bind(&category.id)
+                        .execute(&mut *tx)
+                        .await?
+                        .rows_affected();
+                continue;
+            }
+            *touched = sqlx::query(&format!(
+                "UPDATE {} SET category_id = ?2, updated_at = ?3 WHERE category_id = ?1",
+                table
+            ))
+            .bind(&category.id)
+            .bind(target_id)
+            .bind(Utc::now().to_rfc3339())
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        }
+
+        report.activities = sqlx::query(
+            r#"
+            UPDATE activity_entries
+            SET category = CASE WHEN category = ?1 THEN ?2 ELSE category END,
+                computed_category = CASE WHEN computed_category = ?1 THEN ?2 ELSE computed_category END,
+                category_override = CASE WHEN category_override = ?1 THEN ?2 ELSE category_override END
+            WHERE category = ?1 OR computed_category = ?1 OR category_override = ?1
+            "#,
+        )
+        .bind(&category.id)
+        .bind(target_id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        // Undoing an earlier recategorization must not bring the category back
+        report.recategorization_changes = sqlx::query(
+            r#"
+            UPDATE recategorization_changes
+            SET old_category = CASE WHEN old_category = ?1 THEN ?2 ELSE old_category END,
+                new_category = CASE WHEN new_category = ?1 THEN ?2 ELSE new_category END
+            WHERE old_category = ?1 OR new_category = ?1
+            "#,
+        )
+        .bind(&category.id)
+        .bind(target_id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        // The target may already be allowed, then the deleted category is just dropped
+        report.focus_mode_categories = sqlx::query(
+            "UPDATE OR IGNORE focus_mode_allowed_categories SET category_id = ?2 WHERE category_id = ?1",
+        )
+        .bind(&category.id)
+        .bind(target_id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        report.focus_mode_categories +=
+            sqlx::query("DELETE FROM focus_mode_allowed_categories WHERE category_id = ?1")
+                .bind(&category.id)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+
+        // Focus schedules' allowed categories, the same way
+        report.focus_mode_categories += sqlx::query(
+            "UPDATE OR IGNORE focus_schedule_categories SET category_id = ?2 WHERE category_id = ?1",
+        )
+        .bind(&category.id)
+        .bind(target_id)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        report.focus_mode_categories +=
+            sqlx::query("DELETE FROM focus_schedule_categories WHERE category_id = ?1")
+                .bind(&category.id)
+                .execute(&mut *tx)
+                .await?
+                .rows_affected();
+
+        let new_parent = (target_id != UNKNOWN_CATEGORY_ID).then_some(target_id);
+        report.subcategories = sqlx::query(
+            "UPDATE user_categories SET parent_id = ?2, updated_at = ?3 WHERE parent_id = ?1",
+        )
+        .bind(&category.id)
+        .bind(new_parent)
+        .bind(Utc::now().to_rfc3339())
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
 
-        // Finally, delete the category
         sqlx::query("DELETE FROM user_categories WHERE id = ?1")
-            .bind(id)
-            .execute(&self.pool)
+            .bind(&category.id)
+            .execute(&mut *tx)
             .await?;
 
-        Ok(())
+        if dry_run {
+            tx.rollback().await?;
+        } else {
+            tx.commit().await?;
+            self.invalidate_compiled_mappings();
+        }
+
+        Ok(report)
     }
 
     // App mapping management
     pub async fn add_app_mapping(&self, mapping: &AppMapping) -> Result<(), sqlx::Error> {
         sqlx::query(
             r#"
-            INSERT OR REPLACE INTO app_mappings (id, app_pattern, category_id, is_custom, created_at, updated_at)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            INSERT OR REPLACE INTO app_mappings (id, app_pattern, category_id, is_custom, match_kind, priority, identifiers, created_at, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
             "#,
         )
         .bind(mapping.id.to_string())
         .bind(&mapping.app_pattern)
         .bind(&mapping.category_id)
         .bind(mapping.is_custom as i32)
+        .bind(mapping.match_kind.as_str())
+        .bind(mapping.priority)
+        .bind(&mapping.identifiers)
         .bind(mapping.created_at.to_rfc3339())
         .bind(mapping.updated_at.to_rfc3339())
         .execute(&self.pool)
         .await?;
 
+        self.invalidate_compiled_mappings();
+
         Ok(())
     }
 
+    /// All app mappings in precedence order; the first one that matches wins
     pub async fn get_app_mappings(&self) -> Result<Vec<AppMapping>, sqlx::Error> {
         let rows = sqlx::query(
             r#"
-            SELECT id, app_pattern, category_id, is_custom, created_at, updated_at
+            SELECT id, app_pattern, category_id, is_custom, match_kind, priority, identifiers, created_at, updated_at
             FROM app_mappings
-            ORDER BY app_pattern
             "#,
         )
         .fetch_all(&self.pool)
@@ -564,11 +1657,15 @@ impl Database {
                 app_pattern: row.get("app_pattern"),
                 category_id: row.get("category_id"),
                 is_custom: row.get::<i32, _>("is_custom") != 0,
+                match_kind: MatchKind::from_db(&row.get::<String, _>("match_kind")),
+                priority: row.get("priority"),
+                identifiers: row.get("identifiers"),
                 created_at,
                 updated_at,
             });
         }
 
+        mappings::sort_app_mappings(&mut mappings);
         Ok(mappings)
     }
 
@@ -577,7 +1674,7 @@ impl Database {
         sqlx::query(
             r#"
             UPDATE app_mappings 
-            SET app_pattern = ?2, category_id = ?3, is_custom = ?4, updated_at = ?5
+            SET app_pattern = ?2, category_id = ?3, is_custom = ?4, match_kind = ?5, priority = ?6, identifiers = ?7, updated_at = ?8
             WHERE id = ?1
             "#,
         )
@@ -585,10 +1682,15 @@ impl Database {
         .bind(&mapping.app_pattern)
         .bind(&mapping.category_id)
         .bind(mapping.is_custom as i32)
+        .bind(mapping.match_kind.as_str())
+        .bind(mapping.priority)
+        .bind(&mapping.identifiers)
         .bind(mapping.updated_at.to_rfc3339())
         .execute(&self.pool)
         .await?;
 
+        self.invalidate_compiled_mappings();
+
         Ok(())
     }
 
@@ -599,6 +1701,8 @@ impl Database {
             .execute(&self.pool)
             .await?;
 
+        self.invalidate_compiled_mappings();
+
         Ok(())
     }
 
@@ -613,6 +1717,8 @@ impl Database {
             .execute(&self.pool)
             .await?;
 
+        self.invalidate_compiled_mappings();
+
         Ok(())
     }
 
@@ -621,12 +1727,16 @@ impl Database {
         category_id: &str,
         app_pattern: &str,
         is_custom: bool,
+        match_kind: MatchKind,
     ) -> Result<(), sqlx::Error> {
         let mapping = AppMapping {
             id: Uuid::new_v4(),
             app_pattern: app_pattern.to_string(),
             category_id: category_id.to_string(),
             is_custom,
+            match_kind,
+            priority: 0,
+            identifiers: None,
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
         };
@@ -638,28 +1748,32 @@ impl Database {
     pub async fn add_url_mapping(&self, mapping: &UrlMapping) -> Result<(), sqlx::Error> {
         sqlx::query(
             r#"
-            INSERT OR REPLACE INTO url_mappings (id, url_pattern, category_id, is_custom, created_at, updated_at)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            INSERT OR REPLACE INTO url_mappings (id, url_pattern, category_id, is_custom, match_kind, priority, created_at, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
             "#,
         )
         .bind(mapping.id.to_string())
         .bind(&mapping.url_pattern)
         .bind(&mapping.category_id)
         .bind(mapping.is_custom as i32)
+        .bind(mapping.match_kind.as_str())
+        .bind(mapping.priority)
         .bind(mapping.created_at.to_rfc3339())
         .bind(mapping.updated_at.to_rfc3339())
         .execute(&self.pool)
         .await?;
 
+        self.invalidate_compiled_mappings();
+
         Ok(())
     }
 
+    /// All URL mappings in precedence order; the first one that matches wins
     pub async fn get_url_mappings(&self) -> Result<Vec<UrlMapping>, sqlx::Error> {
         let rows = sqlx::query(
             r#"
-            SELECT id, url_pattern, category_id, is_custom, created_at, updated_at
+            SELECT id, url_pattern, category_id, is_custom, match_kind, priority, created_at, updated_at
             FROM url_mappings
-            ORDER BY created_at ASC
             "#,
         )
         .fetch_all(&self.pool)
@@ -685,11 +1799,14 @@ impl Database {
                 url_pattern: row.get("url_pattern"),
                 category_id: row.get("category_id"),
                 is_custom: row.get::<i32, _>("is_custom") != 0,
+                match_kind: MatchKind::from_db(&row.get::<String, _>("match_kind")),
+                priority: row.get("priority"),
                 created_at,
                 updated_at,
             });
         }
 
+        mappings::sort_url_mappings(&mut mappings);
         Ok(mappings)
     }
 
@@ -698,7 +1815,7 @@ impl Database {
         sqlx::query(
             r#"
             UPDATE url_mappings 
-            SET url_pattern = ?2, category_id = ?3, is_custom = ?4, updated_at = ?5
+            SET url_pattern = ?2, category_id = ?3, is_custom = ?4, match_kind = ?5, priority = ?6, updated_at = ?7
             WHERE id = ?1
             "#,
         )
@@ -706,10 +1823,14 @@ impl Database {
         .bind(&mapping.url_pattern)
         .bind(&mapping.category_id)
         .bind(mapping.is_custom as i32)
+        .bind(mapping.match_kind.as_str())
+        .bind(mapping.priority)
         .bind(mapping.updated_at.to_rfc3339())
         .execute(&self.pool)
         .await?;
 
+        self.invalidate_compiled_mappings();
+
         Ok(())
     }
 
@@ -724,6 +1845,8 @@ impl Database {
             .execute(&self.pool)
             .await?;
 
+        self.invalidate_compiled_mappings();
+
         Ok(())
     }
 
@@ -733,12 +1856,15 @@ impl Database {
         category_id: &str,
         url_pattern: &str,
         is_custom: bool,
+        match_kind: MatchKind,
     ) -> Result<(), sqlx::Error> {
         let mapping = UrlMapping {
             id: Uuid::new_v4(),
             url_pattern: url_pattern.to_string(),
             category_id: category_id.to_string(),
             is_custom,
+            match_kind,
+            priority: 0,
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
         };
@@ -746,6 +1872,357 @@ impl Database {
         self.add_url_mapping(&mapping).await
     }
 
+    // Window-title rule methods
+    pub async fn add_title_mapping(&self, mapping: &TitleMapping) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO title_mappings (id, title_pattern, app_pattern, category_id, is_custom, match_kind, priority, created_at, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+            "#,
+        )
+        .bind(mapping.id.to_string())
+        .bind(&mapping.title_pattern)
+        .bind(&mapping.app_pattern)
+        .bind(&mapping.category_id)
+        .bind(mapping.is_custom as i32)
+        .bind(mapping.match_kind.as_str())
+        .bind(mapping.priority)
+        .bind(mapping.created_at.to_rfc3339())
+        .bind(mapping.updated_at.to_rfc3339())
+        .execute(&self.pool)
+        .await?;
+
+        self.invalidate_compiled_mappings();
+
+        Ok(())
+    }
+
+    /// All window-title rules in precedence order; the first one that matches wins
+    pub async fn get_title_mappings(&self) -> Result<Vec<TitleMapping>, sqlx::Error> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, title_pattern, app_pattern, category_id, is_custom, match_kind, priority, created_at, updated_at
+            FROM title_mappings
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut mappings = Vec::new();
+        for row in rows {
+            let id = Uuid::parse_str(&row.get::<String, _>("id"))
+                .map_err(|_| sqlx::Error::Decode("Invalid UUID format".into()))?;
+            let created_at = DateTime::parse_from_rfc3339(&row.get::<String, _>("created_at"))
+                .map_err(|_| sqlx::Error::Decode("Invalid created_at format".into()))?
+                .with_timezone(&Utc);
+            let updated_at = DateTime::parse_from_rfc3339(&row.get::<String, _>("updated_at"))
+                .map_err(|_| sqlx::Error::Decode("Invalid updated_at format".into()))?
+                .with_timezone(&Utc);
+
+            mappings.push(TitleMapping {
+                id,
+                title_pattern: row.get("title_pattern"),
+                app_pattern: row.get("app_pattern"),
+                category_id: row.get("category_id"),
+                is_custom: row.get::<i32, _>("is_custom") != 0,
+                match_kind: MatchKind::from_db(&row.get::<String, _>("match_kind")),
+                priority: row.get("priority"),
+                created_at,
+                updated_at,
+            });
+        }
+
+        mappings::sort_title_mappings(&mut mappings);
+        Ok(mappings)
+    }
+
+    pub async fn delete_title_mapping(&self, id: &Uuid) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM title_mappings WHERE id = ?1")
+            .bind(id.to_string())
+            .execute(&self.pool)
+            .await?;
+
+        self.invalidate_compiled_mappings();
+
+        Ok(())
+    }
+
+    /// URL, title and app mappings compiled for matching, built on first use and kept
+    /// until a mapping changes
+    pub async fn get_compiled_mappings(&self) -> Result<Arc<CompiledMappings>, sqlx::Error> {
+        let cached = self.compiled_mappings.read().unwrap().clone();
+        if let Some(compiled) = cached {
+            return Ok(compiled);
+        }
+
+        let generation = self.mappings_generation.load(Ordering::Acquire);
+        let compiled = Arc::new(CompiledMappings::new(
+            &self.get_url_mappings().await?,
+            &self.get_title_mappings().await?,
+            &self.get_app_mappings().await?,
+        ));
+
+        let mut cache = self.compiled_mappings.write().unwrap();
+        if self.mappings_generation.load(Ordering::Acquire) == generation {
+            *cache = Some(Arc::clone(&compiled));
+        }
+        Ok(compiled)
+    }
+
+    /// Drop the compiled mappings so the next categorization reloads them
+    pub fn invalidate_compiled_mappings(&self) {
+        let mut cache = self.compiled_mappings.write().unwrap();
+        self.mappings_generation.fetch_add(1, Ordering::AcqRel);
+        *cache = None;
+    }
+
+    /// Mappings that match the same app name, URL or window title, tested against the
+    /// mappings' own patterns and recently tracked activity
+    pub async fn get_mapping_conflicts(&self) -> Result<Vec<MappingConflict>, sqlx::Error> {
+        let app_names: Vec<String> = sqlx::query(
+            r#"
+            SELECT app_name FROM activity_entries
+            GROUP BY app_name
+            ORDER BY MAX(start_time) DESC
+            LIMIT ?1
+            "#,
+        )
+        .bind(CONFLICT_SAMPLE_LIMIT)
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|row| row.get("app_name"))
+        .collect();
+
+        let urls: Vec<String> = sqlx::query(
+            r#"
+            SELECT url FROM activity_entries
+            WHERE url IS NOT NULL AND url != ''
+            GROUP BY url
+            ORDER BY MAX(start_time) DESC
+            LIMIT ?1
+            "#,
+        )
+        .bind(CONFLICT_SAMPLE_LIMIT)
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|row| row.get("url"))
+        .collect();
+
+        let titles: Vec<(String, String)> = sqlx::query(
+            r#"
+            SELECT app_name, window_title FROM activity_entries
+            WHERE window_title != ''
+            GROUP BY app_name, window_title
+            ORDER BY MAX(start_time) DESC
+            LIMIT ?1
+            "#,
+        )
+        .bind(CONFLICT_SAMPLE_LIMIT)
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .map(|row| (row.get("app_name"), row.get("window_title")))
+        .collect();
+
+        let mut conflicts = mappings::url_conflicts(&self.get_url_mappings().await?, &urls);
+        conflicts.extend(mappings::title_conflicts(
+            &self.get_title_mappings().await?,
+            &titles,
+        ));
+        conflicts.extend(mappings::app_conflicts(
+            &self.get_app_mappings().await?,
+            &app_names,
+        ));
+        Ok(conflicts)
+    }
+
+    /// Apps, domains and window titles that fell back to Unknown during `[start, end)`,
+    /// each list cut to the `limit` with the most time.
+    ///
+    /// Domains and titles are the ones each activity was categorized by, not those of its
+    /// segments. Activities the user recategorized by hand aren't included.
+    pub async fn get_uncategorized_activity(
+        &self,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+        limit: usize,
+    ) -> Result<UncategorizedReport, sqlx::Error> {
+        let now = Utc::now();
+        let activities = self.get_activities_in_window(start, end, now).await?;
+        let seconds = |from: DateTime<Utc>, to: Option<DateTime<Utc>>| {
+            (to.unwrap_or(now) - from).num_seconds().max(0)
+        };
+
+        let mut total_seconds = 0;
+        let mut apps: HashMap<String, UncategorizedItem> = HashMap::new();
+        let mut domains: HashMap<String, UncategorizedItem> = HashMap::new();
+        let mut titles: HashMap<(String, String), UncategorizedItem> = HashMap::new();
+        let add = |item: &mut UncategorizedItem, duration: i64, last_seen: DateTime<Utc>| {
+            item.duration_seconds += duration;
+            item.times_accessed += 1;
+            item.last_seen = item.last_seen.max(last_seen);
+        };
+
+        for activity in activities.iter().filter(|activity| {
+            activity.category.is_unknown() && activity.provenance.override_category.is_none()
+        }) {
+            let duration = seconds(activity.start_time, activity.end_time);
+            let last_seen = activity.end_time.unwrap_or(now);
+            total_seconds += duration;
+            add(
+                apps.entry(activity.app_name.clone())
+                    .or_insert_with(|| uncategorized(MappingTarget::App, &activity.app_name, None)),
+                duration,
+                last_seen,
+            );
+
+            // Mappings are applied to the activity's own title and URL, not its segments, so
+            // only those are listed; a rule for any of them also recategorizes the activity
+            let domain = activity
+                .url
+                .as_deref()
+                .and_then(pattern::parse_url)
+                .and_then(|url| url.host_str().map(str::to_lowercase));
+            if let Some(domain) = domain {
+                let domain = domain.trim_start_matches("www.").to_string();
+                add(
+                    domains
+                        .entry(domain.clone())
+                        .or_insert_with(|| uncategorized(MappingTarget::Url, &domain, None)),
+                    duration,
+                    last_seen,
+                );
+            }
+
+            let title = activity.window_title.trim();
+            if !title.is_empty() {
+                add(
+                    titles
+                        .entry((activity.app_name.clone(), title.to_string()))
+                        .or_insert_with(|| {
+                            uncategorized(MappingTarget::Title, title, Some(&activity.app_name))
+                        }),
+                    duration,
+                    last_seen,
+                );
+            }
+        }
+
+        let top = |mut items: Vec<UncategorizedItem>| {
+            items.sort_by(|a, b| {
+                b.duration_seconds
+                    .cmp(&a.duration_seconds)
+                    .then_with(|| a.value.cmp(&b.value))
+            });
+            items.truncate(limit);
+            items
+        };
+
+        Ok(UncategorizedReport {
+            start_time: start,
+            end_time: end,
+            total_seconds,
+            apps: top(apps.into_values().collect()),
+            domains: top(domains.into_values().collect()),
+            titles: top(titles.into_values().collect()),
+        })
+    }
+
+    /// App and URL mappings suggested by the user's manual recategorizations, see
+    /// [`mappings::suggest_mappings`]
+    pub async fn get_mapping_suggestions(
+        &self,
+        min_corrections: usize,
+    ) -> Result<Vec<MappingSuggestion>, sqlx::Error> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, start_time, end_time, app_name, app_bundle_id, window_title, url, category,
+                   computed_category, computed_source, computed_mapping_id, category_override, category_overridden_at
+            FROM activity_entries
+            WHERE category_override IS NOT NULL
+              AND computed_category IS NOT NULL
+              AND category_override != computed_category
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        let corrected: Vec<ActivityEntry> = rows.iter().map(Self::activity_from_row).collect();
+
+        let compiled = self.get_compiled_mappings().await?;
+        Ok(mappings::suggest_mappings(
+            &corrected,
+            &compiled,
+            min_corrections,
+        ))
+    }
+
+    /// The priority a new custom mapping needs to beat the mapping that wins today for
+    /// `value`: a domain, an app name, or a window title of `app_name`. Seeded mappings
+    /// already lose to custom ones, so only a custom winner raises it above 0.
+    pub async fn priority_to_win(
+        &self,
+        target: MappingTarget,
+        value: &str,
+        app_name: Option<&str>,
+    ) -> Result<i32, sqlx::Error> {
+        let compiled = self.get_compiled_mappings().await?;
+        let current = match target {
+            MappingTarget::Url => compiled.best_url(&format!("https://{}/", value)),
+            MappingTarget::Title => compiled.best_title(app_name.unwrap_or_default(), value),
+            MappingTarget::App => compiled.best_app(value, None),
+        };
+        Ok(current
+            .filter(|mapping| mapping.is_custom)
+            .map_or(0, |mapping| mapping.priority.saturating_add(1).max(0)))
+    }
+
+    /// Create the custom mapping a suggestion proposes, with a priority that lets it win
+    pub async fn accept_mapping_suggestion(
+        &self,
+        suggestion: &MappingSuggestion,
+    ) -> Result<MappingRef, sqlx::Error> {
+        let now = Utc::now();
+        let priority = self
+            .priority_to_win(suggestion.target, &suggestion.pattern, None)
+            .await?;
+        match suggestion.target {
+            MappingTarget::Url => {
+                let mapping = UrlMapping {
+                    id: Uuid::new_v4(),
+                    url_pattern: suggestion.pattern.clone(),
+                    category_id: suggestion.category_id.clone(),
+                    is_custom: true,
+                    match_kind: suggestion.match_kind,
+                    priority,
+                    created_at: now,
+                    updated_at: now,
+                };
+                self.add_url_mapping(&mapping).await?;
+                Ok(MappingRef::from(&mapping))
+            }
+            MappingTarget::App => {
+                let mapping = AppMapping {
+                    id: Uuid::new_v4(),
+                    app_pattern: suggestion.pattern.clone(),
+                    category_id: suggestion.category_id.clone(),
+                    is_custom: true,
+                    match_kind: suggestion.match_kind,
+                    priority,
+                    identifiers: None,
+                    created_at: now,
+                    updated_at: now,
+                };
+                self.add_app_mapping(&mapping).await?;
+                Ok(MappingRef::from(&mapping))
+            }
+            MappingTarget::Title => Err(sqlx::Error::Decode(
+                "Only app and URL mappings are suggested".into(),
+            )),
+        }
+    }
+
     // Focus Mode Database Functions
 
     /// Get focus mode enabled status
@@ -842,6 +2319,24 @@ impl Database {
         Ok(rows.into_iter().map(|row| row.get("category_id")).collect())
     }
 
+    /// Allowed category IDs for focus mode together with all their subcategories
+    pub async fn get_focus_mode_effective_categories(&self) -> Result<Vec<String>, sqlx::Error> {
+        let rows = sqlx::query(
+            r#"
+            WITH RECURSIVE allowed(id) AS (
+                SELECT category_id FROM focus_mode_allowed_categories
+                UNION
+                SELECT c.id FROM user_categories c JOIN allowed a ON c.parent_id = a.id
+            )
+            SELECT id FROM allowed ORDER BY id
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| row.get("id")).collect())
+    }
+
     /// Set allowed category IDs for focus mode (replaces existing)
     pub async fn set_focus_mode_allowed_categories(
         &self,
@@ -863,6 +2358,152 @@ impl Database {
         Ok(())
     }
 
+    /// `ids` together with all their subcategories
+    pub async fn expand_category_ids(&self, ids: &[String]) -> Result<Vec<String>, sqlx::Error> {
+        let mut expanded = ids.to_vec();
+        for id in ids {
+            expanded.extend(self.get_category_descendants(id).await?);
+        }
+        expanded.sort();
+        expanded.dedup();
+        Ok(expanded)
+    }
+
+    pub async fn get_focus_schedules(&self) -> Result<Vec<FocusSchedule>, sqlx::Error> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, name, days, start_time, end_time, enabled, created_at, updated_at
+            FROM focus_schedules
+            ORDER BY start_time, name
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut categories: HashMap<String, Vec<String>> = HashMap::new();
+        for row in sqlx::query(
+            "SELECT schedule_id, category_id FROM focus_schedule_categories ORDER BY category_id",
+        )
+        .fetch_all(&self.pool)
+        .await?
+        {
+            categories
+                .entry(row.get("schedule_id"))
+                .or_default()
+                .push(row.get("category_id"));
+        }
+
+        let parse_time = |value: String| {
+            NaiveTime::parse_from_str(&value, "%H:%M")
+                .map_err(|_| sqlx::Error::Decode("Invalid focus schedule time".into()))
+        };
+        let parse_timestamp = |value: String| {
+            DateTime::parse_from_rfc3339(&value)
+                .map(|dt| dt.with_timezone(&Utc))
+                .map_err(|_| sqlx::Error::Decode("Invalid focus schedule timestamp".into()))
+        };
+
+        let mut schedules = Vec::new();
+        for row in rows {
+            let id: String = row.get("id");
+            schedules.push(FocusSchedule {
+                id: Uuid::parse_str(&id)
+                    .map_err(|_| sqlx::Error::Decode("Invalid focus schedule id".into()))?,
+                name: row.get("name"),
+                days: row
+                    .get::<String, _>("days")
+                    .split(',')
+                    .filter_map(|day| day.trim().parse::<u8>().ok())
+                    .filter_map(|day| Weekday::try_from(day).ok())
+                    .collect(),
+                start_time: parse_time(row.get("start_time"))?,
+                end_time: parse_time(row.get("end_time"))?,
+                allowed_categories: categories.remove(&id).unwrap_or_default(),
+                enabled: row.get::<i64, _>("enabled") != 0,
+                created_at: parse_timestamp(row.get("created_at"))?,
+                updated_at: parse_timestamp(row.get("updated_at"))?,
+            });
+        }
+
+        Ok(schedules)
+    }
+
+    /// Create a focus schedule or replace the one with the same id
+    pub async fn save_focus_schedule(&self, schedule: &FocusSchedule) -> Result<(), sqlx::Error> {
+        let days = schedule
+            .days
+            .iter()
+            .map(|day| day.num_days_from_monday().to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO focus_schedules (id, name, days, start_time, end_time, enabled, created_at, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            ON CONFLICT(id) DO UPDATE SET
+                name = excluded.name,
+                days = excluded.days,
+                start_time = excluded.start_time,
+                end_time = excluded.end_time,
+                enabled = excluded.enabled,
+                updated_at = excluded.updated_at
+            "#,
+        )
+        .bind(schedule.id.to_string())
+        .bind(&schedule.name)
+        .bind(days)
+        .bind(schedule.start_time.format("%H:%M").to_string())
+        .bind(schedule.end_time.format("%H:%M").to_string())
+        .bind(schedule.enabled)
+        .bind(schedule.created_at.to_rfc3339())
+        .bind(schedule.updated_at.to_rfc3339())
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query("DELETE FROM focus_schedule_categories WHERE schedule_id = ?1")
+            .bind(schedule.id.to_string())
+            .execute(&mut *tx)
+            .await?;
+        for category_id in &schedule.allowed_categories {
+            sqlx::query(
+                "INSERT OR IGNORE INTO focus_schedule_categories (schedule_id, category_id) VALUES (?1, ?2)",
+            )
+            .bind(schedule.id.to_string())
+            .bind(category_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
+    /// Delete a focus schedule; a missing one is `RowNotFound`
+    pub async fn delete_focus_schedule(&self, id: &Uuid) -> Result<(), sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("DELETE FROM focus_schedule_categories WHERE schedule_id = ?1")
+            .bind(id.to_string())
+            .execute(&mut *tx)
+            .await?;
+        let deleted = sqlx::query("DELETE FROM focus_schedules WHERE id = ?1")
+            .bind(id.to_string())
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        if deleted == 0 {
+            return Err(sqlx::Error::RowNotFound);
+        }
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
     /// Add a temporarily allowed app (with expiry)
     pub async fn add_focus_mode_allowed_app(
         &self,
@@ -1004,6 +2645,66 @@ impl Database {
         Ok(())
     }
 
+    /// Get idle threshold setting (seconds without input before the user counts as away)
+    pub async fn get_idle_threshold_seconds(&self) -> Result<u64, sqlx::Error> {
+        let row = sqlx::query(
+            "SELECT value FROM focus_mode_settings WHERE key = 'idle_threshold_seconds'",
+        )
+        .fetch_optional(&self.pool)
+        .await?;
+
+        let default = crate::tracker::DEFAULT_IDLE_THRESHOLD_SECONDS;
+        if let Some(row) = row {
+            let value: String = row.get("value");
+            Ok(value.parse::<u64>().unwrap_or(default))
+        } else {
+            Ok(default)
+        }
+    }
+
+    /// Set idle threshold setting
+    pub async fn set_idle_threshold_seconds(&self, seconds: u64) -> Result<(), sqlx::Error> {
+        let value = seconds.to_string();
+        sqlx::query(
+            "INSERT OR REPLACE INTO focus_mode_settings (key, value) VALUES ('idle_threshold_seconds', ?)",
+        )
+        .bind(value)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Get the user's timezone, falling back to the system timezone when unset
+    pub async fn get_timezone(&self) -> Result<Tz, sqlx::Error> {
+        let row = sqlx::query("SELECT value FROM focus_mode_settings WHERE key = 'timezone'")
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row
+            .and_then(|row| row.get::<String, _>("value").parse::<Tz>().ok())
+            .unwrap_or_else(system_timezone))
+    }
+
+    /// Set the user's timezone; `None` goes back to following the system timezone
+    pub async fn set_timezone(&self, tz: Option<Tz>) -> Result<(), sqlx::Error> {
+        match tz {
+            Some(tz) => {
+                sqlx::query(
+                    "INSERT OR REPLACE INTO focus_mode_settings (key, value) VALUES ('timezone', ?)",
+                )
+                .bind(tz.name())
+                .execute(&self.pool)
+                .await?;
+            }
+            None => {
+                sqlx::query("DELETE FROM focus_mode_settings WHERE key = 'timezone'")
+                    .execute(&self.pool)
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
     // Pomodoro session methods
     pub async fn save_pomodoro_session(
         &self,
@@ -1116,10 +2817,11 @@ impl Database {
 
     pub async fn get_pomodoro_sessions(
         &self,
-        start_date: Option<String>,
-        end_date: Option<String>,
+        start_date: Option<NaiveDate>,
+        end_date: Option<NaiveDate>,
         session_type: Option<crate::models::PomodoroSessionType>,
         limit: Option<i64>,
+        tz: &Tz,
     ) -> Result<Vec<crate::models::PomodoroSession>, sqlx::Error> {
         let mut query = String::from(
             r#"
@@ -1134,13 +2836,13 @@ impl Database {
         let mut bind_params: Vec<String> = Vec::new();
 
         if let Some(start) = start_date {
-            query.push_str(" AND DATE(start_time) >= ?");
-            bind_params.push(start);
+            query.push_str(" AND start_time >= ?");
+            bind_params.push(day_bounds(start, tz).0.to_rfc3339());
         }
 
         if let Some(end) = end_date {
-            query.push_str(" AND DATE(start_time) <= ?");
-            bind_params.push(end);
+            query.push_str(" AND start_time < ?");
+            bind_params.push(day_bounds(end, tz).1.to_rfc3339());
         }
 
         if let Some(s_type) = session_type {
@@ -1259,12 +2961,27 @@ impl Database {
         Ok(())
     }
 
+    /// Pomodoro totals, plus a breakdown per local calendar day a session started on
     pub async fn get_pomodoro_summary(
         &self,
-        start_date: Option<String>,
-        end_date: Option<String>,
+        start_date: Option<NaiveDate>,
+        end_date: Option<NaiveDate>,
+        tz: &Tz,
     ) -> Result<crate::models::PomodoroSummary, sqlx::Error> {
-        let mut query = String::from(
+        let mut filter = String::new();
+        let mut bind_params: Vec<String> = Vec::new();
+
+        if let Some(start) = start_date {
+            filter.push_str(" AND start_time >= ?");
+            bind_params.push(day_bounds(start, tz).0.to_rfc3339());
+        }
+
+        if let Some(end) = end_date {
+            filter.push_str(" AND start_time < ?");
+            bind_params.push(day_bounds(end, tz).1.to_rfc3339());
+        }
+
+        let query = format!(
             r#"
             SELECT 
                 COUNT(*) as total_sessions,
@@ -1273,22 +2990,11 @@ impl Database {
                 SUM(CASE WHEN session_type = 'break' AND completed = 1 THEN COALESCE(actual_duration_seconds, duration_minutes * 60) ELSE 0 END) as total_break_time,
                 AVG(CASE WHEN completed = 1 THEN COALESCE(actual_duration_seconds, duration_minutes * 60) ELSE NULL END) as avg_duration
             FROM pomodoro_sessions 
-            WHERE 1=1
+            WHERE 1=1{}
             "#,
+            filter
         );
 
-        let mut bind_params: Vec<String> = Vec::new();
-
-        if let Some(start) = &start_date {
-            query.push_str(" AND DATE(start_time) >= ?");
-            bind_params.push(start.clone());
-        }
-
-        if let Some(end) = &end_date {
-            query.push_str(" AND DATE(start_time) <= ?");
-            bind_params.push(end.clone());
-        }
-
         let mut sql_query = sqlx::query(&query);
         for param in &bind_params {
             sql_query = sql_query.bind(param);
@@ -1296,47 +3002,55 @@ impl Database {
 
         let row = sql_query.fetch_one(&self.pool).await?;
 
-        // Get sessions by date
-        let mut date_query = String::from(
+        // Sessions by date: SQLite's DATE() would bucket by UTC day, so group in local time here
+        let date_query = format!(
             r#"
             SELECT 
-                DATE(start_time) as date,
-                SUM(CASE WHEN session_type = 'work' THEN 1 ELSE 0 END) as work_sessions,
-                SUM(CASE WHEN session_type = 'break' THEN 1 ELSE 0 END) as break_sessions,
-                SUM(CASE WHEN session_type = 'work' AND completed = 1 THEN COALESCE(actual_duration_seconds, duration_minutes * 60) ELSE 0 END) as work_time,
-                SUM(CASE WHEN session_type = 'break' AND completed = 1 THEN COALESCE(actual_duration_seconds, duration_minutes * 60) ELSE 0 END) as break_time
+                start_time,
+                session_type,
+                CASE WHEN completed = 1 THEN COALESCE(actual_duration_seconds, duration_minutes * 60) ELSE 0 END as counted_seconds
             FROM pomodoro_sessions 
-            WHERE 1=1
+            WHERE 1=1{}
             "#,
+            filter
         );
 
-        if start_date.is_some() {
-            date_query.push_str(" AND DATE(start_time) >= ?");
-        }
-
-        if end_date.is_some() {
-            date_query.push_str(" AND DATE(start_time) <= ?");
-        }
-
-        date_query.push_str(" GROUP BY DATE(start_time) ORDER BY DATE(start_time)");
-
         let mut date_sql_query = sqlx::query(&date_query);
-        for param in bind_params {
+        for param in &bind_params {
             date_sql_query = date_sql_query.bind(param);
         }
 
         let date_rows = date_sql_query.fetch_all(&self.pool).await?;
 
-        let sessions_by_date = date_rows
-            .into_iter()
-            .map(|row| crate::models::PomodoroDateSummary {
-                date: row.get("date"),
-                work_sessions: row.get("work_sessions"),
-                break_sessions: row.get("break_sessions"),
-                total_work_time_seconds: row.get("work_time"),
-                total_break_time_seconds: row.get("break_time"),
-            })
-            .collect();
+        let mut by_date: std::collections::BTreeMap<NaiveDate, crate::models::PomodoroDateSummary> =
+            std::collections::BTreeMap::new();
+        for row in date_rows {
+            let start_time = DateTime::parse_from_rfc3339(&row.get::<String, _>("start_time"))
+                .unwrap()
+                .with_timezone(&Utc);
+            let date = local_date(start_time, tz);
+            let counted_seconds: i64 = row.get("counted_seconds");
+
+            let day = by_date
+                .entry(date)
+                .or_insert_with(|| crate::models::PomodoroDateSummary {
+                    date: date.to_string(),
+                    work_sessions: 0,
+                    break_sessions: 0,
+                    total_work_time_seconds: 0,
+                    total_break_time_seconds: 0,
+                });
+            match row.get::<String, _>("session_type").as_str() {
+                "break" => {
+                    day.break_sessions += 1;
+                    day.total_break_time_seconds += counted_seconds;
+                }
+                _ => {
+                    day.work_sessions += 1;
+                    day.total_work_time_seconds += counted_seconds;
+                }
+            }
+        }
 
         let summary = crate::models::PomodoroSummary {
             total_sessions: row.get("total_sessions"),
@@ -1344,7 +3058,7 @@ impl Database {
             total_work_time_seconds: row.get("total_work_time"),
             total_break_time_seconds: row.get("total_break_time"),
             average_session_duration: row.get::<Option<f64>, _>("avg_duration").unwrap_or(0.0),
-            sessions_by_date,
+            sessions_by_date: by_date.into_values().collect(),
         };
 
         Ok(summary)
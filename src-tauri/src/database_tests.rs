@@ -1,11 +1,15 @@
 #[cfg(test)]
-mod database_tests {
+mod tests {
     use crate::database::Database;
     use crate::models::*;
-    use chrono::{NaiveDate, Utc};
+    use crate::timezone::day_bounds;
+    use chrono::{FixedOffset, NaiveDate, Utc};
+    use chrono_tz::Tz;
     use uuid::Uuid;
 
-    use crate::test_config::test_utils::create_test_database;
+    use crate::activity::reapply_mappings;
+    use crate::commands::mapping::triage_pattern;
+    use crate::test_config::test_utils::{create_sample_category, create_test_database};
 
     async fn create_test_db() -> Database {
         create_test_database().await
@@ -15,7 +19,147 @@ mod database_tests {
     async fn test_database_creation() {
         let _db = create_test_db().await;
         // If we get here without panicking, database creation succeeded
-        assert!(true);
+    }
+
+    #[tokio::test]
+    async fn test_category_ids_migration_rewrites_legacy_values() {
+        use crate::migrations::{apply_migrations, get_migrations};
+        use sqlx::SqlitePool;
+
+        // A database from before category ids, with the serialized enum still stored
+        let pool = SqlitePool::connect("sqlite::memory:").await.unwrap();
+        sqlx::query(
+            "CREATE TABLE schema_migrations (
+                version INTEGER PRIMARY KEY,
+                description TEXT NOT NULL,
+                applied_at TEXT NOT NULL
+            )",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        for migration in get_migrations().into_iter().filter(|m| m.version < 13) {
+            sqlx::query(&migration.sql).execute(&pool).await.unwrap();
+            sqlx::query(
+                "INSERT INTO schema_migrations (version, description, applied_at) VALUES (?, ?, ?)",
+            )
+            .bind(migration.version)
+            .bind(&migration.description)
+            .bind(Utc::now().to_rfc3339())
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let legacy = [
+            ("development", r#""Development""#),
+            ("custom-id", r#"{"Custom":"custom-id"}"#),
+            ("unknown", r#""Unknown""#),
+        ];
+        let mapping_tables = [
+            ("app_mappings", "app_pattern"),
+            ("url_mappings", "url_pattern"),
+            ("title_mappings", "title_pattern"),
+        ];
+        for (i, (_, value)) in legacy.iter().enumerate() {
+            sqlx::query(
+                "INSERT INTO activity_entries
+                    (id, start_time, app_name, window_title, category, computed_category, category_override)
+                 VALUES (?1, ?2, 'App', 'Title', ?3, ?3, ?3)",
+            )
+            .bind(i.to_string())
+            .bind(Utc::now().to_rfc3339())
+            .bind(value)
+            .execute(&pool)
+            .await
+            .unwrap();
+            for (table, pattern) in mapping_tables {
+                sqlx::query(&format!(
+                    "INSERT INTO {} (id, {}, category_id, is_custom, created_at, updated_at)
+                     VALUES (?1, ?1, ?2, 1, ?3, ?3)",
+                    table, pattern
+                ))
+                .bind(format!("legacy-{}", i))
+                .bind(value)
+                .bind(Utc::now().to_rfc3339())
+                .execute(&pool)
+                .await
+                .unwrap();
+            }
+            sqlx::query("INSERT INTO focus_mode_allowed_categories (category_id) VALUES (?1)")
+                .bind(value)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+        // Mappings were matched case-insensitively, so plain names were stored too
+        sqlx::query(
+            "INSERT INTO app_mappings (id, app_pattern, category_id, is_custom, created_at, updated_at)
+             VALUES ('plain', 'plain', 'Social', 1, '', '')",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO focus_mode_allowed_categories (category_id) VALUES ('Development')",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        apply_migrations(&pool).await.unwrap();
+
+        for (i, (id, _)) in legacy.iter().enumerate() {
+            let row: (String, String, String) = sqlx::query_as(
+                "SELECT category, computed_category, category_override FROM activity_entries WHERE id = ?1",
+            )
+            .bind(i.to_string())
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+            assert_eq!(row, (id.to_string(), id.to_string(), id.to_string()));
+
+            for (table, _) in mapping_tables {
+                let category_id: String =
+                    sqlx::query_scalar(&format!("SELECT category_id FROM {} WHERE id = ?1", table))
+                        .bind(format!("legacy-{}", i))
+                        .fetch_one(&pool)
+                        .await
+                        .unwrap();
+                assert_eq!(category_id, *id, "{}", table);
+            }
+        }
+        let plain: String =
+            sqlx::query_scalar("SELECT category_id FROM app_mappings WHERE id = 'plain'")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(plain, "social");
+
+        let focus: Vec<String> = sqlx::query_scalar(
+            "SELECT category_id FROM focus_mode_allowed_categories ORDER BY category_id",
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(focus, vec!["custom-id", "development", "unknown"]);
+
+        // Productivity weights come from categories.json
+        let weights: Vec<(String, i64)> = sqlx::query_as(
+            "SELECT id, productivity FROM user_categories
+             WHERE id IN ('development', 'social', 'utility') ORDER BY id",
+        )
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(
+            weights,
+            vec![
+                ("development".to_string(), 2),
+                ("social".to_string(), -1),
+                ("utility".to_string(), 0)
+            ]
+        );
     }
 
     #[tokio::test]
@@ -31,7 +175,8 @@ mod database_tests {
             app_bundle_id: Some("com.test.app".to_string()),
             window_title: "Test Window".to_string(),
             url: Some("https://example.com".to_string()),
-            category: ActivityCategory::Development,
+            category: ActivityCategory::new("development"),
+            provenance: CategoryProvenance::default(),
             segments: vec![],
         };
 
@@ -70,6 +215,7 @@ mod database_tests {
             name: "Work".to_string(),
             color: "#FF0000".to_string(),
             parent_id: None,
+            productivity: Productivity::default(),
             created_at: Utc::now(),
             updated_at: Utc::now(),
         };
@@ -103,7 +249,9 @@ mod database_tests {
         assert_eq!(updated_category.color, "#00FF00");
 
         // Test deleting category
-        let result = db.delete_user_category(&category.id).await;
+        let result = db
+            .delete_user_category(&category.id, "unknown", false)
+            .await;
         assert!(result.is_ok());
 
         // Verify deletion
@@ -122,6 +270,7 @@ mod database_tests {
             name: "Development".to_string(),
             color: "#0000FF".to_string(),
             parent_id: None,
+            productivity: Productivity::default(),
             created_at: Utc::now(),
             updated_at: Utc::now(),
         };
@@ -133,7 +282,12 @@ mod database_tests {
 
         // Test adding app mapping
         let result = db
-            .add_simple_app_mapping(&category.id, "Visual Studio Code", true)
+            .add_simple_app_mapping(
+                &category.id,
+                "Visual Studio Code",
+                true,
+                MatchKind::Substring,
+            )
             .await;
         assert!(result.is_ok());
 
@@ -161,6 +315,87 @@ mod database_tests {
         assert_eq!(mappings.len(), initial_count);
     }
 
+    #[tokio::test]
+    async fn test_update_app_mapping_keeps_stored_settings() {
+        use crate::commands::mapping::updated_app_mapping;
+
+        let db = create_test_db().await;
+        let work = create_sample_category("Work", "#FF0000");
+        let development = create_sample_category("Development", "#00FF00");
+        db.add_user_category(&work).await.unwrap();
+        db.add_user_category(&development).await.unwrap();
+
+        db.add_app_mapping(&AppMapping {
+            id: Uuid::new_v4(),
+            app_pattern: "Zed".to_string(),
+            category_id: work.id.clone(),
+            is_custom: false,
+            match_kind: MatchKind::Exact,
+            identifiers: Some("dev.zed.Zed".to_string()),
+            priority: 3,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        })
+        .await
+        .unwrap();
+
+        // Only the category changes; kind, priority and identifiers stay as stored
+        let mappings = db.get_app_mappings().await.unwrap();
+        let (mapping, replaced) = updated_app_mapping(
+            &mappings,
+            "Zed".to_string(),
+            development.id.clone(),
+            None,
+            None,
+            None,
+        );
+        assert_eq!(mapping.match_kind, MatchKind::Exact);
+        assert_eq!(mapping.priority, 3);
+        assert_eq!(mapping.identifiers.as_deref(), Some("dev.zed.Zed"));
+        let replaced = replaced.unwrap();
+        assert_eq!(replaced.category_id, work.id);
+
+        db.remove_app_mapping(&replaced.category_id, &replaced.app_pattern)
+            .await
+            .unwrap();
+        db.add_app_mapping(&mapping).await.unwrap();
+        let stored: Vec<_> = db
+            .get_app_mappings()
+            .await
+            .unwrap()
+            .into_iter()
+            .filter(|m| m.app_pattern == "Zed")
+            .collect();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].category_id, development.id);
+        assert_eq!(stored[0].match_kind, MatchKind::Exact);
+
+        // Given settings still win, and an empty string clears the identifiers
+        let (mapping, _) = updated_app_mapping(
+            &db.get_app_mappings().await.unwrap(),
+            "Zed".to_string(),
+            development.id.clone(),
+            Some(MatchKind::Glob),
+            Some(0),
+            Some(String::new()),
+        );
+        assert_eq!(mapping.match_kind, MatchKind::Glob);
+        assert_eq!(mapping.priority, 0);
+        assert_eq!(mapping.identifiers, None);
+
+        // A new pattern gets the defaults
+        let (mapping, replaced) = updated_app_mapping(
+            &mappings,
+            "Helix".to_string(),
+            development.id.clone(),
+            None,
+            None,
+            None,
+        );
+        assert!(replaced.is_none());
+        assert_eq!(mapping.match_kind, MatchKind::default());
+    }
+
     #[tokio::test]
     async fn test_url_mappings() {
         let db = create_test_db().await;
@@ -171,6 +406,7 @@ mod database_tests {
             name: "Social".to_string(),
             color: "#FF00FF".to_string(),
             parent_id: None,
+            productivity: Productivity::default(),
             created_at: Utc::now(),
             updated_at: Utc::now(),
         };
@@ -187,6 +423,8 @@ mod database_tests {
             url_pattern: unique_url.clone(),
             category_id: category.id.clone(),
             is_custom: true,
+            match_kind: MatchKind::Substring,
+            priority: 0,
             created_at: Utc::now(),
             updated_at: Utc::now(),
         };
@@ -274,6 +512,368 @@ mod database_tests {
         assert!(!categories.contains(&"work".to_string()));
     }
 
+    #[tokio::test]
+    async fn test_category_hierarchy() {
+        let db = create_test_db().await;
+        let category = |name: &str, parent_id: Option<&str>| UserCategory {
+            id: Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            color: "#123456".to_string(),
+            parent_id: parent_id.map(str::to_string),
+            productivity: Productivity::default(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        let work = category("Work", None);
+        let review = category("Code Review", Some(&work.id));
+        let security = category("Security Review", Some(&review.id));
+        for category in [&work, &review, &security] {
+            db.add_user_category(category).await.unwrap();
+        }
+
+        let mut descendants = vec![review.id.clone(), security.id.clone()];
+        descendants.sort();
+        assert_eq!(
+            db.get_category_descendants(&work.id).await.unwrap(),
+            descendants
+        );
+        assert!(db
+            .get_category_descendants(&security.id)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // Allowing a category in focus mode allows its subcategories
+        db.set_focus_mode_allowed_categories(std::slice::from_ref(&review.id))
+            .await
+            .unwrap();
+        let effective = db.get_focus_mode_effective_categories().await.unwrap();
+        assert_eq!(effective.len(), 2);
+        assert!(effective.contains(&review.id));
+        assert!(effective.contains(&security.id));
+        assert!(!effective.contains(&work.id));
+
+        // Subcategory time rolls up into every ancestor
+        let at = |h, m| {
+            NaiveDate::from_ymd_opt(2024, 1, 15)
+                .unwrap()
+                .and_hms_opt(h, m, 0)
+                .unwrap()
+                .and_utc()
+        };
+        for (category_id, start, end) in [
+            (&review.id, at(9, 0), at(10, 0)),
+            (&security.id, at(10, 0), at(10, 30)),
+        ] {
+            db.start_activity(&ActivityEntry {
+                id: Uuid::new_v4(),
+                start_time: start,
+                end_time: Some(end),
+                app_name: "Browser".to_string(),
+                app_bundle_id: None,
+                window_title: "Pull request".to_string(),
+                url: None,
+                category: ActivityCategory::new(category_id.clone()),
+                provenance: CategoryProvenance::default(),
+                segments: vec![],
+            })
+            .await
+            .unwrap();
+        }
+
+        let summary = db
+            .get_activity_summary(at(0, 0).date_naive(), &Tz::UTC)
+            .await
+            .unwrap();
+        let find = |id: &str| {
+            summary
+                .categories
+                .iter()
+                .find(|c| c.category == ActivityCategory::new(id.to_string()))
+                .unwrap()
+        };
+        assert_eq!(summary.categories.len(), 3);
+        assert_eq!(find(&work.id).duration_seconds, 0);
+        assert_eq!(find(&work.id).total_duration_seconds, 5400);
+        assert_eq!(find(&work.id).total_percentage, 100.0);
+        assert_eq!(find(&work.id).parent_id, None);
+        assert_eq!(find(&review.id).duration_seconds, 3600);
+        assert_eq!(find(&review.id).total_duration_seconds, 5400);
+        assert_eq!(find(&review.id).parent_id, Some(work.id.clone()));
+        assert_eq!(find(&security.id).total_duration_seconds, 1800);
+
+        // Deleting a category moves its subcategories to the target
+        db.delete_user_category(&review.id, &work.id, false)
+            .await
+            .unwrap();
+        let security = db
+            .get_user_category_by_id(&security.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(security.parent_id, Some(work.id.clone()));
+    }
+
+    #[tokio::test]
+    async fn test_delete_category_reassigns_references() {
+        let db = create_test_db().await;
+        let category = |name: &str, parent_id: Option<&str>| UserCategory {
+            id: Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            color: "#123456".to_string(),
+            parent_id: parent_id.map(str::to_string),
+            productivity: Productivity::default(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        let reading = category("Reading", None);
+        let papers = category("Papers", Some(&reading.id));
+        let study = category("Study", None);
+        for category in [&reading, &papers, &study] {
+            db.add_user_category(category).await.unwrap();
+        }
+
+        db.add_simple_app_mapping(&reading.id, "Kindle", true, MatchKind::Exact)
+            .await
+            .unwrap();
+        db.add_url_mapping(&crate::test_config::test_utils::create_sample_url_mapping(
+            &reading.id,
+            "arxiv.org",
+        ))
+        .await
+        .unwrap();
+        db.set_focus_mode_allowed_categories(&[reading.id.clone(), study.id.clone()])
+            .await
+            .unwrap();
+
+        let start_time = NaiveDate::from_ymd_opt(2024, 3, 1)
+            .unwrap()
+            .and_hms_opt(9, 0, 0)
+            .unwrap()
+            .and_utc();
+        let mut activity = ActivityEntry {
+            id: Uuid::new_v4(),
+            start_time,
+            end_time: Some(start_time + chrono::Duration::minutes(30)),
+            app_name: "Kindle".to_string(),
+            app_bundle_id: None,
+            window_title: "Book".to_string(),
+            url: None,
+            category: ActivityCategory::new(reading.id.clone()),
+            provenance: CategoryProvenance::default(),
+            segments: vec![],
+        };
+        db.start_activity(&activity).await.unwrap();
+        activity.id = Uuid::new_v4();
+        activity.category = ActivityCategory::unknown();
+        db.start_activity(&activity).await.unwrap();
+        db.update_activity_category(
+            &activity.id.to_string(),
+            &ActivityCategory::new(reading.id.clone()),
+        )
+        .await
+        .unwrap();
+
+        let expected = |dry_run| CategoryDeletionReport {
+            category_id: reading.id.clone(),
+            target_category_id: study.id.clone(),
+            dry_run,
+            app_mappings: 1,
+            url_mappings: 1,
+            title_mappings: 0,
+            activities: 2,
+            recategorization_changes: 0,
+            focus_mode_categories: 1,
+            subcategories: 1,
+            deleted_mappings: 0,
+        };
+
+        // A dry run counts the rows but leaves them alone
+        let report = db
+            .delete_user_category(&reading.id, &study.id, true)
+            .await
+            .unwrap();
+        assert_eq!(
+            serde_json::to_value(&report).unwrap(),
+            serde_json::to_value(expected(true)).unwrap()
+        );
+        assert!(db
+            .get_user_category_by_id(&reading.id)
+            .await
+            .unwrap()
+            .is_some());
+        assert_eq!(
+            db.get_focus_mode_allowed_categories().await.unwrap().len(),
+            2
+        );
+
+        let report = db
+            .delete_user_category(&reading.id, &study.id, false)
+            .await
+            .unwrap();
+        assert_eq!(
+            serde_json::to_value(&report).unwrap(),
+            serde_json::to_value(expected(false)).unwrap()
+        );
+        assert!(db
+            .get_user_category_by_id(&reading.id)
+            .await
+            .unwrap()
+            .is_none());
+
+        let app_mapping = db
+            .get_app_mappings()
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|m| m.app_pattern == "Kindle")
+            .unwrap();
+        assert_eq!(app_mapping.category_id, study.id);
+        let url_mapping = db
+            .get_url_mappings()
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|m| m.url_pattern == "arxiv.org")
+            .unwrap();
+        assert_eq!(url_mapping.category_id, study.id);
+
+        let activities = db
+            .get_activities_by_date(start_time.date_naive(), &Tz::UTC)
+            .await
+            .unwrap();
+        assert_eq!(activities.len(), 2);
+        for activity in &activities {
+            assert_eq!(activity.category, ActivityCategory::new(study.id.clone()));
+        }
+        assert!(activities.iter().any(|a| {
+            a.provenance.override_category == Some(ActivityCategory::new(study.id.clone()))
+        }));
+
+        assert_eq!(
+            db.get_focus_mode_allowed_categories().await.unwrap(),
+            vec![study.id.clone()]
+        );
+        let papers = db
+            .get_user_category_by_id(&papers.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(papers.parent_id, Some(study.id.clone()));
+    }
+
+    #[tokio::test]
+    async fn test_delete_category_into_unknown_deletes_its_mappings() {
+        let db = create_test_db().await;
+        let reading = UserCategory {
+            id: Uuid::new_v4().to_string(),
+            name: "Reading".to_string(),
+            color: "#123456".to_string(),
+            parent_id: None,
+            productivity: Productivity::default(),
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        };
+        db.add_user_category(&reading).await.unwrap();
+        db.add_simple_app_mapping(&reading.id, "Kindle", true, MatchKind::Exact)
+            .await
+            .unwrap();
+        db.add_url_mapping(&crate::test_config::test_utils::create_sample_url_mapping(
+            &reading.id,
+            "arxiv.org",
+        ))
+        .await
+        .unwrap();
+        let app_mappings = db.get_app_mappings().await.unwrap().len();
+        let url_mappings = db.get_url_mappings().await.unwrap().len();
+
+        let report = db
+            .delete_user_category(&reading.id, "unknown", false)
+            .await
+            .unwrap();
+        assert_eq!(report.deleted_mappings, 2);
+        assert_eq!(report.app_mappings + report.url_mappings, 0);
+
+        assert_eq!(db.get_app_mappings().await.unwrap().len(), app_mappings - 1);
+        assert_eq!(db.get_url_mappings().await.unwrap().len(), url_mappings - 1);
+        assert!(!db
+            .get_app_mappings()
+            .await
+            .unwrap()
+            .iter()
+            .any(|m| m.app_pattern == "Kindle"));
+    }
+
+    #[tokio::test]
+    async fn test_productivity_score_and_trend() {
+        let db = create_test_db().await;
+        let at = |day, h| {
+            NaiveDate::from_ymd_opt(2024, 5, day)
+                .unwrap()
+                .and_hms_opt(h, 0, 0)
+                .unwrap()
+                .and_utc()
+        };
+        for (category, start_time) in [
+            ("development", at(1, 9)),
+            ("development", at(2, 9)),
+            ("entertainment", at(2, 10)),
+        ] {
+            db.start_activity(&ActivityEntry {
+                id: Uuid::new_v4(),
+                start_time,
+                end_time: Some(start_time + chrono::Duration::hours(1)),
+                app_name: "App".to_string(),
+                app_bundle_id: None,
+                window_title: "Window".to_string(),
+                url: None,
+                category: ActivityCategory::new(category),
+                provenance: CategoryProvenance::default(),
+                segments: vec![],
+            })
+            .await
+            .unwrap();
+        }
+
+        // An hour very productive and an hour very distracting cancel out
+        let second_day = at(2, 0).date_naive();
+        let summary = db.get_activity_summary(second_day, &Tz::UTC).await.unwrap();
+        assert_eq!(
+            summary.productivity,
+            ProductivityScore {
+                score: Some(50.0),
+                productive_seconds: 3600,
+                neutral_seconds: 0,
+                distracting_seconds: 3600,
+            }
+        );
+
+        let trend = db
+            .get_productivity_trend(at(1, 0).date_naive(), second_day, &Tz::UTC)
+            .await
+            .unwrap();
+        assert_eq!(trend.days.len(), 2);
+        assert_eq!(trend.days[0].productivity.score, Some(100.0));
+        assert_eq!(trend.days[1].productivity.score, Some(50.0));
+        assert_eq!(trend.average_score, Some(100.0));
+        assert_eq!(trend.good_day, Some(false));
+
+        // Weights are per category and can be changed
+        let mut entertainment = db
+            .get_user_category_by_id("entertainment")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(entertainment.productivity, Productivity::VeryDistracting);
+        entertainment.productivity = Productivity::Neutral;
+        db.update_user_category(&entertainment).await.unwrap();
+
+        let summary = db.get_activity_summary(second_day, &Tz::UTC).await.unwrap();
+        assert_eq!(summary.productivity.score, Some(75.0));
+        assert_eq!(summary.productivity.neutral_seconds, 3600);
+        assert_eq!(summary.productivity.distracting_seconds, 0);
+    }
+
     #[tokio::test]
     async fn test_focus_mode_allowed_apps() {
         let db = create_test_db().await;
@@ -344,7 +944,10 @@ mod database_tests {
         let test_date = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
 
         // Test getting activities for empty date
-        let activities = db.get_activities_by_date(test_date).await.unwrap();
+        let activities = db
+            .get_activities_by_date(test_date, &Tz::UTC)
+            .await
+            .unwrap();
         assert!(activities.is_empty());
 
         // Create and add test activity for specific date
@@ -356,7 +959,8 @@ mod database_tests {
             app_bundle_id: Some("com.test.app".to_string()),
             window_title: "Test Window".to_string(),
             url: None,
-            category: ActivityCategory::Development,
+            category: ActivityCategory::new("development"),
+            provenance: CategoryProvenance::default(),
             segments: vec![],
         };
 
@@ -370,7 +974,10 @@ mod database_tests {
         // Test getting activities for the date (this might not work as expected
         // depending on how the database stores and queries dates)
         // The test verifies the query doesn't crash
-        let _activities = db.get_activities_by_date(test_date).await.unwrap();
+        let _activities = db
+            .get_activities_by_date(test_date, &Tz::UTC)
+            .await
+            .unwrap();
         // Note: The actual result depends on the database implementation
     }
 
@@ -383,7 +990,7 @@ mod database_tests {
 
         // Test getting activities for empty date range
         let activities = db
-            .get_activities_by_date_range(start_date, end_date)
+            .get_activities_by_date_range(start_date, end_date, &Tz::UTC)
             .await
             .unwrap();
         assert!(activities.is_empty());
@@ -396,21 +1003,662 @@ mod database_tests {
         let test_date = NaiveDate::from_ymd_opt(2024, 1, 15).unwrap();
 
         // Test getting summary for empty date
-        let summary = db.get_activity_summary(test_date).await.unwrap();
+        let summary = db.get_activity_summary(test_date, &Tz::UTC).await.unwrap();
         assert_eq!(summary.total_active_time, 0);
         assert!(summary.categories.is_empty());
         assert!(summary.top_apps.is_empty());
+        assert!(summary.detailed_activities.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_activity_summary_segment_rollups() {
+        let db = create_test_db().await;
+        let at = |h, m| {
+            NaiveDate::from_ymd_opt(2024, 1, 15)
+                .unwrap()
+                .and_hms_opt(h, m, 0)
+                .unwrap()
+                .and_utc()
+        };
+
+        // Afternoon in the editor: main.rs, then lib.rs, then back to main.rs
+        let editor = ActivityEntry {
+            id: Uuid::new_v4(),
+            start_time: at(13, 0),
+            end_time: Some(at(14, 0)),
+            app_name: "Visual Studio Code".to_string(),
+            app_bundle_id: None,
+            window_title: "main.rs - velosi".to_string(),
+            url: None,
+            category: ActivityCategory::new("development"),
+            provenance: CategoryProvenance::default(),
+            segments: vec![],
+        };
+        db.start_activity(&editor).await.unwrap();
+
+        let file_visits = [
+            ("main.rs", at(13, 0), at(13, 20)),
+            ("lib.rs", at(13, 20), at(13, 30)),
+            ("main.rs", at(13, 30), at(14, 0)),
+        ];
+        for (file, start_time, end_time) in file_visits {
+            db.start_segment(&ActivitySegment {
+                id: Uuid::new_v4(),
+                activity_id: editor.id,
+                start_time,
+                end_time: Some(end_time),
+                segment_type: SegmentType::EditorFile,
+                title: file.to_string(),
+                url: None,
+                file_path: Some(format!("src/{}", file)),
+                metadata: None,
+            })
+            .await
+            .unwrap();
+        }
+
+        let terminal = ActivityEntry {
+            id: Uuid::new_v4(),
+            start_time: at(14, 0),
+            end_time: Some(at(14, 5)),
+            app_name: "Terminal".to_string(),
+            app_bundle_id: None,
+            window_title: "zsh".to_string(),
+            url: None,
+            category: ActivityCategory::new("development"),
+            provenance: CategoryProvenance::default(),
+            segments: vec![],
+        };
+        db.start_activity(&terminal).await.unwrap();
+        db.start_segment(&ActivitySegment {
+            id: Uuid::new_v4(),
+            activity_id: terminal.id,
+            start_time: at(14, 0),
+            end_time: Some(at(14, 5)),
+            segment_type: SegmentType::TerminalSession,
+            title: "zsh".to_string(),
+            url: None,
+            file_path: None,
+            metadata: None,
+        })
+        .await
+        .unwrap();
+
+        let summary = db
+            .get_activity_summary(at(0, 0).date_naive(), &Tz::UTC)
+            .await
+            .unwrap();
+        let detailed = &summary.detailed_activities;
+        assert_eq!(detailed.len(), 2);
+
+        assert_eq!(detailed[0].app_name, "Visual Studio Code");
+        assert_eq!(detailed[0].duration_seconds, 60 * 60);
+        let files = &detailed[0].segments;
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].file_path, Some("src/main.rs".to_string()));
+        assert_eq!(files[0].duration_seconds, 50 * 60);
+        assert_eq!(files[0].times_accessed, 2);
+        assert_eq!(files[1].title, "lib.rs");
+        assert_eq!(files[1].times_accessed, 1);
+
+        assert_eq!(detailed[1].app_name, "Terminal");
+        assert_eq!(
+            detailed[1].segments[0].segment_type,
+            SegmentType::TerminalSession
+        );
+        assert_eq!(detailed[1].segments[0].duration_seconds, 5 * 60);
     }
 
     #[tokio::test]
     async fn test_timeline_data() {
         let db = create_test_db().await;
 
-        // Test getting recent timeline (empty)
-        let timeline = db.get_recent_timeline(60).await.unwrap(); // Last hour
+        // Test getting the last hour's timeline (empty)
+        let now = Utc::now();
+        let timeline = db
+            .get_timeline(now - chrono::Duration::hours(1), now)
+            .await
+            .unwrap();
         assert!(timeline.activities.is_empty());
     }
 
+    #[tokio::test]
+    async fn test_activity_segments() {
+        let db = create_test_db().await;
+        let start = Utc::now() - chrono::Duration::minutes(10);
+
+        let activity = ActivityEntry {
+            id: Uuid::new_v4(),
+            start_time: start,
+            end_time: None,
+            app_name: "Google Chrome".to_string(),
+            app_bundle_id: None,
+            window_title: "GitHub".to_string(),
+            url: Some("https://github.com".to_string()),
+            category: ActivityCategory::new("development"),
+            provenance: CategoryProvenance::default(),
+            segments: vec![],
+        };
+        db.start_activity(&activity).await.unwrap();
+
+        let tab = |title: &str, at| ActivitySegment {
+            id: Uuid::new_v4(),
+            activity_id: activity.id,
+            start_time: at,
+            end_time: None,
+            segment_type: SegmentType::BrowserTab,
+            title: title.to_string(),
+            url: Some(format!("https://{}", title)),
+            file_path: None,
+            metadata: None,
+        };
+
+        db.start_segment(&tab("github.com", start)).await.unwrap();
+        db.end_current_segment(start + chrono::Duration::minutes(4))
+            .await
+            .unwrap();
+        db.start_segment(&tab("docs.rs", start + chrono::Duration::minutes(4)))
+            .await
+            .unwrap();
+
+        let open = db.get_current_segment().await.unwrap().unwrap();
+        assert_eq!(open.title, "docs.rs");
+        assert_eq!(open.segment_type, SegmentType::BrowserTab);
+
+        let current = db.get_current_activity().await.unwrap().unwrap();
+        assert_eq!(current.segments.len(), 2);
+        assert_eq!(current.segments[0].title, "github.com");
+        assert_eq!(
+            current.segments[0].end_time,
+            Some(start + chrono::Duration::minutes(4))
+        );
+
+        // Ending the activity closes its open segment at the same time
+        let end = start + chrono::Duration::minutes(6);
+        db.end_current_activity(end).await.unwrap();
+        assert!(db.get_current_segment().await.unwrap().is_none());
+
+        let activities = db
+            .get_activities_by_date(start.date_naive(), &Tz::UTC)
+            .await
+            .unwrap();
+        let segments = &activities[0].segments;
+        assert_eq!(segments[1].end_time, Some(end));
+
+        // The timeline clips segments to the requested window
+        let timeline = db
+            .get_timeline(
+                start + chrono::Duration::minutes(2),
+                start + chrono::Duration::minutes(10),
+            )
+            .await
+            .unwrap();
+        let timeline_segments = &timeline.activities[0].segments;
+        assert_eq!(timeline_segments.len(), 2);
+        assert_eq!(timeline_segments[0].duration_seconds, 2 * 60);
+        assert_eq!(timeline_segments[1].duration_seconds, 2 * 60);
+    }
+
+    #[tokio::test]
+    async fn test_timeline_range_clips_activities_at_edges() {
+        let db = create_test_db().await;
+        let at = |d, h, m| {
+            NaiveDate::from_ymd_opt(2024, 2, d)
+                .unwrap()
+                .and_hms_opt(h, m, 0)
+                .unwrap()
+                .and_utc()
+        };
+        let activity = |app: &str, start_time, end_time| ActivityEntry {
+            id: Uuid::new_v4(),
+            start_time,
+            end_time: Some(end_time),
+            app_name: app.to_string(),
+            app_bundle_id: None,
+            window_title: app.to_string(),
+            url: None,
+            category: ActivityCategory::unknown(),
+            provenance: CategoryProvenance::default(),
+            segments: vec![],
+        };
+
+        // Late night session spanning midnight, then a morning one, then one the next night
+        db.start_activity(&activity("Terminal", at(1, 23, 30), at(2, 0, 30)))
+            .await
+            .unwrap();
+        db.start_activity(&activity("Slack", at(2, 9, 0), at(2, 9, 45)))
+            .await
+            .unwrap();
+        db.start_activity(&activity("Mail", at(3, 0, 0), at(3, 0, 10)))
+            .await
+            .unwrap();
+
+        let (start, end) = day_bounds(NaiveDate::from_ymd_opt(2024, 2, 2).unwrap(), &Utc);
+        let timeline = db.get_timeline(start, end).await.unwrap();
+        assert_eq!(timeline.start_time, at(2, 0, 0));
+        assert_eq!(timeline.end_time, at(3, 0, 0));
+
+        let apps: Vec<&str> = timeline
+            .activities
+            .iter()
+            .map(|a| a.app_name.as_str())
+            .collect();
+        assert_eq!(apps, ["Terminal", "Slack"]);
+        assert_eq!(timeline.activities[0].start_time, at(2, 0, 0));
+        assert_eq!(timeline.activities[0].duration_seconds, 30 * 60);
+        assert_eq!(timeline.activities[1].duration_seconds, 45 * 60);
+
+        // The previous day keeps the part before midnight
+        let timeline = db.get_timeline(at(1, 0, 0), at(2, 0, 0)).await.unwrap();
+        assert_eq!(timeline.activities.len(), 1);
+        assert_eq!(timeline.activities[0].end_time, Some(at(2, 0, 0)));
+        assert_eq!(timeline.activities[0].duration_seconds, 30 * 60);
+    }
+
+    #[test]
+    fn test_day_bounds_in_local_timezone() {
+        let date = NaiveDate::from_ymd_opt(2024, 2, 2).unwrap();
+
+        // UTC+05:30: local midnight is 18:30 UTC the day before
+        let ist = FixedOffset::east_opt(5 * 3600 + 1800).unwrap();
+        let (start, end) = day_bounds(date, &ist);
+        assert_eq!(start.to_rfc3339(), "2024-02-01T18:30:00+00:00");
+        assert_eq!(end - start, chrono::Duration::hours(24));
+
+        // UTC-08:00: local midnight is 08:00 UTC the same day
+        let pst = FixedOffset::west_opt(8 * 3600).unwrap();
+        let (start, _) = day_bounds(date, &pst);
+        assert_eq!(start.to_rfc3339(), "2024-02-02T08:00:00+00:00");
+    }
+
+    #[tokio::test]
+    async fn test_activities_by_date_split_at_local_midnight() {
+        let db = create_test_db().await;
+        let kolkata: Tz = "Asia/Kolkata".parse().unwrap();
+        let at = |h, m| {
+            NaiveDate::from_ymd_opt(2024, 2, 1)
+                .unwrap()
+                .and_hms_opt(h, m, 0)
+                .unwrap()
+                .and_utc()
+        };
+
+        // 23:30-01:00 in Kolkata (UTC+05:30), crossing local midnight at 18:30 UTC
+        let activity = ActivityEntry {
+            id: Uuid::new_v4(),
+            start_time: at(18, 0),
+            end_time: Some(at(19, 30)),
+            app_name: "Terminal".to_string(),
+            app_bundle_id: None,
+            window_title: "zsh".to_string(),
+            url: None,
+            category: ActivityCategory::new("development"),
+            provenance: CategoryProvenance::default(),
+            segments: vec![],
+        };
+        db.start_activity(&activity).await.unwrap();
+
+        let first_day = NaiveDate::from_ymd_opt(2024, 2, 1).unwrap();
+        let activities = db
+            .get_activities_by_date(first_day, &kolkata)
+            .await
+            .unwrap();
+        assert_eq!(activities.len(), 1);
+        assert_eq!(activities[0].start_time, at(18, 0));
+        assert_eq!(activities[0].end_time, Some(at(18, 30)));
+
+        let second_day = NaiveDate::from_ymd_opt(2024, 2, 2).unwrap();
+        let activities = db
+            .get_activities_by_date(second_day, &kolkata)
+            .await
+            .unwrap();
+        assert_eq!(activities.len(), 1);
+        assert_eq!(activities[0].id, activity.id);
+        assert_eq!(activities[0].start_time, at(18, 30));
+        assert_eq!(activities[0].end_time, Some(at(19, 30)));
+
+        let first = db.get_activity_summary(first_day, &kolkata).await.unwrap();
+        let second = db.get_activity_summary(second_day, &kolkata).await.unwrap();
+        assert_eq!(first.total_active_time, 30 * 60);
+        assert_eq!(second.total_active_time, 60 * 60);
+
+        // In UTC the whole activity falls on February 1st
+        let utc = db.get_activity_summary(first_day, &Tz::UTC).await.unwrap();
+        assert_eq!(utc.total_active_time, 90 * 60);
+    }
+
+    #[tokio::test]
+    async fn test_date_range_clamps_activities_to_period() {
+        let db = create_test_db().await;
+        let at = |d, h| {
+            NaiveDate::from_ymd_opt(2024, 2, d)
+                .unwrap()
+                .and_hms_opt(h, 0, 0)
+                .unwrap()
+                .and_utc()
+        };
+        let activity = |app: &str, start_time, end_time| ActivityEntry {
+            id: Uuid::new_v4(),
+            start_time,
+            end_time: Some(end_time),
+            app_name: app.to_string(),
+            app_bundle_id: None,
+            window_title: app.to_string(),
+            url: None,
+            category: ActivityCategory::new("development"),
+            provenance: CategoryProvenance::default(),
+            segments: vec![],
+        };
+
+        // Overnight IDE session into the period, and another running out of it
+        let ide = activity("Visual Studio Code", at(1, 22), at(2, 2));
+        db.start_activity(&ide).await.unwrap();
+        db.start_segment(&ActivitySegment {
+            id: Uuid::new_v4(),
+            activity_id: ide.id,
+            start_time: at(1, 22),
+            end_time: Some(at(2, 1)),
+            segment_type: SegmentType::EditorFile,
+            title: "main.rs".to_string(),
+            url: None,
+            file_path: Some("/src/main.rs".to_string()),
+            metadata: None,
+        })
+        .await
+        .unwrap();
+        db.start_activity(&activity("Terminal", at(3, 23), at(4, 3)))
+            .await
+            .unwrap();
+
+        let activities = db
+            .get_activities_by_date_range(
+                NaiveDate::from_ymd_opt(2024, 2, 2).unwrap(),
+                NaiveDate::from_ymd_opt(2024, 2, 3).unwrap(),
+                &Tz::UTC,
+            )
+            .await
+            .unwrap();
+        assert_eq!(activities.len(), 2);
+        assert_eq!(activities[0].start_time, at(2, 0));
+        assert_eq!(activities[0].end_time, Some(at(2, 2)));
+        assert_eq!(activities[0].segments[0].start_time, at(2, 0));
+        assert_eq!(activities[1].start_time, at(3, 23));
+        assert_eq!(activities[1].end_time, Some(at(4, 0)));
+
+        let summary = db
+            .get_activity_summary(NaiveDate::from_ymd_opt(2024, 2, 2).unwrap(), &Tz::UTC)
+            .await
+            .unwrap();
+        assert_eq!(summary.total_active_time, 2 * 3600);
+        assert_eq!(
+            summary.detailed_activities[0].segments[0].duration_seconds,
+            3600
+        );
+    }
+
+    #[tokio::test]
+    async fn test_open_activity_from_yesterday_is_split() {
+        let db = create_test_db().await;
+        let today = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+        let midnight = today.and_hms_opt(0, 0, 0).unwrap().and_utc();
+        let now = midnight + chrono::Duration::hours(2);
+
+        // Still running, started three hours before midnight
+        let activity = ActivityEntry {
+            id: Uuid::new_v4(),
+            start_time: midnight - chrono::Duration::hours(3),
+            end_time: None,
+            app_name: "Visual Studio Code".to_string(),
+            app_bundle_id: None,
+            window_title: "main.rs".to_string(),
+            url: None,
+            category: ActivityCategory::new("development"),
+            provenance: CategoryProvenance::default(),
+            segments: vec![],
+        };
+        db.start_activity(&activity).await.unwrap();
+
+        let yesterday = db
+            .get_activity_summary_at(today.pred_opt().unwrap(), &Tz::UTC, now)
+            .await
+            .unwrap();
+        assert_eq!(yesterday.total_active_time, 3 * 3600);
+
+        let summary = db
+            .get_activity_summary_at(today, &Tz::UTC, now)
+            .await
+            .unwrap();
+        assert_eq!(summary.total_active_time, 2 * 3600);
+
+        let activities = db
+            .get_activities_by_date_at(today, &Tz::UTC, now)
+            .await
+            .unwrap();
+        assert_eq!(activities[0].start_time, midnight);
+        assert_eq!(activities[0].end_time, None);
+    }
+
+    #[tokio::test]
+    async fn test_summary_on_dst_transition_days() {
+        let db = create_test_db().await;
+        let new_york: Tz = "America/New_York".parse().unwrap();
+        let local = |m, d, h| {
+            NaiveDate::from_ymd_opt(2024, m, d)
+                .unwrap()
+                .and_hms_opt(h, 0, 0)
+                .unwrap()
+                .and_local_timezone(new_york)
+                .unwrap()
+                .with_timezone(&Utc)
+        };
+
+        // One activity covering each transition day from the evening before to the morning after
+        for (start, end) in [
+            (local(3, 9, 22), local(3, 11, 2)),
+            (local(11, 2, 22), local(11, 4, 2)),
+        ] {
+            db.start_activity(&ActivityEntry {
+                id: Uuid::new_v4(),
+                start_time: start,
+                end_time: Some(end),
+                app_name: "Terminal".to_string(),
+                app_bundle_id: None,
+                window_title: "long build".to_string(),
+                url: None,
+                category: ActivityCategory::new("development"),
+                provenance: CategoryProvenance::default(),
+                segments: vec![],
+            })
+            .await
+            .unwrap();
+        }
+
+        // Clocks spring forward on March 10th and fall back on November 3rd
+        let spring = db
+            .get_activity_summary(NaiveDate::from_ymd_opt(2024, 3, 10).unwrap(), &new_york)
+            .await
+            .unwrap();
+        assert_eq!(spring.total_active_time, 23 * 3600);
+
+        let fall = db
+            .get_activity_summary(NaiveDate::from_ymd_opt(2024, 11, 3).unwrap(), &new_york)
+            .await
+            .unwrap();
+        assert_eq!(fall.total_active_time, 25 * 3600);
+
+        let (start, end) = day_bounds(NaiveDate::from_ymd_opt(2024, 3, 10).unwrap(), &new_york);
+        assert_eq!(start.to_rfc3339(), "2024-03-10T05:00:00+00:00");
+        assert_eq!(end.to_rfc3339(), "2024-03-11T04:00:00+00:00");
+    }
+
+    #[tokio::test]
+    async fn test_weekly_report() {
+        let db = create_test_db().await;
+        let at = |m, d, h, min| {
+            NaiveDate::from_ymd_opt(2024, m, d)
+                .unwrap()
+                .and_hms_opt(h, min, 0)
+                .unwrap()
+                .and_utc()
+        };
+        let activity = |app: &str, category, start_time, end_time| ActivityEntry {
+            id: Uuid::new_v4(),
+            start_time,
+            end_time: Some(end_time),
+            app_name: app.to_string(),
+            app_bundle_id: None,
+            window_title: app.to_string(),
+            url: None,
+            category,
+            provenance: CategoryProvenance::default(),
+            segments: vec![],
+        };
+
+        for entry in [
+            // Previous week: two hours of development, one of them late Sunday night
+            activity(
+                "Visual Studio Code",
+                ActivityCategory::new("development"),
+                at(1, 30, 9, 0),
+                at(1, 30, 10, 0),
+            ),
+            activity(
+                "Visual Studio Code",
+                ActivityCategory::new("development"),
+                at(2, 4, 23, 0),
+                at(2, 5, 1, 0),
+            ),
+            // This week: a two hour editor/terminal streak with a one minute break
+            activity(
+                "Visual Studio Code",
+                ActivityCategory::new("development"),
+                at(2, 5, 9, 0),
+                at(2, 5, 10, 0),
+            ),
+            activity(
+                "Terminal",
+                ActivityCategory::new("development"),
+                at(2, 5, 10, 1),
+                at(2, 5, 11, 0),
+            ),
+            activity(
+                "Slack",
+                ActivityCategory::new("social"),
+                at(2, 6, 12, 0),
+                at(2, 6, 12, 30),
+            ),
+        ] {
+            db.start_activity(&entry).await.unwrap();
+        }
+
+        let wednesday = NaiveDate::from_ymd_opt(2024, 2, 7).unwrap();
+        let report = db
+            .get_activity_report(ReportPeriod::Week, wednesday, &Tz::UTC)
+            .await
+            .unwrap();
+
+        assert_eq!(report.start_date, "2024-02-05");
+        assert_eq!(report.end_date, "2024-02-11");
+        assert_eq!(report.days.len(), 7);
+        assert_eq!(report.days[0].total_active_time, 3 * 3600 - 60);
+        assert_eq!(report.days[1].total_active_time, 30 * 60);
+        assert_eq!(report.days[2].total_active_time, 0);
+
+        assert_eq!(report.total_active_time, 3 * 3600 - 60 + 30 * 60);
+        assert_eq!(report.previous_total_active_time, 2 * 3600);
+
+        let development = &report.categories[0];
+        assert_eq!(development.category, ActivityCategory::new("development"));
+        assert_eq!(development.duration_seconds, 3 * 3600 - 60);
+        assert_eq!(development.previous_duration_seconds, 2 * 3600);
+        let change = development.change_percentage.unwrap();
+        assert!((change - 49.166).abs() < 0.01);
+        assert_eq!(report.categories[1].change_percentage, None);
+
+        assert_eq!(report.top_apps[0].app_name, "Visual Studio Code");
+        assert_eq!(report.top_apps[0].duration_seconds, 2 * 3600);
+
+        assert_eq!(report.focus_streaks.len(), 3);
+        assert_eq!(report.focus_streaks[0].start_time, at(2, 5, 9, 0));
+        assert_eq!(report.focus_streaks[0].duration_seconds, 2 * 3600);
+
+        // On Tuesday evening only Monday and Tuesday of the previous week are compared
+        let report = db
+            .get_activity_report_at(ReportPeriod::Week, wednesday, &Tz::UTC, at(2, 6, 18, 0))
+            .await
+            .unwrap();
+        assert_eq!(report.total_active_time, 3 * 3600 - 60 + 30 * 60);
+        assert_eq!(report.previous_total_active_time, 3600);
+        assert_eq!(report.categories[0].previous_duration_seconds, 3600);
+    }
+
+    #[test]
+    fn test_report_period_dates() {
+        let date = |m, d| NaiveDate::from_ymd_opt(2024, m, d).unwrap();
+
+        assert_eq!(
+            ReportPeriod::Week.dates_containing(date(2, 7)),
+            (date(2, 5), date(2, 11))
+        );
+        assert_eq!(
+            ReportPeriod::Week.previous_dates(date(2, 5)),
+            (date(1, 29), date(2, 4))
+        );
+        assert_eq!(
+            ReportPeriod::Month.dates_containing(date(2, 14)),
+            (date(2, 1), date(2, 29))
+        );
+        assert_eq!(
+            ReportPeriod::Month.previous_dates(date(3, 31)),
+            (date(2, 1), date(2, 29))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pomodoro_summary_groups_by_local_date() {
+        let db = create_test_db().await;
+        let tokyo: Tz = "Asia/Tokyo".parse().unwrap();
+
+        // 23:30 UTC on February 1st is 08:30 on February 2nd in Tokyo
+        let start_time = NaiveDate::from_ymd_opt(2024, 2, 1)
+            .unwrap()
+            .and_hms_opt(23, 30, 0)
+            .unwrap()
+            .and_utc();
+        let session = PomodoroSession {
+            id: Uuid::new_v4(),
+            session_type: PomodoroSessionType::Work,
+            start_time,
+            end_time: Some(start_time + chrono::Duration::minutes(25)),
+            duration_minutes: 25,
+            actual_duration_seconds: Some(25 * 60),
+            work_description: None,
+            completed: true,
+            focus_mode_enabled: false,
+            app_tracking_enabled: false,
+        };
+        db.save_pomodoro_session(&session).await.unwrap();
+
+        let feb_2 = NaiveDate::from_ymd_opt(2024, 2, 2).unwrap();
+        let summary = db
+            .get_pomodoro_summary(Some(feb_2), Some(feb_2), &tokyo)
+            .await
+            .unwrap();
+        assert_eq!(summary.total_sessions, 1);
+        assert_eq!(summary.sessions_by_date.len(), 1);
+        assert_eq!(summary.sessions_by_date[0].date, "2024-02-02");
+        assert_eq!(summary.sessions_by_date[0].total_work_time_seconds, 25 * 60);
+
+        let summary = db.get_pomodoro_summary(None, None, &Tz::UTC).await.unwrap();
+        assert_eq!(summary.sessions_by_date[0].date, "2024-02-01");
+
+        let sessions = db
+            .get_pomodoro_sessions(Some(feb_2), Some(feb_2), None, None, &Tz::UTC)
+            .await
+            .unwrap();
+        assert!(sessions.is_empty());
+    }
+
     #[tokio::test]
     async fn test_update_activity_category() {
         let db = create_test_db().await;
@@ -424,20 +1672,267 @@ mod database_tests {
             app_bundle_id: None,
             window_title: "Test Window".to_string(),
             url: None,
-            category: ActivityCategory::Unknown,
+            category: ActivityCategory::unknown(),
+            provenance: CategoryProvenance::default(),
             segments: vec![],
         };
 
         db.start_activity(&activity).await.unwrap();
 
         // Test updating category
-        let new_category = ActivityCategory::Development;
+        let new_category = ActivityCategory::new("development");
         let result = db
             .update_activity_category(&activity.id.to_string(), &new_category)
             .await;
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_manual_category_is_kept_apart_from_computed() {
+        let db = create_test_db().await;
+        let mapping_id = Uuid::new_v4();
+        let activity = ActivityEntry {
+            id: Uuid::new_v4(),
+            start_time: Utc::now() - chrono::Duration::minutes(30),
+            end_time: None,
+            app_name: "Slack".to_string(),
+            app_bundle_id: None,
+            window_title: "#incidents".to_string(),
+            url: None,
+            category: ActivityCategory::new("communication"),
+            provenance: CategoryProvenance {
+                computed_source: Some(CategorizationSource::AppMapping),
+                mapping_id: Some(mapping_id),
+                ..Default::default()
+            },
+            segments: vec![],
+        };
+        db.start_activity(&activity).await.unwrap();
+
+        db.update_activity_category(
+            &activity.id.to_string(),
+            &ActivityCategory::new("productive"),
+        )
+        .await
+        .unwrap();
+
+        let stored = db.get_current_activity().await.unwrap().unwrap();
+        assert_eq!(stored.category, ActivityCategory::new("productive"));
+        assert_eq!(
+            stored.provenance.computed_category,
+            Some(ActivityCategory::new("communication"))
+        );
+        assert_eq!(
+            stored.provenance.computed_source,
+            Some(CategorizationSource::AppMapping)
+        );
+        assert_eq!(stored.provenance.mapping_id, Some(mapping_id));
+        assert_eq!(
+            stored.provenance.override_category,
+            Some(ActivityCategory::new("productive"))
+        );
+        assert!(stored.provenance.overridden_at.is_some());
+
+        let summary = db
+            .get_activity_summary(activity.start_time.date_naive(), &Tz::UTC)
+            .await
+            .unwrap();
+        assert_eq!(summary.corrections.len(), 1);
+        assert_eq!(
+            summary.corrections[0].computed,
+            ActivityCategory::new("communication")
+        );
+        assert_eq!(
+            summary.corrections[0].corrected,
+            ActivityCategory::new("productive")
+        );
+
+        db.clear_activity_category_override(&activity.id.to_string())
+            .await
+            .unwrap();
+        let stored = db.get_current_activity().await.unwrap().unwrap();
+        assert_eq!(stored.category, ActivityCategory::new("communication"));
+        assert_eq!(stored.provenance.override_category, None);
+    }
+
+    #[tokio::test]
+    async fn test_uncategorized_activity_for_triage() {
+        let db = create_test_db().await;
+        let at = |h, m| {
+            NaiveDate::from_ymd_opt(2024, 1, 15)
+                .unwrap()
+                .and_hms_opt(h, m, 0)
+                .unwrap()
+                .and_utc()
+        };
+        let activity =
+            |app: &str, category, start_time, end_time, url: Option<&str>| ActivityEntry {
+                id: Uuid::new_v4(),
+                start_time,
+                end_time: Some(end_time),
+                app_name: app.to_string(),
+                app_bundle_id: None,
+                window_title: "Untitled".to_string(),
+                url: url.map(str::to_string),
+                category,
+                provenance: CategoryProvenance::default(),
+                segments: vec![],
+            };
+
+        let acme = activity(
+            "Acme Studio",
+            ActivityCategory::unknown(),
+            at(13, 0),
+            at(13, 40),
+            None,
+        );
+        let mut browser = activity(
+            "Google Chrome",
+            ActivityCategory::unknown(),
+            at(14, 0),
+            at(14, 30),
+            Some("https://example.org/docs"),
+        );
+        browser.window_title = "Docs".to_string();
+        let editor = activity(
+            "Visual Studio Code",
+            ActivityCategory::new("development"),
+            at(15, 0),
+            at(16, 0),
+            None,
+        );
+        let corrected = activity(
+            "Acme Studio",
+            ActivityCategory::unknown(),
+            at(16, 0),
+            at(16, 10),
+            None,
+        );
+        for entry in [&acme, &browser, &editor, &corrected] {
+            db.start_activity(entry).await.unwrap();
+        }
+        db.update_activity_category(
+            &corrected.id.to_string(),
+            &ActivityCategory::new("productive"),
+        )
+        .await
+        .unwrap();
+
+        // Segments aren't categorized on their own, so their titles and domains aren't listed
+        let tabs = [
+            ("Docs", "https://example.org/docs", at(14, 0), at(14, 20)),
+            ("Other", "https://other.example/", at(14, 20), at(14, 30)),
+        ];
+        for (title, url, start_time, end_time) in tabs {
+            db.start_segment(&ActivitySegment {
+                id: Uuid::new_v4(),
+                activity_id: browser.id,
+                start_time,
+                end_time: Some(end_time),
+                segment_type: SegmentType::BrowserTab,
+                title: title.to_string(),
+                url: Some(url.to_string()),
+                file_path: None,
+                metadata: None,
+            })
+            .await
+            .unwrap();
+        }
+
+        let (start, end) = day_bounds(at(0, 0).date_naive(), &Tz::UTC);
+        let report = db.get_uncategorized_activity(start, end, 10).await.unwrap();
+        assert_eq!(report.total_seconds, 70 * 60);
+
+        let summary = |items: &[UncategorizedItem]| {
+            items
+                .iter()
+                .map(|item| {
+                    (
+                        item.value.clone(),
+                        item.duration_seconds,
+                        item.times_accessed,
+                    )
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            summary(&report.apps),
+            vec![
+                ("Acme Studio".to_string(), 40 * 60, 1),
+                ("Google Chrome".to_string(), 30 * 60, 1),
+            ]
+        );
+        assert_eq!(
+            summary(&report.domains),
+            vec![("example.org".to_string(), 30 * 60, 1)]
+        );
+        assert_eq!(
+            summary(&report.titles),
+            vec![
+                ("Untitled".to_string(), 40 * 60, 1),
+                ("Docs".to_string(), 30 * 60, 1),
+            ]
+        );
+        assert_eq!(report.titles[0].app_name.as_deref(), Some("Acme Studio"));
+        assert_eq!(report.titles[0].last_seen, at(13, 40));
+
+        let top = db.get_uncategorized_activity(start, end, 1).await.unwrap();
+        assert_eq!(top.titles.len(), 1);
+
+        // A rule for a listed title is picked up when the day is backfilled
+        let (match_kind, pattern) = triage_pattern(&report.titles[1]).unwrap();
+        db.add_title_mapping(&TitleMapping {
+            id: Uuid::new_v4(),
+            title_pattern: pattern,
+            app_pattern: report.titles[1].app_name.clone(),
+            category_id: "education".to_string(),
+            is_custom: true,
+            match_kind,
+            priority: 0,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        })
+        .await
+        .unwrap();
+        let backfill = reapply_mappings(&db, start, end).await.unwrap();
+        assert_eq!(backfill.changed, 1);
+        let report = db.get_uncategorized_activity(start, end, 10).await.unwrap();
+        assert_eq!(
+            summary(&report.apps),
+            vec![("Acme Studio".to_string(), 40 * 60, 1)]
+        );
+        assert!(report.domains.is_empty());
+    }
+
+    #[test]
+    fn test_triage_pattern_keeps_title_rules_to_their_app() {
+        let item = |target, value: &str, app_name: Option<&str>| UncategorizedItem {
+            target,
+            value: value.to_string(),
+            app_name: app_name.map(str::to_string),
+            duration_seconds: 0,
+            times_accessed: 0,
+            last_seen: Utc::now(),
+        };
+
+        assert_eq!(
+            triage_pattern(&item(MappingTarget::Title, "Inbox", Some("Mail"))).unwrap(),
+            (MatchKind::Substring, "Inbox".to_string())
+        );
+        assert_eq!(
+            triage_pattern(&item(MappingTarget::Title, "a | b", Some("Mail"))).unwrap(),
+            (MatchKind::Regex, r"a \| b".to_string())
+        );
+        assert_eq!(
+            triage_pattern(&item(MappingTarget::App, "Foo|Bar", None)).unwrap(),
+            (MatchKind::Regex, r"^Foo\|Bar$".to_string())
+        );
+
+        // The scope would turn into two alternatives and match either app
+        let error = triage_pattern(&item(MappingTarget::Title, "Inbox", Some("Foo|Bar")));
+        assert!(error.unwrap_err().contains("Foo|Bar"));
+    }
+
     #[tokio::test]
     async fn test_expired_allowed_apps_cleanup() {
         let db = create_test_db().await;
@@ -464,4 +1959,36 @@ mod database_tests {
         let allowed = db.is_focus_mode_app_allowed("Valid App").await.unwrap();
         assert!(allowed);
     }
+
+    #[tokio::test]
+    async fn test_idle_threshold_setting() {
+        let db = create_test_db().await;
+
+        // Seeded by migration 6
+        assert_eq!(db.get_idle_threshold_seconds().await.unwrap(), 300);
+
+        db.set_idle_threshold_seconds(900).await.unwrap();
+        assert_eq!(db.get_idle_threshold_seconds().await.unwrap(), 900);
+    }
+
+    #[tokio::test]
+    async fn test_timezone_setting() {
+        let db = create_test_db().await;
+
+        // Unset means following the system timezone
+        assert_eq!(
+            db.get_timezone().await.unwrap(),
+            crate::timezone::system_timezone()
+        );
+
+        let berlin: Tz = "Europe/Berlin".parse().unwrap();
+        db.set_timezone(Some(berlin)).await.unwrap();
+        assert_eq!(db.get_timezone().await.unwrap(), berlin);
+
+        db.set_timezone(None).await.unwrap();
+        assert_eq!(
+            db.get_timezone().await.unwrap(),
+            crate::timezone::system_timezone()
+        );
+    }
 }
@@ -1,4 +1,4 @@
-use crate::{cache::CacheManager, AppState};
+use crate::{cache::CacheManager, pattern, AppState};
 use tauri::{AppHandle, Emitter, Manager};
 
 pub struct FocusMode {
@@ -35,13 +35,16 @@ impl FocusMode {
         // Check if app blocking is enabled
         let app_blocking_enabled = state.db.get_app_blocking_enabled().await.unwrap_or(true);
         if !app_blocking_enabled {
-            println!("ℹ️ App blocking disabled by user preference, allowing '{}'", app_name);
+            println!(
+                "ℹ️ App blocking disabled by user preference, allowing '{}'",
+                app_name
+            );
             return Ok(true); // App is allowed
         }
 
         // Always allow velosi app itself
         if app_name.to_lowercase().contains("velosi")
-            || bundle_id.map_or(false, |bid| bid.to_lowercase().contains("velosi"))
+            || bundle_id.is_some_and(|bid| bid.to_lowercase().contains("velosi"))
         {
             return Ok(true); // Velosi app is always allowed
         }
@@ -80,29 +83,20 @@ impl FocusMode {
 
         // Find the category for this app
         for mapping in app_mappings {
-            let patterns: Vec<&str> = mapping.app_pattern.split('|').collect();
-            for pattern in patterns {
-                if app_name
-                    .to_lowercase()
-                    .contains(&pattern.trim().to_lowercase())
-                    || bundle_id.map_or(false, |bid| {
-                        bid.to_lowercase().contains(&pattern.trim().to_lowercase())
-                    })
-                {
-                    // App matches this category
-                    if allowed_categories.contains(&mapping.category_id) {
-                        return Ok(true); // App is allowed
-                    } else {
-                        return self
-                            .block_app_with_notification(
-                                app_name,
-                                &format!(
-                                    "Category '{}' is not allowed in focus mode",
-                                    mapping.category_id
-                                ),
-                            )
-                            .await;
-                    }
+            if pattern::app_mapping_matches(&mapping, app_name, bundle_id) {
+                // App matches this category
+                if allowed_categories.contains(&mapping.category_id) {
+                    return Ok(true); // App is allowed
+                } else {
+                    return self
+                        .block_app_with_notification(
+                            app_name,
+                            &format!(
+                                "Category '{}' is not allowed in focus mode",
+                                mapping.category_id
+                            ),
+                        )
+                        .await;
                 }
             }
         }
@@ -1,5 +1,5 @@
 #[cfg(test)]
-mod focus_mode_tests {
+mod tests {
     use crate::models::*;
     use crate::test_config::test_utils::*;
     use chrono::Utc;
@@ -111,19 +111,25 @@ mod focus_mode_tests {
             name: "Development".to_string(),
             color: "#0000FF".to_string(),
             parent_id: None,
+            productivity: Productivity::default(),
             created_at: Utc::now(),
             updated_at: Utc::now(),
         };
 
         db.add_user_category(&category).await.unwrap();
-        db.add_simple_app_mapping(&category.id, "Visual Studio Code", true)
-            .await
-            .unwrap();
+        db.add_simple_app_mapping(
+            &category.id,
+            "Visual Studio Code",
+            true,
+            MatchKind::Substring,
+        )
+        .await
+        .unwrap();
 
         // Test that mappings are created correctly
         let mappings = db.get_app_mappings().await.unwrap();
         // Should have at least our mapping plus any initial mappings
-        assert!(mappings.len() >= 1);
+        assert!(!mappings.is_empty());
 
         // Find our specific mapping
         let vscode_mapping = mappings
@@ -256,4 +262,238 @@ mod focus_mode_tests {
         assert!(!db_allowed);
         assert!(!cache_contains);
     }
+
+    fn sample_schedule(
+        name: &str,
+        days: &[chrono::Weekday],
+        start: (u32, u32),
+        end: (u32, u32),
+    ) -> FocusSchedule {
+        FocusSchedule {
+            id: Uuid::new_v4(),
+            name: name.to_string(),
+            days: days.to_vec(),
+            start_time: chrono::NaiveTime::from_hms_opt(start.0, start.1, 0).unwrap(),
+            end_time: chrono::NaiveTime::from_hms_opt(end.0, end.1, 0).unwrap(),
+            allowed_categories: Vec::new(),
+            enabled: true,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_focus_schedule_transitions() {
+        use crate::schedule::{active_schedule, next_transition};
+        use chrono::{NaiveDate, TimeZone, Weekday};
+
+        let at = |day: u32, hour: u32, minute: u32| {
+            NaiveDate::from_ymd_opt(2024, 1, day)
+                .unwrap()
+                .and_hms_opt(hour, minute, 0)
+                .unwrap()
+        };
+        let weekdays = [
+            Weekday::Mon,
+            Weekday::Tue,
+            Weekday::Wed,
+            Weekday::Thu,
+            Weekday::Fri,
+        ];
+        // 2024-01-01 is a Monday
+        let morning = sample_schedule("Morning", &weekdays, (9, 0), (12, 0));
+        let lunch = sample_schedule("Lunch", &[Weekday::Mon], (11, 0), (13, 0));
+        let late = sample_schedule("Late", &[Weekday::Fri], (22, 0), (2, 0));
+        let mut paused = sample_schedule("Paused", &[Weekday::Sun], (10, 0), (11, 0));
+        paused.enabled = false;
+        let schedules = vec![morning.clone(), lunch.clone(), late.clone(), paused];
+        let tz = chrono_tz::UTC;
+
+        assert!(active_schedule(&schedules, at(1, 8, 59)).is_none());
+        assert_eq!(
+            active_schedule(&schedules, at(1, 9, 0)).unwrap().id,
+            morning.id
+        );
+        assert_eq!(
+            active_schedule(&schedules, at(1, 11, 30)).unwrap().id,
+            morning.id
+        );
+        assert_eq!(
+            active_schedule(&schedules, at(1, 12, 30)).unwrap().id,
+            lunch.id
+        );
+        assert!(active_schedule(&schedules, at(2, 12, 0)).is_none());
+
+        // Overnight windows run into the next day
+        assert_eq!(
+            active_schedule(&schedules, at(6, 1, 0)).unwrap().id,
+            late.id
+        );
+        assert!(active_schedule(&schedules, at(6, 2, 0)).is_none());
+        assert!(active_schedule(&schedules, at(7, 10, 30)).is_none());
+
+        let next = next_transition(&schedules, at(1, 8, 0), &tz).unwrap();
+        assert!(next.enables_focus_mode);
+        assert_eq!(next.local_time, at(1, 9, 0));
+        assert_eq!(next.at, Utc.with_ymd_and_hms(2024, 1, 1, 9, 0, 0).unwrap());
+        assert_eq!(next.schedule_id, morning.id);
+
+        // Overlapping windows keep focus mode on until the last one ends
+        let next = next_transition(&schedules, at(1, 10, 0), &tz).unwrap();
+        assert!(!next.enables_focus_mode);
+        assert_eq!(next.local_time, at(1, 13, 0));
+        assert_eq!(next.schedule_id, lunch.id);
+
+        let next = next_transition(&schedules, at(5, 13, 0), &tz).unwrap();
+        assert!(next.enables_focus_mode);
+        assert_eq!(next.local_time, at(5, 22, 0));
+
+        let next = next_transition(&schedules, at(6, 1, 0), &tz).unwrap();
+        assert!(!next.enables_focus_mode);
+        assert_eq!(next.local_time, at(6, 2, 0));
+
+        // Past the weekend to Monday
+        let next = next_transition(&schedules, at(7, 12, 0), &tz).unwrap();
+        assert_eq!(next.local_time, at(8, 9, 0));
+
+        // Local times are converted with the user's timezone
+        let next = next_transition(&schedules, at(1, 8, 0), &chrono_tz::Europe::Berlin).unwrap();
+        assert_eq!(next.at, Utc.with_ymd_and_hms(2024, 1, 1, 8, 0, 0).unwrap());
+
+        assert!(next_transition(&[], at(1, 8, 0), &tz).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_focus_schedule_storage() {
+        use chrono::Weekday;
+
+        let db = create_test_database().await;
+        let work = create_sample_category("Work", "#FF0000");
+        let reading = create_sample_category("Reading", "#00FF00");
+        db.add_user_category(&work).await.unwrap();
+        db.add_user_category(&reading).await.unwrap();
+
+        let mut schedule =
+            sample_schedule("Deep work", &[Weekday::Mon, Weekday::Wed], (9, 0), (12, 0));
+        schedule.allowed_categories = vec![work.id.clone()];
+        db.save_focus_schedule(&schedule).await.unwrap();
+
+        let schedules = db.get_focus_schedules().await.unwrap();
+        assert_eq!(schedules.len(), 1);
+        assert_eq!(schedules[0].id, schedule.id);
+        assert_eq!(schedules[0].days, vec![Weekday::Mon, Weekday::Wed]);
+        assert_eq!(schedules[0].start_time, schedule.start_time);
+        assert_eq!(schedules[0].end_time, schedule.end_time);
+        assert_eq!(schedules[0].allowed_categories, vec![work.id.clone()]);
+
+        // Saving again updates in place and replaces the categories
+        schedule.name = "Mornings".to_string();
+        schedule.enabled = false;
+        schedule.allowed_categories = vec![reading.id.clone()];
+        db.save_focus_schedule(&schedule).await.unwrap();
+
+        let schedules = db.get_focus_schedules().await.unwrap();
+        assert_eq!(schedules.len(), 1);
+        assert_eq!(schedules[0].name, "Mornings");
+        assert!(!schedules[0].enabled);
+        assert_eq!(schedules[0].allowed_categories, vec![reading.id.clone()]);
+
+        // Deleting a category moves the schedule over to the target
+        let report = db
+            .delete_user_category(&reading.id, &work.id, false)
+            .await
+            .unwrap();
+        assert_eq!(report.focus_mode_categories, 1);
+        let schedules = db.get_focus_schedules().await.unwrap();
+        assert_eq!(schedules[0].allowed_categories, vec![work.id.clone()]);
+
+        db.delete_focus_schedule(&schedule.id).await.unwrap();
+        assert!(db.get_focus_schedules().await.unwrap().is_empty());
+        assert!(matches!(
+            db.delete_focus_schedule(&schedule.id).await,
+            Err(sqlx::Error::RowNotFound)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_category_edits_keep_the_active_schedule_categories() {
+        use crate::commands::focus_mode::effective_allowed_categories;
+        use chrono::Weekday;
+
+        let db = create_test_database().await;
+        let work = create_sample_category("Work", "#FF0000");
+        let mut meetings = create_sample_category("Meetings", "#00FF00");
+        let reading = create_sample_category("Reading", "#0000FF");
+        for category in [&work, &meetings, &reading] {
+            db.add_user_category(category).await.unwrap();
+        }
+        db.set_focus_mode_allowed_categories(&[reading.id.clone()])
+            .await
+            .unwrap();
+        let state = create_test_app_state(db);
+
+        let mut schedule = sample_schedule("Deep work", &[Weekday::Mon], (9, 0), (12, 0));
+        schedule.allowed_categories = vec![work.id.clone()];
+        *state.active_focus_schedule.lock().unwrap() = Some(schedule);
+
+        // Moving a category refreshes the cache; during the window it still uses the schedule
+        meetings.parent_id = Some(work.id.clone());
+        state.db.update_user_category(&meetings).await.unwrap();
+        let mut expected = vec![work.id.clone(), meetings.id.clone()];
+        expected.sort();
+        assert_eq!(
+            effective_allowed_categories(&state).await.unwrap(),
+            expected
+        );
+
+        // Back to the usual categories once the window ends
+        *state.active_focus_schedule.lock().unwrap() = None;
+        assert_eq!(
+            effective_allowed_categories(&state).await.unwrap(),
+            vec![reading.id.clone()]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_edits_to_the_active_schedule_apply_during_its_window() {
+        use crate::commands::focus_mode::effective_allowed_categories;
+        use crate::schedule::refresh_active_schedule;
+        use chrono::Weekday;
+
+        let db = create_test_database().await;
+        let work = create_sample_category("Work", "#FF0000");
+        let reading = create_sample_category("Reading", "#0000FF");
+        for category in [&work, &reading] {
+            db.add_user_category(category).await.unwrap();
+        }
+        let state = create_test_app_state(db);
+
+        let mut schedule = sample_schedule("Deep work", &[Weekday::Mon], (9, 0), (12, 0));
+        schedule.allowed_categories = vec![work.id.clone()];
+        state.db.save_focus_schedule(&schedule).await.unwrap();
+        *state.active_focus_schedule.lock().unwrap() = Some(schedule.clone());
+
+        // Unchanged, or another schedule: nothing to refresh
+        assert!(!refresh_active_schedule(&state, &schedule).unwrap());
+        let other = sample_schedule("Evening", &[Weekday::Mon], (18, 0), (20, 0));
+        assert!(!refresh_active_schedule(&state, &other).unwrap());
+
+        schedule.allowed_categories = vec![reading.id.clone()];
+        schedule.updated_at = Utc::now();
+        state.db.save_focus_schedule(&schedule).await.unwrap();
+        let saved = state
+            .db
+            .get_focus_schedules()
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|s| s.id == schedule.id)
+            .unwrap();
+        assert!(refresh_active_schedule(&state, &saved).unwrap());
+        assert_eq!(
+            effective_allowed_categories(&state).await.unwrap(),
+            vec![reading.id.clone()]
+        );
+        assert!(!refresh_active_schedule(&state, &saved).unwrap());
+    }
 }
@@ -0,0 +1,136 @@
+//! System-wide "time since last keyboard/mouse input".
+//!
+//! Each platform exposes this differently:
+//! - macOS: `CGEventSourceSecondsSinceLastEventType` from CoreGraphics
+//! - Windows: `GetLastInputInfo` compared against `GetTickCount`
+//! - Linux: GNOME's Mutter IdleMonitor or `org.freedesktop.ScreenSaver` over D-Bus
+//!   (both work on Wayland), then `xprintidle` (XScreenSaver extension) on X11
+//!
+//! `None` means idle time could not be determined; callers treat the user as active.
+
+use std::time::Duration;
+
+#[cfg(target_os = "linux")]
+use std::process::Command;
+
+#[cfg(target_os = "macos")]
+#[link(name = "CoreGraphics", kind = "framework")]
+extern "C" {
+    fn CGEventSourceSecondsSinceLastEventType(source_state_id: i32, event_type: u32) -> f64;
+}
+
+/// Idle time since the last user input event, if the platform can tell us.
+#[cfg(target_os = "macos")]
+pub fn system_idle_time() -> Option<Duration> {
+    // kCGEventSourceStateCombinedSessionState
+    const COMBINED_SESSION_STATE: i32 = 0;
+    // kCGAnyInputEventType
+    const ANY_INPUT_EVENT: u32 = !0;
+
+    let seconds =
+        unsafe { CGEventSourceSecondsSinceLastEventType(COMBINED_SESSION_STATE, ANY_INPUT_EVENT) };
+    if seconds.is_finite() && seconds >= 0.0 {
+        Some(Duration::from_secs_f64(seconds))
+    } else {
+        None
+    }
+}
+
+/// Idle time since the last user input event, if the platform can tell us.
+#[cfg(target_os = "windows")]
+pub fn system_idle_time() -> Option<Duration> {
+    use winapi::um::sysinfoapi::GetTickCount;
+    use winapi::um::winuser::{GetLastInputInfo, LASTINPUTINFO};
+
+    let mut info = LASTINPUTINFO {
+        cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32,
+        dwTime: 0,
+    };
+
+    unsafe {
+        if GetLastInputInfo(&mut info) == 0 {
+            return None;
+        }
+        // Both are milliseconds since boot; wrapping_sub handles the 49.7 day rollover
+        let idle_ms = GetTickCount().wrapping_sub(info.dwTime);
+        Some(Duration::from_millis(idle_ms as u64))
+    }
+}
+
+/// Idle time since the last user input event, if the platform can tell us.
+#[cfg(target_os = "linux")]
+pub fn system_idle_time() -> Option<Duration> {
+    // GNOME (X11 and Wayland)
+    let mutter = run_command(
+        "gdbus",
+        &[
+            "call",
+            "--session",
+            "--dest",
+            "org.gnome.Mutter.IdleMonitor",
+            "--object-path",
+            "/org/gnome/Mutter/IdleMonitor/Core",
+            "--method",
+            "org.gnome.Mutter.IdleMonitor.GetIdletime",
+        ],
+    );
+    if let Some(ms) = mutter.as_deref().and_then(parse_gdbus_uint) {
+        return Some(Duration::from_millis(ms));
+    }
+
+    // KDE and other freedesktop screensavers (KDE reports milliseconds)
+    let screensaver = run_command(
+        "gdbus",
+        &[
+            "call",
+            "--session",
+            "--dest",
+            "org.freedesktop.ScreenSaver",
+            "--object-path",
+            "/org/freedesktop/ScreenSaver",
+            "--method",
+            "org.freedesktop.ScreenSaver.GetSessionIdleTime",
+        ],
+    );
+    if let Some(ms) = screensaver.as_deref().and_then(parse_gdbus_uint) {
+        return Some(Duration::from_millis(ms));
+    }
+
+    // X11 via the XScreenSaver extension
+    let xprintidle = run_command("xprintidle", &[]);
+    if let Some(ms) = xprintidle.and_then(|out| out.trim().parse::<u64>().ok()) {
+        return Some(Duration::from_millis(ms));
+    }
+
+    None
+}
+
+/// Idle time since the last user input event, if the platform can tell us.
+#[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+pub fn system_idle_time() -> Option<Duration> {
+    None
+}
+
+#[cfg(target_os = "linux")]
+fn run_command(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if output.status.success() {
+        Some(String::from_utf8_lossy(&output.stdout).to_string())
+    } else {
+        None
+    }
+}
+
+/// Parse a printed GVariant tuple holding one unsigned integer, e.g. `(uint64 12345,)`.
+#[cfg(target_os = "linux")]
+pub fn parse_gdbus_uint(output: &str) -> Option<u64> {
+    output
+        .trim()
+        .trim_start_matches('(')
+        .trim_end_matches(')')
+        .trim_end_matches(',')
+        .split_whitespace()
+        .last()?
+        .parse()
+        .ok()
+}
@@ -3,11 +3,22 @@ mod cache;
 mod commands;
 mod database;
 mod focus_mode;
+mod idle;
+#[cfg(target_os = "linux")]
+mod linux_window;
 mod local_proxy_blocker;
+mod mappings;
+mod matcher;
 mod migrations;
 mod models;
+mod pattern;
+mod schedule;
+mod timezone;
 mod tracker;
 mod tray;
+#[cfg(target_os = "linux")]
+mod wayland_window;
+mod window_source;
 
 #[cfg(test)]
 mod database_tests;
@@ -55,6 +66,10 @@ pub struct AppState {
     recently_hidden_apps: Arc<Mutex<std::collections::HashMap<String, Instant>>>,
     // Website blocker instance
     website_blocker: Arc<Mutex<Option<local_proxy_blocker::LocalProxyBlocker>>>,
+    // Next time a focus schedule turns focus mode on or off, shown in the tray
+    next_focus_transition: Arc<Mutex<Option<crate::models::FocusTransition>>>,
+    // Focus schedule whose window is in progress; its categories replace the usual ones
+    active_focus_schedule: Arc<Mutex<Option<crate::models::FocusSchedule>>>,
 }
 
 // =============================================================================
@@ -107,7 +122,7 @@ pub fn run() {
             let db_arc = Arc::new(db);
             let focus_enabled = false; // Always start with focus mode disabled
             let allowed_categories = rt
-                .block_on(db_arc.get_focus_mode_allowed_categories())
+                .block_on(db_arc.get_focus_mode_effective_categories())
                 .unwrap_or_default();
 
             // Load allowed apps cache from database
@@ -117,10 +132,17 @@ pub fn run() {
             let allowed_apps: std::collections::HashMap<String, Option<i64>> =
                 allowed_apps_vec.into_iter().collect();
 
+            // Apply the user's idle threshold to the tracker
+            let mut tracker = ActivityTracker::new();
+            let idle_threshold = rt
+                .block_on(db_arc.get_idle_threshold_seconds())
+                .unwrap_or(tracker::DEFAULT_IDLE_THRESHOLD_SECONDS);
+            tracker.set_inactive_threshold(std::time::Duration::from_secs(idle_threshold));
+
             // Initialize application state
             let state = AppState {
                 db: db_arc,
-                tracker: Arc::new(Mutex::new(ActivityTracker::new())),
+                tracker: Arc::new(Mutex::new(tracker)),
                 is_tracking: Arc::new(Mutex::new(true)), // Start tracking by default
                 pause_until: Arc::new(Mutex::new(None)),
                 current_activity: Arc::new(Mutex::new(None)),
@@ -135,6 +157,9 @@ pub fn run() {
                 recently_hidden_apps: Arc::new(Mutex::new(std::collections::HashMap::new())),
                 // Website blocker (initialized at startup)
                 website_blocker: Arc::new(Mutex::new(None)),
+                // Filled in by the focus schedule loop
+                next_focus_transition: Arc::new(Mutex::new(None)),
+                active_focus_schedule: Arc::new(Mutex::new(None)),
             };
 
             // Website blocker will be initialized on first use via commands
@@ -173,6 +198,13 @@ pub fn run() {
             });
             println!("✅ Activity tracking task spawned!");
 
+            // Turn focus mode on and off following the weekly schedules
+            let handle_clone = app_handle.clone();
+            std::thread::spawn(move || {
+                let rt = tokio::runtime::Runtime::new().unwrap();
+                rt.block_on(schedule::start_focus_scheduler(handle_clone));
+            });
+
             Ok(())
         })
         // Register Tauri commands that can be called from the frontend
@@ -185,16 +217,25 @@ pub fn run() {
             commands::resume_tracking,
             commands::toggle_tracking,
             commands::get_pause_status,
+            commands::get_idle_threshold,
+            commands::set_idle_threshold,
+            commands::get_timezone,
+            commands::set_timezone,
             commands::get_current_activity,
             commands::set_current_activity,
             commands::get_activities_by_date,
             commands::get_activities_by_date_range,
             commands::get_activity_summary,
+            commands::get_weekly_report,
+            commands::get_monthly_report,
+            commands::get_productivity_trend,
             commands::get_timeline_data,
+            commands::get_timeline_range,
             commands::get_categories,
             commands::load_categories,
             commands::add_category,
             commands::update_category,
+            commands::move_category,
             commands::delete_category,
             commands::get_app_mappings,
             commands::get_url_mappings,
@@ -209,10 +250,23 @@ pub fn run() {
             commands::pause_tracking_indefinitely,
             commands::resume_tracking_now,
             commands::update_activity_category,
+            commands::reset_activity_category,
+            commands::reapply_mappings,
+            commands::undo_recategorization,
+            commands::get_recategorization_runs,
+            commands::get_uncategorized_activity,
             commands::get_permission_status,
             commands::remove_app_mapping,
             commands::add_url_mapping,
             commands::remove_url_mapping,
+            commands::get_title_mappings,
+            commands::add_title_mapping,
+            commands::delete_title_mapping,
+            commands::get_mapping_conflicts,
+            commands::get_mapping_suggestions,
+            commands::accept_mapping_suggestion,
+            commands::assign_uncategorized,
+            commands::explain_categorization,
             // Focus mode commands
             commands::enable_focus_mode,
             commands::disable_focus_mode,
@@ -226,6 +280,11 @@ pub fn run() {
             commands::remove_focus_mode_allowed_app,
             commands::show_focus_overlay,
             commands::hide_focus_overlay,
+            // Focus schedule commands
+            commands::get_focus_schedules,
+            commands::save_focus_schedule,
+            commands::delete_focus_schedule,
+            commands::get_next_focus_transition,
             // Blocking preferences commands
             commands::get_app_blocking_enabled,
             commands::set_app_blocking_enabled,
@@ -260,8 +319,8 @@ pub fn run() {
         ])
         .build(tauri::generate_context!())
         .expect("error while building tauri application")
-        .run(|app_handle, event| match event {
-            tauri::RunEvent::Exit => {
+        .run(|app_handle, event| {
+            if let tauri::RunEvent::Exit = event {
                 println!("🛑 Application is exiting, disabling system proxy...");
                 // Get the app state
                 if let Some(state) = app_handle.try_state::<AppState>() {
@@ -278,6 +337,5 @@ pub fn run() {
                     }
                 }
             }
-            _ => {}
         });
 }
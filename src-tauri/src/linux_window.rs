@@ -0,0 +1,237 @@
+//! Foreground-window lookup for Linux.
+//!
+//! On X11 this mirrors the macOS approach of shelling out to a system tool: `xprop` is
+//! part of the standard X11 utilities and gives us `_NET_ACTIVE_WINDOW` on the root
+//! window plus `_NET_WM_NAME`, `WM_CLASS` and `_NET_WM_PID` on the focused window.
+//! Wayland sessions are handled by `wayland_window`; `active_window` picks the
+//! provider for the running session. The parsers are kept free of process spawning
+//! so they can be unit-tested anywhere.
+
+use std::process::Command;
+
+use crate::wayland_window;
+
+/// Properties of the focused window, whichever provider found it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinuxWindow {
+    /// X11 window id or compositor-specific window id
+    pub id: String,
+    pub title: Option<String>,
+    /// WM_CLASS instance name (first value), e.g. "code"
+    pub instance: Option<String>,
+    /// WM_CLASS class name (second value), e.g. "Code", or the Wayland app_id
+    pub class: Option<String>,
+    pub pid: Option<u32>,
+}
+
+/// Where the focused window is read from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WindowProvider {
+    /// X11 session, or XWayland as a last resort
+    X11,
+    /// wlroots compositors (Sway, Hyprland, river, ...) via wlr-foreign-toplevel-management
+    Wlroots,
+    /// GNOME Shell via the Window Calls extension's D-Bus interface
+    Gnome,
+}
+
+/// Pick a provider from `XDG_SESSION_TYPE` and `XDG_CURRENT_DESKTOP`.
+pub fn detect_provider(
+    session_type: Option<&str>,
+    current_desktop: Option<&str>,
+) -> WindowProvider {
+    if !session_type.is_some_and(|s| s.eq_ignore_ascii_case("wayland")) {
+        return WindowProvider::X11;
+    }
+
+    // XDG_CURRENT_DESKTOP is a colon-separated list, e.g. "ubuntu:GNOME"
+    let is_gnome = current_desktop
+        .is_some_and(|desktop| desktop.split(':').any(|d| d.eq_ignore_ascii_case("gnome")));
+
+    if is_gnome {
+        WindowProvider::Gnome
+    } else {
+        WindowProvider::Wlroots
+    }
+}
+
+/// Read the focused window using the provider for the current session.
+///
+/// Wayland providers fall back to X11 so XWayland windows are still seen when the
+/// compositor doesn't expose a usable protocol (e.g. KDE, or GNOME without the extension).
+pub fn active_window() -> Option<LinuxWindow> {
+    let session_type = std::env::var("XDG_SESSION_TYPE").ok();
+    let current_desktop = std::env::var("XDG_CURRENT_DESKTOP").ok();
+    let provider = detect_provider(session_type.as_deref(), current_desktop.as_deref());
+
+    let window = match provider {
+        WindowProvider::X11 => None,
+        WindowProvider::Wlroots => wayland_window::active_wlroots_window(None),
+        WindowProvider::Gnome => wayland_window::active_gnome_window(),
+    };
+
+    if window.is_none() && provider != WindowProvider::X11 {
+        println!("{:?} provider returned no window, trying X11", provider);
+    }
+
+    window.or_else(|| active_x11_window(None))
+}
+
+/// Read the currently focused window from the X server.
+///
+/// `display` overrides `$DISPLAY` (used by the Xvfb test); `None` uses the session display.
+pub fn active_x11_window(display: Option<&str>) -> Option<LinuxWindow> {
+    let root = run_xprop(display, &["-root", "_NET_ACTIVE_WINDOW"])?;
+    let id = parse_active_window_id(&root)?;
+
+    let props = run_xprop(
+        display,
+        &[
+            "-id",
+            &id,
+            "_NET_WM_NAME",
+            "WM_NAME",
+            "WM_CLASS",
+            "_NET_WM_PID",
+        ],
+    )?;
+
+    let title = parse_string_property(&props, "_NET_WM_NAME")
+        .or_else(|| parse_string_property(&props, "WM_NAME"))
+        .filter(|t| !t.is_empty());
+    let (instance, class) = match parse_wm_class(&props) {
+        Some((instance, class)) => (Some(instance), Some(class)),
+        None => (None, None),
+    };
+    let pid = parse_cardinal_property(&props, "_NET_WM_PID");
+
+    Some(LinuxWindow {
+        id,
+        title,
+        instance,
+        class,
+        pid,
+    })
+}
+
+/// Name of the executable that owns `pid`, read from `/proc`.
+pub fn process_name(pid: u32) -> Option<String> {
+    // Prefer the exe symlink since /proc/<pid>/comm is truncated to 15 characters
+    if let Ok(exe) = std::fs::read_link(format!("/proc/{}/exe", pid)) {
+        if let Some(name) = exe.file_name().and_then(|n| n.to_str()) {
+            return Some(name.to_string());
+        }
+    }
+
+    std::fs::read_to_string(format!("/proc/{}/comm", pid))
+        .ok()
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+fn run_xprop(display: Option<&str>, args: &[&str]) -> Option<String> {
+    let mut cmd = Command::new("xprop");
+    if let Some(display) = display {
+        cmd.arg("-display").arg(display);
+    }
+
+    match cmd.args(args).output() {
+        Ok(output) if output.status.success() => {
+            Some(String::from_utf8_lossy(&output.stdout).to_string())
+        }
+        Ok(output) => {
+            println!(
+                "xprop failed with status: {}, stderr: {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+            None
+        }
+        Err(e) => {
+            println!("Failed to execute xprop: {}", e);
+            None
+        }
+    }
+}
+
+/// Parse `_NET_ACTIVE_WINDOW(WINDOW): window id # 0x3a00007`.
+///
+/// Returns `None` when no window has focus (id `0x0`) or the property is missing.
+pub fn parse_active_window_id(output: &str) -> Option<String> {
+    let line = output
+        .lines()
+        .find(|l| l.starts_with("_NET_ACTIVE_WINDOW"))?;
+    // Window managers print `window id # 0x..`; a property set by hand prints `= 0x..`
+    let value = line.rsplit(['#', '=']).next()?.trim();
+    // Some window managers append a second id after a comma
+    let value = value.split(',').next()?.trim();
+
+    let id = match value.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16).ok()?,
+        None => value.parse().ok()?,
+    };
+    if id == 0 {
+        return None;
+    }
+    Some(format!("0x{:x}", id))
+}
+
+/// Parse a quoted string property such as `_NET_WM_NAME(UTF8_STRING) = "title"`.
+pub fn parse_string_property(output: &str, name: &str) -> Option<String> {
+    let value = property_value(output, name)?;
+    parse_quoted_values(value).into_iter().next()
+}
+
+/// Parse `WM_CLASS(STRING) = "code", "Code"` into `(instance, class)`.
+pub fn parse_wm_class(output: &str) -> Option<(String, String)> {
+    let value = property_value(output, "WM_CLASS")?;
+    let mut values = parse_quoted_values(value).into_iter();
+    let instance = values.next()?;
+    let class = values.next().unwrap_or_else(|| instance.clone());
+    Some((instance, class))
+}
+
+/// Parse a numeric property such as `_NET_WM_PID(CARDINAL) = 12345`.
+pub fn parse_cardinal_property(output: &str, name: &str) -> Option<u32> {
+    property_value(output, name)?.trim().parse().ok()
+}
+
+fn property_value<'a>(output: &'a str, name: &str) -> Option<&'a str> {
+    output.lines().find_map(|line| {
+        let rest = line.strip_prefix(name)?;
+        // Require the `(TYPE)` suffix so e.g. WM_NAME doesn't match WM_NAME_EXTRA
+        if !rest.starts_with('(') {
+            return None;
+        }
+        let (_, value) = rest.split_once(" = ")?;
+        Some(value)
+    })
+}
+
+/// Split a comma-separated list of xprop quoted strings, handling `\"` and `\\` escapes.
+fn parse_quoted_values(value: &str) -> Vec<String> {
+    let mut values = Vec::new();
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '"' {
+            continue;
+        }
+
+        let mut current = String::new();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => {
+                    if let Some(escaped) = chars.next() {
+                        current.push(escaped);
+                    }
+                }
+                '"' => break,
+                _ => current.push(c),
+            }
+        }
+        values.push(current);
+    }
+
+    values
+}
@@ -1,4 +1,5 @@
 use crate::database::Database;
+#[cfg(any(target_os = "macos", target_os = "windows"))]
 use std::process::Command;
 use std::sync::Arc;
 use std::time::Duration;
@@ -537,7 +538,7 @@ impl LocalProxyBlocker {
         // Log results
         let mut errors = 0;
 
-        if let Err(_) = result1 {
+        if result1.is_err() {
             Self::log_event(
                 &proxy_logs,
                 "TIMEOUT",
@@ -548,7 +549,7 @@ impl LocalProxyBlocker {
             errors += 1;
         }
 
-        if let Err(_) = result2 {
+        if result2.is_err() {
             Self::log_event(
                 &proxy_logs,
                 "TIMEOUT",
@@ -582,11 +583,9 @@ impl LocalProxyBlocker {
     }
 
     fn extract_host_from_url(url: &str) -> Option<String> {
-        if url.starts_with("http://") {
-            let without_protocol = &url[7..];
+        if let Some(without_protocol) = url.strip_prefix("http://") {
             Some(without_protocol.split('/').next()?.to_string())
-        } else if url.starts_with("https://") {
-            let without_protocol = &url[8..];
+        } else if let Some(without_protocol) = url.strip_prefix("https://") {
             Some(without_protocol.split('/').next()?.to_string())
         } else if url.contains("://") {
             let parts: Vec<&str> = url.split("://").collect();
@@ -675,7 +674,7 @@ impl LocalProxyBlocker {
         {
             // Check HTTP proxy
             let output = Command::new("networksetup")
-                .args(&["-getwebproxy", "Wi-Fi"])
+                .args(["-getwebproxy", "Wi-Fi"])
                 .output()
                 .map_err(|e| format!("Failed to check HTTP proxy: {}", e))?;
 
@@ -693,7 +692,7 @@ impl LocalProxyBlocker {
 
             // Check HTTPS proxy
             let output = Command::new("networksetup")
-                .args(&["-getsecurewebproxy", "Wi-Fi"])
+                .args(["-getsecurewebproxy", "Wi-Fi"])
                 .output()
                 .map_err(|e| format!("Failed to check HTTPS proxy: {}", e))?;
 
@@ -715,7 +714,7 @@ impl LocalProxyBlocker {
         #[cfg(target_os = "windows")]
         {
             let output = Command::new("netsh")
-                .args(&["winhttp", "show", "proxy"])
+                .args(["winhttp", "show", "proxy"])
                 .output()
                 .map_err(|e| format!("Failed to check system proxy: {}", e))?;
 
@@ -742,7 +741,7 @@ impl LocalProxyBlocker {
         {
             // Enable HTTP proxy
             let output = Command::new("networksetup")
-                .args(&[
+                .args([
                     "-setwebproxy",
                     "Wi-Fi",
                     "127.0.0.1",
@@ -759,7 +758,7 @@ impl LocalProxyBlocker {
 
             // Enable HTTPS proxy
             let output = Command::new("networksetup")
-                .args(&[
+                .args([
                     "-setsecurewebproxy",
                     "Wi-Fi",
                     "127.0.0.1",
@@ -787,7 +786,7 @@ impl LocalProxyBlocker {
         #[cfg(target_os = "windows")]
         {
             let output = Command::new("netsh")
-                .args(&[
+                .args([
                     "winhttp",
                     "set",
                     "proxy",
@@ -826,7 +825,7 @@ impl LocalProxyBlocker {
         {
             // Disable HTTP proxy
             let output = Command::new("networksetup")
-                .args(&["-setwebproxystate", "Wi-Fi", "off"])
+                .args(["-setwebproxystate", "Wi-Fi", "off"])
                 .output()
                 .map_err(|e| format!("Failed to disable HTTP proxy: {}", e))?;
             if !output.status.success() {
@@ -838,7 +837,7 @@ impl LocalProxyBlocker {
 
             // Disable HTTPS proxy
             let output = Command::new("networksetup")
-                .args(&["-setsecurewebproxystate", "Wi-Fi", "off"])
+                .args(["-setsecurewebproxystate", "Wi-Fi", "off"])
                 .output()
                 .map_err(|e| format!("Failed to disable HTTPS proxy: {}", e))?;
             if !output.status.success() {
@@ -861,7 +860,7 @@ impl LocalProxyBlocker {
         #[cfg(target_os = "windows")]
         {
             let output = Command::new("netsh")
-                .args(&["winhttp", "reset", "proxy"])
+                .args(["winhttp", "reset", "proxy"])
                 .output()
                 .map_err(|e| format!("Failed to disable system proxy: {}", e))?;
             if !output.status.success() {
@@ -0,0 +1,423 @@
+//! Precedence between app/URL/title mappings and detection of mappings that overlap.
+//!
+//! URL mappings are tried first, then window-title rules, then app mappings. Within one
+//! table the winner is decided by, in order: custom mappings over seeded ones, the explicit
+//! `priority` (higher first), title rules limited to an app over unlimited ones,
+//! the match kind (exact, domain suffix, glob, substring, regex) and the amount of literal
+//! text in the pattern (longer first). Remaining ties are broken by pattern and category id so the
+//! result never depends on insertion order or generated ids.
+//!
+//! Manual recategorizations feed back into the tables as suggested mappings.
+
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+use crate::matcher::CompiledMappings;
+use crate::models::{
+    ActivityEntry, AppMapping, MappingConflict, MappingRef, MappingSuggestion, MappingTarget,
+    MatchKind, TitleMapping, UrlMapping,
+};
+use crate::pattern;
+
+/// How many times an app or domain must be recategorized the same way before a mapping
+/// is suggested
+pub const DEFAULT_SUGGESTION_THRESHOLD: usize = 3;
+
+/// How strongly a mapping claims a value; the greater value wins
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Precedence {
+    is_custom: bool,
+    priority: i32,
+    scoped: bool,
+    kind_rank: u8,
+    literal_len: usize,
+}
+
+impl From<&AppMapping> for MappingRef {
+    fn from(mapping: &AppMapping) -> Self {
+        MappingRef {
+            id: mapping.id,
+            pattern: mapping.app_pattern.clone(),
+            category_id: mapping.category_id.clone(),
+            is_custom: mapping.is_custom,
+            match_kind: mapping.match_kind,
+            priority: mapping.priority,
+            app_scope: None,
+        }
+    }
+}
+
+impl From<&UrlMapping> for MappingRef {
+    fn from(mapping: &UrlMapping) -> Self {
+        MappingRef {
+            id: mapping.id,
+            pattern: mapping.url_pattern.clone(),
+            category_id: mapping.category_id.clone(),
+            is_custom: mapping.is_custom,
+            match_kind: mapping.match_kind,
+            priority: mapping.priority,
+            app_scope: None,
+        }
+    }
+}
+
+impl From<&TitleMapping> for MappingRef {
+    fn from(mapping: &TitleMapping) -> Self {
+        MappingRef {
+            id: mapping.id,
+            pattern: mapping.title_pattern.clone(),
+            category_id: mapping.category_id.clone(),
+            is_custom: mapping.is_custom,
+            match_kind: mapping.match_kind,
+            priority: mapping.priority,
+            app_scope: mapping.app_pattern.clone(),
+        }
+    }
+}
+
+pub fn precedence(mapping: &MappingRef) -> Precedence {
+    Precedence {
+        is_custom: mapping.is_custom,
+        priority: mapping.priority,
+        scoped: mapping.app_scope.is_some(),
+        kind_rank: kind_rank(mapping.match_kind),
+        literal_len: literal_len(mapping.match_kind, &mapping.pattern),
+    }
+}
+
+/// Order app mappings so the first one that matches is the one that should win
+pub fn sort_app_mappings(mappings: &mut [AppMapping]) {
+    mappings.sort_by_cached_key(|mapping| sort_key(&MappingRef::from(mapping)));
+}
+
+/// Order URL mappings so the first one that matches is the one that should win
+pub fn sort_url_mappings(mappings: &mut [UrlMapping]) {
+    mappings.sort_by_cached_key(|mapping| sort_key(&MappingRef::from(mapping)));
+}
+
+/// Order window-title rules so the first one that matches is the one that should win
+pub fn sort_title_mappings(mappings: &mut [TitleMapping]) {
+    mappings.sort_by_cached_key(|mapping| sort_key(&MappingRef::from(mapping)));
+}
+
+/// Sets of app mappings that match the same app name.
+///
+/// Candidates are the literal parts of every pattern plus `observed` app names, so regex
+/// mappings are only reported when they match another pattern or something tracked.
+pub fn app_conflicts(mappings: &[AppMapping], observed: &[String]) -> Vec<MappingConflict> {
+    let refs = mappings.iter().map(MappingRef::from).collect();
+    let observed = observed.iter().map(|value| (None, value.clone())).collect();
+    conflicts(MappingTarget::App, refs, observed)
+}
+
+/// Sets of URL mappings that match the same URL, see [`app_conflicts`]
+pub fn url_conflicts(mappings: &[UrlMapping], observed: &[String]) -> Vec<MappingConflict> {
+    let refs = mappings.iter().map(MappingRef::from).collect();
+    let observed = observed.iter().map(|value| (None, value.clone())).collect();
+    conflicts(MappingTarget::Url, refs, observed)
+}
+
+/// Sets of window-title rules that match the same title in the same app, tested against
+/// `observed` (app name, window title) pairs as well. Rules limited to different apps
+/// never conflict.
+pub fn title_conflicts(
+    mappings: &[TitleMapping],
+    observed: &[(String, String)],
+) -> Vec<MappingConflict> {
+    let refs = mappings.iter().map(MappingRef::from).collect();
+    let observed = observed
+        .iter()
+        .map(|(app_name, title)| (Some(app_name.clone()), title.clone()))
+        .collect();
+    conflicts(MappingTarget::Title, refs, observed)
+}
+
+/// Mappings for apps and domains the user recategorized the same way at least
+/// `min_corrections` times.
+///
+/// Activities with a URL count towards their domain, others towards their app name.
+/// Nothing is suggested where the current mappings already give the corrected category.
+pub fn suggest_mappings(
+    activities: &[ActivityEntry],
+    compiled: &CompiledMappings,
+    min_corrections: usize,
+) -> Vec<MappingSuggestion> {
+    let mut groups: HashMap<(MappingTarget, String, String), (usize, DateTime<Utc>)> =
+        HashMap::new();
+    for activity in activities {
+        let provenance = &activity.provenance;
+        let Some(corrected) = &provenance.override_category else {
+            continue;
+        };
+        let Some(category_id) = corrected.category_id() else {
+            continue;
+        };
+        if provenance.computed_category.as_ref() == Some(corrected) {
+            continue;
+        }
+
+        let domain = activity
+            .url
+            .as_deref()
+            .and_then(pattern::parse_url)
+            .and_then(|url| url.host_str().map(str::to_lowercase));
+        let key = match domain {
+            Some(domain) => (
+                MappingTarget::Url,
+                domain.trim_start_matches("www.").to_string(),
+                category_id.to_string(),
+            ),
+            None => (
+                MappingTarget::App,
+                activity.app_name.clone(),
+                category_id.to_string(),
+            ),
+        };
+
+        let corrected_at = provenance.overridden_at.unwrap_or(activity.start_time);
+        let (count, last) = groups.entry(key).or_insert((0, corrected_at));
+        *count += 1;
+        *last = (*last).max(corrected_at);
+    }
+
+    let mut suggestions: Vec<MappingSuggestion> = groups
+        .into_iter()
+        .filter(|(_, (count, _))| *count >= min_corrections)
+        .filter_map(|((target, pattern, category_id), (corrections, last))| {
+            let current = match target {
+                MappingTarget::Url => compiled.match_url(&format!("https://{}/", pattern)),
+                _ => compiled.match_app(&pattern, None),
+            };
+            let current_category_id = current
+                .first()
+                .map(|(_, mapping)| mapping.category_id.clone());
+            if current_category_id.as_deref() == Some(category_id.as_str()) {
+                return None;
+            }
+
+            Some(MappingSuggestion {
+                target,
+                match_kind: match target {
+                    MappingTarget::Url => MatchKind::DomainSuffix,
+                    _ => MatchKind::Exact,
+                },
+                pattern,
+                category_id,
+                current_category_id,
+                corrections,
+                last_corrected_at: last,
+            })
+        })
+        .collect();
+
+    suggestions.sort_by(|a, b| {
+        b.corrections
+            .cmp(&a.corrections)
+            .then_with(|| a.pattern.cmp(&b.pattern))
+            .then_with(|| a.category_id.cmp(&b.category_id))
+    });
+    suggestions
+}
+
+/// Why `winner` is applied instead of `loser`
+pub fn explain_win(winner: &MappingRef, loser: &MappingRef) -> String {
+    let (w, l) = (precedence(winner), precedence(loser));
+    let reason = if w.is_custom != l.is_custom {
+        "custom mappings beat seeded ones".to_string()
+    } else if w.priority != l.priority {
+        format!("priority {} is higher than {}", w.priority, l.priority)
+    } else if w.scoped != l.scoped {
+        "rules limited to an app beat ones for every app".to_string()
+    } else if w.kind_rank != l.kind_rank {
+        format!(
+            "{} matches are more specific than {} matches",
+            winner.match_kind.as_str(),
+            loser.match_kind.as_str()
+        )
+    } else if w.literal_len != l.literal_len {
+        "its pattern is longer and more specific".to_string()
+    } else {
+        "both are equally specific, ties go to the alphabetically first pattern".to_string()
+    };
+
+    format!(
+        "{} wins over {}: {}",
+        describe(winner),
+        describe(loser),
+        reason
+    )
+}
+
+fn sort_key(mapping: &MappingRef) -> (Reverse<Precedence>, String, String, Uuid) {
+    (
+        Reverse(precedence(mapping)),
+        mapping.pattern.to_lowercase(),
+        mapping.category_id.clone(),
+        mapping.id,
+    )
+}
+
+fn kind_rank(kind: MatchKind) -> u8 {
+    match kind {
+        MatchKind::Exact => 4,
+        MatchKind::DomainSuffix => 3,
+        MatchKind::Glob => 2,
+        MatchKind::Substring => 1,
+        // A regex can't be ranked without understanding it
+        MatchKind::Regex => 0,
+    }
+}
+
+/// Characters that must literally appear, counted on the shortest alternative
+fn literal_len(kind: MatchKind, pattern: &str) -> usize {
+    if kind == MatchKind::Regex {
+        return 0;
+    }
+
+    pattern
+        .split('|')
+        .map(str::trim)
+        .filter(|alternative| !alternative.is_empty())
+        .map(|alternative| {
+            alternative
+                .chars()
+                .filter(|c| kind != MatchKind::Glob || (*c != '*' && *c != '?'))
+                .count()
+        })
+        .min()
+        .unwrap_or(0)
+}
+
+/// `observed` values come with the app they were seen in, which only title rules look at
+fn conflicts(
+    target: MappingTarget,
+    mut mappings: Vec<MappingRef>,
+    observed: Vec<(Option<String>, String)>,
+) -> Vec<MappingConflict> {
+    mappings.sort_by_cached_key(sort_key);
+
+    let candidates = mappings
+        .iter()
+        .flat_map(|mapping| {
+            // A rule limited to apps is sampled in the first of them
+            let app_name = mapping
+                .app_scope
+                .as_deref()
+                .and_then(|scope| scope.split('|').map(str::trim).find(|app| !app.is_empty()))
+                .map(str::to_string);
+            samples(target, mapping)
+                .into_iter()
+                .map(move |value| (app_name.clone(), value))
+        })
+        .chain(observed)
+        .collect::<Vec<_>>();
+
+    let mut seen = HashSet::new();
+    let mut result = Vec::new();
+    for (app_name, value) in candidates {
+        let matching: Vec<&MappingRef> = mappings
+            .iter()
+            .filter(|mapping| matches(target, mapping, app_name.as_deref(), &value))
+            .collect();
+        let Some((winner, overridden)) = matching.split_first() else {
+            continue;
+        };
+        if overridden.is_empty() {
+            continue;
+        }
+
+        let ids: Vec<Uuid> = matching.iter().map(|mapping| mapping.id).collect();
+        if !seen.insert(ids) {
+            continue;
+        }
+
+        result.push(MappingConflict {
+            target,
+            sample: match app_name {
+                Some(app_name) => format!("{}: {}", app_name, value),
+                None => value,
+            },
+            winner: (*winner).clone(),
+            overridden: overridden
+                .iter()
+                .map(|mapping| (*mapping).clone())
+                .collect(),
+            same_category: overridden
+                .iter()
+                .all(|mapping| mapping.category_id == winner.category_id),
+            explanation: overridden
+                .iter()
+                .map(|loser| explain_win(winner, loser))
+                .collect::<Vec<_>>()
+                .join("; "),
+        });
+    }
+
+    // Contradictory overlaps first, redundant ones after
+    result.sort_by_key(|conflict| conflict.same_category);
+    result
+}
+
+fn matches(
+    target: MappingTarget,
+    mapping: &MappingRef,
+    app_name: Option<&str>,
+    value: &str,
+) -> bool {
+    match target {
+        MappingTarget::App => pattern::app_matches(mapping.match_kind, &mapping.pattern, value),
+        MappingTarget::Url => pattern::url_matches(mapping.match_kind, &mapping.pattern, value),
+        MappingTarget::Title => pattern::title_matches(
+            mapping.match_kind,
+            &mapping.pattern,
+            mapping.app_scope.as_deref(),
+            app_name.unwrap_or_default(),
+            value,
+        ),
+    }
+}
+
+/// Values the mapping is known to match, built from the literal parts of its pattern
+fn samples(target: MappingTarget, mapping: &MappingRef) -> Vec<String> {
+    if mapping.match_kind == MatchKind::Regex {
+        return Vec::new();
+    }
+
+    mapping
+        .pattern
+        .split('|')
+        .map(str::trim)
+        .filter(|alternative| !alternative.is_empty())
+        .map(|alternative| {
+            let literal = if mapping.match_kind == MatchKind::Glob {
+                // `*` can match nothing, `?` needs exactly one character
+                alternative.replace('*', "").replace('?', "x")
+            } else {
+                alternative.to_string()
+            };
+            match target {
+                MappingTarget::Url if !literal.contains("://") => {
+                    format!("https://{}", literal.trim_start_matches('.'))
+                }
+                _ => literal,
+            }
+        })
+        .collect()
+}
+
+fn describe(mapping: &MappingRef) -> String {
+    format!(
+        "'{}' ({} {} mapping to {})",
+        mapping.pattern,
+        if mapping.is_custom {
+            "custom"
+        } else {
+            "seeded"
+        },
+        mapping.match_kind.as_str(),
+        mapping.category_id
+    )
+}
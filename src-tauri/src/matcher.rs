@@ -0,0 +1,257 @@
+//! Mappings compiled once for categorization.
+//!
+//! Patterns are lowercased and indexed when the set is built: exact alternatives and
+//! identifiers in hash maps, substrings in one Aho-Corasick automaton, domain suffixes by
+//! domain so a URL only looks up its host and the host's parent domains. Globs and regexes
+//! are compiled once and tried one by one. Matching gives the same answers as `pattern`.
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use aho_corasick::AhoCorasick;
+use regex::Regex;
+
+use crate::models::{AppMapping, MappingRef, MatchKind, TitleMapping, UrlMapping};
+use crate::pattern;
+
+/// URL, title and app mappings, each in precedence order
+pub struct CompiledMappings {
+    urls: CompiledTable,
+    titles: CompiledTable,
+    apps: CompiledTable,
+}
+
+impl CompiledMappings {
+    /// Compile mappings in the order `Database::get_*_mappings` returns them
+    pub fn new(
+        url_mappings: &[UrlMapping],
+        title_mappings: &[TitleMapping],
+        app_mappings: &[AppMapping],
+    ) -> Self {
+        let mut urls = CompiledTable::new(true);
+        for mapping in url_mappings {
+            urls.push(MappingRef::from(mapping), None);
+        }
+
+        let mut titles = CompiledTable::new(false);
+        for mapping in title_mappings {
+            titles.push(MappingRef::from(mapping), None);
+        }
+
+        let mut apps = CompiledTable::new(false);
+        for mapping in app_mappings {
+            apps.push(MappingRef::from(mapping), mapping.identifiers.as_deref());
+        }
+
+        CompiledMappings {
+            urls: urls.finish(),
+            titles: titles.finish(),
+            apps: apps.finish(),
+        }
+    }
+
+    /// URL mappings that match `url`, best first, with their index in precedence order
+    pub fn match_url(&self, url: &str) -> Vec<(usize, &MappingRef)> {
+        self.urls.resolve(self.url_candidates(url))
+    }
+
+    /// Title rules that match `title` and are not limited to other apps
+    pub fn match_title(&self, app_name: &str, title: &str) -> Vec<(usize, &MappingRef)> {
+        self.titles.resolve(self.title_candidates(app_name, title))
+    }
+
+    /// App mappings that match the app name or its bundle id / executable / WM_CLASS
+    pub fn match_app(&self, app_name: &str, bundle_id: Option<&str>) -> Vec<(usize, &MappingRef)> {
+        self.apps.resolve(self.app_candidates(app_name, bundle_id))
+    }
+
+    /// The URL mapping that wins for `url`, without collecting the ones it overrides
+    pub fn best_url(&self, url: &str) -> Option<&MappingRef> {
+        self.urls.first(self.url_candidates(url))
+    }
+
+    /// The title rule that wins for `title`
+    pub fn best_title(&self, app_name: &str, title: &str) -> Option<&MappingRef> {
+        self.titles.first(self.title_candidates(app_name, title))
+    }
+
+    /// The app mapping that wins for the app
+    pub fn best_app(&self, app_name: &str, bundle_id: Option<&str>) -> Option<&MappingRef> {
+        self.apps.first(self.app_candidates(app_name, bundle_id))
+    }
+
+    fn url_candidates(&self, url: &str) -> BTreeSet<usize> {
+        let mut found = self.urls.candidates(url);
+        self.urls.domain_candidates(url, &mut found);
+        found
+    }
+
+    fn title_candidates(&self, app_name: &str, title: &str) -> BTreeSet<usize> {
+        let app_name = app_name.to_lowercase();
+        let mut found = self.titles.candidates(title);
+        found.retain(|&index| {
+            self.titles.scopes[index]
+                .as_ref()
+                .is_none_or(|scope| scope.contains(&app_name))
+        });
+        found
+    }
+
+    fn app_candidates(&self, app_name: &str, bundle_id: Option<&str>) -> BTreeSet<usize> {
+        let mut found = self.apps.candidates(app_name);
+        if let Some(bundle_id) = bundle_id {
+            let bundle_id = pattern::normalize_identifier(bundle_id);
+            if let Some(indexes) = self.apps.identifiers.get(&bundle_id) {
+                found.extend(indexes);
+            }
+        }
+        found
+    }
+}
+
+struct CompiledTable {
+    is_url: bool,
+    entries: Vec<MappingRef>,
+    scopes: Vec<Option<HashSet<String>>>,
+    exact: HashMap<String, Vec<usize>>,
+    substrings: Vec<String>,
+    substring_owners: Vec<usize>,
+    automaton: Option<AhoCorasick>,
+    domains: HashMap<String, Vec<(usize, String)>>,
+    identifiers: HashMap<String, Vec<usize>>,
+    wildcards: Vec<(usize, Regex)>,
+}
+
+impl CompiledTable {
+    fn new(is_url: bool) -> Self {
+        CompiledTable {
+            is_url,
+            entries: Vec::new(),
+            scopes: Vec::new(),
+            exact: HashMap::new(),
+            substrings: Vec::new(),
+            substring_owners: Vec::new(),
+            automaton: None,
+            domains: HashMap::new(),
+            identifiers: HashMap::new(),
+            wildcards: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, mapping: MappingRef, identifiers: Option<&str>) {
+        let index = self.entries.len();
+
+        match mapping.match_kind {
+            MatchKind::Exact => {
+                for alternative in pattern::alternatives(&mapping.pattern) {
+                    self.exact.entry(alternative).or_default().push(index);
+                }
+            }
+            MatchKind::DomainSuffix if self.is_url => {
+                for alternative in pattern::alternatives(&mapping.pattern) {
+                    let domain = alternative.split('/').next().unwrap_or_default();
+                    let domain = domain.trim_start_matches('.').to_string();
+                    self.domains
+                        .entry(domain)
+                        .or_default()
+                        .push((index, alternative));
+                }
+            }
+            // Domain suffixes outside URLs are plain substrings, as in `pattern`
+            MatchKind::Substring | MatchKind::DomainSuffix => {
+                for alternative in pattern::alternatives(&mapping.pattern) {
+                    self.substrings.push(alternative);
+                    self.substring_owners.push(index);
+                }
+            }
+            MatchKind::Glob | MatchKind::Regex => {
+                if let Some(regex) =
+                    pattern::compile_wildcards(mapping.match_kind, &mapping.pattern)
+                {
+                    self.wildcards.push((index, regex));
+                }
+            }
+        }
+
+        for identifier in identifiers.unwrap_or_default().split('|') {
+            let identifier = pattern::normalize_identifier(identifier);
+            if !identifier.is_empty() {
+                self.identifiers.entry(identifier).or_default().push(index);
+            }
+        }
+
+        self.scopes.push(
+            mapping
+                .app_scope
+                .as_deref()
+                .map(|scope| pattern::alternatives(scope).collect()),
+        );
+        self.entries.push(mapping);
+    }
+
+    fn finish(mut self) -> Self {
+        if !self.substrings.is_empty() {
+            // Patterns are plain lowercase strings, building the automaton can't fail
+            self.automaton = AhoCorasick::new(&self.substrings).ok();
+        }
+        self
+    }
+
+    /// Entries whose exact, substring, glob or regex pattern matches `value`
+    fn candidates(&self, value: &str) -> BTreeSet<usize> {
+        let value = value.to_lowercase();
+        let mut found = BTreeSet::new();
+
+        if let Some(indexes) = self.exact.get(&value) {
+            found.extend(indexes);
+        }
+        if let Some(automaton) = &self.automaton {
+            for hit in automaton.find_overlapping_iter(&value) {
+                found.insert(self.substring_owners[hit.pattern().as_usize()]);
+            }
+        }
+        for (index, regex) in &self.wildcards {
+            if !found.contains(index) && regex.is_match(&value) {
+                found.insert(*index);
+            }
+        }
+
+        found
+    }
+
+    /// Entries whose domain suffix matches the URL's host or one of its parent domains
+    fn domain_candidates(&self, url: &str, found: &mut BTreeSet<usize>) {
+        if self.domains.is_empty() {
+            return;
+        }
+        let Some(url) = pattern::parse_url(url) else {
+            return;
+        };
+        let Some(host) = url.host_str().map(str::to_lowercase) else {
+            return;
+        };
+
+        let mut domain = host.as_str();
+        loop {
+            for (index, alternative) in self.domains.get(domain).into_iter().flatten() {
+                if pattern::domain_suffix_matches(alternative, &url) {
+                    found.insert(*index);
+                }
+            }
+            match domain.split_once('.') {
+                Some((_, parent)) => domain = parent,
+                None => break,
+            }
+        }
+    }
+
+    fn resolve(&self, found: BTreeSet<usize>) -> Vec<(usize, &MappingRef)> {
+        found
+            .into_iter()
+            .map(|index| (index, &self.entries[index]))
+            .collect()
+    }
+
+    fn first(&self, found: BTreeSet<usize>) -> Option<&MappingRef> {
+        found.first().map(|&index| &self.entries[index])
+    }
+}
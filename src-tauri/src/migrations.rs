@@ -1,7 +1,8 @@
-use serde_json;
 use sqlx::{Row, SqlitePool};
 use std::collections::HashMap;
 
+use crate::models::{MatchKind, Productivity};
+
 #[derive(Debug)]
 pub struct Migration {
     pub version: i32,
@@ -37,6 +38,56 @@ pub fn get_migrations() -> Vec<Migration> {
             description: "create_pomodoro_tables".to_string(),
             sql: include_str!("../migrations/5_create_pomodoro_tables.sql").to_string(),
         },
+        Migration {
+            version: 6,
+            description: "add_idle_threshold".to_string(),
+            sql: include_str!("../migrations/6_add_idle_threshold.sql").to_string(),
+        },
+        Migration {
+            version: 7,
+            description: "add_mapping_match_kind".to_string(),
+            sql: include_str!("../migrations/7_add_mapping_match_kind.sql").to_string(),
+        },
+        Migration {
+            version: 8,
+            description: "add_mapping_priority".to_string(),
+            sql: include_str!("../migrations/8_add_mapping_priority.sql").to_string(),
+        },
+        Migration {
+            version: 9,
+            description: "create_title_mappings".to_string(),
+            sql: include_str!("../migrations/9_create_title_mappings.sql").to_string(),
+        },
+        Migration {
+            version: 10,
+            description: "add_app_mapping_identifiers".to_string(),
+            sql: include_str!("../migrations/10_add_app_mapping_identifiers.sql").to_string(),
+        },
+        Migration {
+            version: 11,
+            description: "add_recategorization".to_string(),
+            sql: include_str!("../migrations/11_add_recategorization.sql").to_string(),
+        },
+        Migration {
+            version: 12,
+            description: "add_category_provenance".to_string(),
+            sql: include_str!("../migrations/12_add_category_provenance.sql").to_string(),
+        },
+        Migration {
+            version: 13,
+            description: "store_category_ids".to_string(),
+            sql: include_str!("../migrations/13_store_category_ids.sql").to_string(),
+        },
+        Migration {
+            version: 14,
+            description: "add_category_productivity".to_string(),
+            sql: include_str!("../migrations/14_add_category_productivity.sql").to_string(),
+        },
+        Migration {
+            version: 15,
+            description: "create_focus_schedules".to_string(),
+            sql: include_str!("../migrations/15_create_focus_schedules.sql").to_string(),
+        },
     ]
 }
 
@@ -137,67 +188,130 @@ pub async fn load_initial_data(pool: &SqlitePool) -> Result<(), sqlx::Error> {
 
     if existing_seed.is_some() {
         println!("✅ Initial data already loaded");
-        return Ok(());
+    } else {
+        // Load categories
+        load_categories(pool).await?;
+
+        // Load app mappings
+        load_app_mappings(pool).await?;
+
+        // Load URL mappings
+        load_url_mappings(pool).await?;
+
+        // Mark data as loaded
+        mark_seed_loaded(pool, "initial_data").await?;
+
+        println!("🎉 Initial data loaded successfully");
+    }
+
+    // Identifiers, title rules and productivity weights came later, so existing databases
+    // get them through their own seed markers
+    let identifiers_seed = sqlx::query("SELECT id FROM data_seeds WHERE id = 'app_identifiers'")
+        .fetch_optional(pool)
+        .await?;
+    if identifiers_seed.is_none() {
+        load_app_identifiers(pool).await?;
+        mark_seed_loaded(pool, "app_identifiers").await?;
     }
 
-    // Load categories
-    load_categories(pool).await?;
+    let title_seed = sqlx::query("SELECT id FROM data_seeds WHERE id = 'title_mappings'")
+        .fetch_optional(pool)
+        .await?;
+    if title_seed.is_none() {
+        load_title_mappings(pool).await?;
+        mark_seed_loaded(pool, "title_mappings").await?;
+    }
 
-    // Load app mappings
-    load_app_mappings(pool).await?;
+    let productivity_seed =
+        sqlx::query("SELECT id FROM data_seeds WHERE id = 'category_productivity'")
+            .fetch_optional(pool)
+            .await?;
+    if productivity_seed.is_none() {
+        load_category_productivity(pool).await?;
+        mark_seed_loaded(pool, "category_productivity").await?;
+    }
 
-    // Load URL mappings
-    load_url_mappings(pool).await?;
+    Ok(())
+}
 
-    // Mark data as loaded
+async fn mark_seed_loaded(pool: &SqlitePool, seed_id: &str) -> Result<(), sqlx::Error> {
     sqlx::query("INSERT INTO data_seeds (id, loaded_at) VALUES (?, ?)")
-        .bind("initial_data")
+        .bind(seed_id)
         .bind(chrono::Utc::now().to_rfc3339())
         .execute(pool)
         .await?;
-
-    println!("🎉 Initial data loaded successfully");
     Ok(())
 }
 
 async fn load_categories(pool: &SqlitePool) -> Result<(), sqlx::Error> {
     println!("📂 Loading categories...");
 
-    let categories_json = include_str!("../../data/categories.json");
-    let categories_data: serde_json::Value =
-        serde_json::from_str(categories_json).map_err(|e| {
-            sqlx::Error::Decode(format!("Failed to parse categories.json: {}", e).into())
-        })?;
+    for category in seeded_categories()? {
+        if let (Some(id), Some(name), Some(color)) = (
+            category.get("id").and_then(|v| v.as_str()),
+            category.get("name").and_then(|v| v.as_str()),
+            category.get("color").and_then(|v| v.as_str()),
+        ) {
+            let parent_id = category.get("parent_id").and_then(|v| v.as_str());
+            let now = chrono::Utc::now().to_rfc3339();
+
+            sqlx::query(
+                "INSERT OR IGNORE INTO user_categories (id, name, color, parent_id, productivity, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?)"
+            )
+            .bind(id)
+            .bind(name)
+            .bind(color)
+            .bind(parent_id)
+            .bind(seeded_productivity(&category).weight())
+            .bind(&now)
+            .bind(&now)
+            .execute(pool)
+            .await?;
+        }
+    }
+    println!("✅ Categories loaded");
 
-    if let Some(categories) = categories_data.get("categories").and_then(|c| c.as_array()) {
-        for category in categories {
-            if let (Some(id), Some(name), Some(color)) = (
-                category.get("id").and_then(|v| v.as_str()),
-                category.get("name").and_then(|v| v.as_str()),
-                category.get("color").and_then(|v| v.as_str()),
-            ) {
-                let parent_id = category.get("parent_id").and_then(|v| v.as_str());
-                let now = chrono::Utc::now().to_rfc3339();
+    Ok(())
+}
 
-                sqlx::query(
-                    "INSERT OR IGNORE INTO user_categories (id, name, color, parent_id, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?)"
-                )
+/// Give the seeded categories of a database from before productivity weights the weights
+/// categories.json now seeds them with
+async fn load_category_productivity(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    println!("📊 Loading category productivity...");
+
+    for category in seeded_categories()? {
+        if let Some(id) = category.get("id").and_then(|v| v.as_str()) {
+            sqlx::query("UPDATE user_categories SET productivity = ? WHERE id = ?")
+                .bind(seeded_productivity(&category).weight())
                 .bind(id)
-                .bind(name)
-                .bind(color)
-                .bind(parent_id)
-                .bind(&now)
-                .bind(&now)
                 .execute(pool)
                 .await?;
-            }
         }
-        println!("✅ Categories loaded");
     }
 
     Ok(())
 }
 
+fn seeded_categories() -> Result<Vec<serde_json::Value>, sqlx::Error> {
+    let categories_json = include_str!("../../data/categories.json");
+    let mut categories_data: serde_json::Value =
+        serde_json::from_str(categories_json).map_err(|e| {
+            sqlx::Error::Decode(format!("Failed to parse categories.json: {}", e).into())
+        })?;
+
+    Ok(match categories_data["categories"].take() {
+        serde_json::Value::Array(categories) => categories,
+        _ => Vec::new(),
+    })
+}
+
+fn seeded_productivity(category: &serde_json::Value) -> Productivity {
+    category
+        .get("productivity")
+        .and_then(|v| serde_json::from_value(v.clone()).ok())
+        .unwrap_or_default()
+}
+
 async fn load_app_mappings(pool: &SqlitePool) -> Result<(), sqlx::Error> {
     println!("📱 Loading app mappings...");
 
@@ -214,17 +328,19 @@ async fn load_app_mappings(pool: &SqlitePool) -> Result<(), sqlx::Error> {
                 mapping.get("apps").and_then(|v| v.as_array()),
             ) {
                 for app in apps {
-                    if let Some(app_pattern) = app.as_str() {
+                    if let Some((app_pattern, identifiers)) = seeded_app(app) {
                         let id = uuid::Uuid::new_v4().to_string();
                         let now = chrono::Utc::now().to_rfc3339();
 
                         sqlx::query(
-                            "INSERT OR IGNORE INTO app_mappings (id, app_pattern, category_id, is_custom, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?)"
+                            "INSERT OR IGNORE INTO app_mappings (id, app_pattern, category_id, is_custom, match_kind, identifiers, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
                         )
                         .bind(&id)
                         .bind(app_pattern)
                         .bind(category)
                         .bind(false) // Built-in mappings are not custom
+                        .bind(MatchKind::Exact.as_str()) // Seeded patterns are full app names
+                        .bind(identifiers)
                         .bind(&now)
                         .bind(&now)
                         .execute(pool)
@@ -239,6 +355,53 @@ async fn load_app_mappings(pool: &SqlitePool) -> Result<(), sqlx::Error> {
     Ok(())
 }
 
+/// An `apps` entry is either a name pattern or `{ "name": ..., "identifiers": ... }`
+fn seeded_app(app: &serde_json::Value) -> Option<(&str, Option<&str>)> {
+    match app.as_str() {
+        Some(name) => Some((name, None)),
+        None => Some((
+            app.get("name")?.as_str()?,
+            app.get("identifiers").and_then(|v| v.as_str()),
+        )),
+    }
+}
+
+/// Add the identifiers from app-mappings.json to app mappings seeded before they existed
+async fn load_app_identifiers(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    println!("🪪 Loading app identifiers...");
+
+    let app_mappings_json = include_str!("../../data/app-mappings.json");
+    let app_mappings_data: serde_json::Value =
+        serde_json::from_str(app_mappings_json).map_err(|e| {
+            sqlx::Error::Decode(format!("Failed to parse app-mappings.json: {}", e).into())
+        })?;
+
+    if let Some(mappings) = app_mappings_data.get("mappings").and_then(|m| m.as_array()) {
+        for mapping in mappings {
+            if let (Some(category), Some(apps)) = (
+                mapping.get("category").and_then(|v| v.as_str()),
+                mapping.get("apps").and_then(|v| v.as_array()),
+            ) {
+                for app in apps {
+                    if let Some((app_pattern, Some(identifiers))) = seeded_app(app) {
+                        sqlx::query(
+                            "UPDATE app_mappings SET identifiers = ? WHERE is_custom = 0 AND app_pattern = ? AND category_id = ? AND identifiers IS NULL"
+                        )
+                        .bind(identifiers)
+                        .bind(app_pattern)
+                        .bind(category)
+                        .execute(pool)
+                        .await?;
+                    }
+                }
+            }
+        }
+        println!("✅ App identifiers loaded");
+    }
+
+    Ok(())
+}
+
 async fn load_url_mappings(pool: &SqlitePool) -> Result<(), sqlx::Error> {
     println!("🔗 Loading URL mappings...");
 
@@ -261,12 +424,13 @@ async fn load_url_mappings(pool: &SqlitePool) -> Result<(), sqlx::Error> {
                         let now = chrono::Utc::now().to_rfc3339();
 
                         sqlx::query(
-                            "INSERT OR IGNORE INTO url_mappings (id, url_pattern, category_id, is_custom, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?)"
+                            "INSERT OR IGNORE INTO url_mappings (id, url_pattern, category_id, is_custom, match_kind, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?)"
                         )
                         .bind(&id)
                         .bind(url_pattern)
                         .bind(category)
                         .bind(false) // Built-in mappings are not custom
+                        .bind(MatchKind::DomainSuffix.as_str()) // Seeded patterns are domains
                         .bind(&now)
                         .bind(&now)
                         .execute(pool)
@@ -280,3 +444,55 @@ async fn load_url_mappings(pool: &SqlitePool) -> Result<(), sqlx::Error> {
 
     Ok(())
 }
+
+async fn load_title_mappings(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    println!("🪟 Loading title rules...");
+
+    let title_mappings_json = include_str!("../../data/title-mappings.json");
+    let title_mappings_data: serde_json::Value = serde_json::from_str(title_mappings_json)
+        .map_err(|e| {
+            sqlx::Error::Decode(format!("Failed to parse title-mappings.json: {}", e).into())
+        })?;
+
+    if let Some(mappings) = title_mappings_data
+        .get("mappings")
+        .and_then(|m| m.as_array())
+    {
+        for mapping in mappings {
+            if let (Some(category), Some(titles)) = (
+                mapping.get("category").and_then(|v| v.as_str()),
+                mapping.get("titles").and_then(|v| v.as_array()),
+            ) {
+                for title in titles {
+                    if let Some(title_pattern) = title.get("pattern").and_then(|v| v.as_str()) {
+                        let apps = title.get("apps").and_then(|v| v.as_str());
+                        // Titles are free text, so substring unless the entry says otherwise
+                        let match_kind = title
+                            .get("match_kind")
+                            .and_then(|v| v.as_str())
+                            .map_or(MatchKind::Substring, MatchKind::from_db);
+                        let id = uuid::Uuid::new_v4().to_string();
+                        let now = chrono::Utc::now().to_rfc3339();
+
+                        sqlx::query(
+                            "INSERT OR IGNORE INTO title_mappings (id, title_pattern, app_pattern, category_id, is_custom, match_kind, created_at, updated_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
+                        )
+                        .bind(&id)
+                        .bind(title_pattern)
+                        .bind(apps)
+                        .bind(category)
+                        .bind(false) // Built-in mappings are not custom
+                        .bind(match_kind.as_str())
+                        .bind(&now)
+                        .bind(&now)
+                        .execute(pool)
+                        .await?;
+                    }
+                }
+            }
+        }
+        println!("✅ Title rules loaded");
+    }
+
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Utc, Weekday};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -10,11 +10,23 @@ pub struct ActivityEntry {
     pub app_name: String,
     pub app_bundle_id: Option<String>,
     pub window_title: String,
-    pub url: Option<String>, // For browsers
-    pub category: ActivityCategory,
+    pub url: Option<String>,        // For browsers
+    pub category: ActivityCategory, // Effective category: the override if any, else computed
+    #[serde(default)]
+    pub provenance: CategoryProvenance,
     pub segments: Vec<ActivitySegment>, // New: granular activity segments
 }
 
+/// Where an activity's category came from
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct CategoryProvenance {
+    pub computed_category: Option<ActivityCategory>, // None if corrected before this was kept
+    pub computed_source: Option<CategorizationSource>,
+    pub mapping_id: Option<Uuid>, // URL/app mapping or title rule behind the computed category
+    pub override_category: Option<ActivityCategory>, // Set by the user, wins over computed
+    pub overridden_at: Option<DateTime<Utc>>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ActivitySegment {
     pub id: Uuid,
@@ -38,16 +50,14 @@ pub enum SegmentType {
     Unknown,
 }
 
+/// A category by its `user_categories` id. Built-ins (development, social, unknown, ...)
+/// are seeded rows like any category the user creates, and the id is what gets stored.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
-pub enum ActivityCategory {
-    Productive,
-    Social,
-    Entertainment,
-    Development,
-    Communication,
-    Custom(String), // For user-defined categories
-    Unknown,
-}
+#[serde(transparent)]
+pub struct ActivityCategory(String);
+
+/// The seeded category for activities no mapping matched
+pub const UNKNOWN_CATEGORY_ID: &str = "unknown";
 
 // User-defined categories stored in database
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -56,17 +66,77 @@ pub struct UserCategory {
     pub name: String,
     pub color: String,
     pub parent_id: Option<String>, // For subcategories
+    #[serde(default)]
+    pub productivity: Productivity,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+/// How time in a category counts toward the productivity score
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Productivity {
+    VeryDistracting,
+    Distracting,
+    #[default]
+    Neutral,
+    Productive,
+    VeryProductive,
+}
+
+impl Productivity {
+    /// Stored weight, from -2 for very distracting to 2 for very productive
+    pub fn weight(self) -> i64 {
+        match self {
+            Productivity::VeryDistracting => -2,
+            Productivity::Distracting => -1,
+            Productivity::Neutral => 0,
+            Productivity::Productive => 1,
+            Productivity::VeryProductive => 2,
+        }
+    }
+
+    pub fn from_weight(weight: i64) -> Self {
+        match weight {
+            i64::MIN..=-2 => Productivity::VeryDistracting,
+            -1 => Productivity::Distracting,
+            0 => Productivity::Neutral,
+            1 => Productivity::Productive,
+            2..=i64::MAX => Productivity::VeryProductive,
+        }
+    }
+}
+
+/// How a mapping pattern is compared against an app name or URL (always case-insensitive)
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchKind {
+    /// Whole app name / URL, `|` separates alternatives
+    Exact,
+    /// Anywhere in the app name / URL, `|` separates alternatives
+    #[default]
+    Substring,
+    /// `*` and `?` wildcards over the whole value, `|` separates alternatives
+    Glob,
+    /// Regular expression, searched anywhere in the value
+    Regex,
+    /// URL host is the domain or a subdomain of it, optionally followed by a path prefix
+    DomainSuffix,
+}
+
 // App to category mappings stored in database
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppMapping {
     pub id: Uuid,
-    pub app_pattern: String, // App name pattern (can include wildcards)
+    pub app_pattern: String, // App name pattern, interpreted according to `match_kind`
     pub category_id: String, // References either built-in or user category
     pub is_custom: bool,     // true if user override, false if default
+    #[serde(default)]
+    pub match_kind: MatchKind,
+    #[serde(default)]
+    pub identifiers: Option<String>, // Bundle ids, executables, WM_CLASS/desktop ids separated by `|`
+    #[serde(default)]
+    pub priority: i32, // Higher wins; ties fall back to custom-over-seeded and specificity
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -74,13 +144,199 @@ pub struct AppMapping {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UrlMapping {
     pub id: Uuid,
-    pub url_pattern: String, // URL pattern (domain, subdomain, or full URL)
+    pub url_pattern: String, // URL pattern, interpreted according to `match_kind`
     pub category_id: String, // References either built-in or user category
     pub is_custom: bool,     // true if user override, false if default
+    #[serde(default)]
+    pub match_kind: MatchKind,
+    #[serde(default)]
+    pub priority: i32, // Higher wins; ties fall back to custom-over-seeded and specificity
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+/// Window-title rule, optionally limited to some apps
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TitleMapping {
+    pub id: Uuid,
+    pub title_pattern: String, // Window title pattern, interpreted according to `match_kind`
+    pub app_pattern: Option<String>, // Exact app names separated by `|`, None for every app
+    pub category_id: String,
+    pub is_custom: bool,
+    #[serde(default)]
+    pub match_kind: MatchKind,
+    #[serde(default)]
+    pub priority: i32,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Whether a mapping matches app names, URLs or window titles
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MappingTarget {
+    App,
+    Url,
+    Title,
+}
+
+/// A mapping as it takes part in precedence decisions
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MappingRef {
+    pub id: Uuid,
+    pub pattern: String,
+    pub category_id: String,
+    pub is_custom: bool,
+    pub match_kind: MatchKind,
+    pub priority: i32,
+    #[serde(default)]
+    pub app_scope: Option<String>, // Only set for title rules limited to some apps
+}
+
+/// Two or more mappings that all match `sample`; only `winner` is applied
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MappingConflict {
+    pub target: MappingTarget,
+    pub sample: String, // App name, URL or "app: title" matched by every mapping below
+    pub winner: MappingRef,
+    pub overridden: Vec<MappingRef>,
+    pub same_category: bool, // true if the overlap is redundant rather than contradictory
+    pub explanation: String,
+}
+
+/// An app or URL mapping proposed because the user kept recategorizing the same app or
+/// domain the same way
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MappingSuggestion {
+    pub target: MappingTarget, // App or Url
+    pub pattern: String,       // Exact app name, or a domain for a domain suffix match
+    pub match_kind: MatchKind,
+    pub category_id: String,
+    pub current_category_id: Option<String>, // What the mappings give today, None if nothing matches
+    pub corrections: usize,                  // Activities recategorized this way
+    pub last_corrected_at: DateTime<Utc>,
+}
+
+/// Time that fell back to Unknown over a period, grouped so it can be triaged
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UncategorizedReport {
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    pub total_seconds: i64,
+    pub apps: Vec<UncategorizedItem>,    // Most time first
+    pub domains: Vec<UncategorizedItem>, // Most time first
+    pub titles: Vec<UncategorizedItem>,  // Most time first
+}
+
+/// An app, domain or window title that nothing categorized
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct UncategorizedItem {
+    pub target: MappingTarget,
+    pub value: String,
+    pub app_name: Option<String>, // For titles, the app the window belonged to
+    pub duration_seconds: i64,
+    pub times_accessed: usize,
+    pub last_seen: DateTime<Utc>,
+}
+
+/// The mapping created from the triage queue and, if requested, the history it changed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TriageResult {
+    pub mapping: MappingRef,
+    pub backfill: Option<RecategorizationReport>,
+}
+
+/// Which stage of categorization produced the category
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CategorizationSource {
+    UrlMapping,
+    TitleMapping,
+    AppMapping,
+    /// Nothing matched
+    Fallback,
+}
+
+impl CategorizationSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CategorizationSource::UrlMapping => "url_mapping",
+            CategorizationSource::TitleMapping => "title_mapping",
+            CategorizationSource::AppMapping => "app_mapping",
+            CategorizationSource::Fallback => "fallback",
+        }
+    }
+
+    pub fn from_db(value: &str) -> Option<Self> {
+        match value {
+            "url_mapping" => Some(CategorizationSource::UrlMapping),
+            "title_mapping" => Some(CategorizationSource::TitleMapping),
+            "app_mapping" => Some(CategorizationSource::AppMapping),
+            "fallback" => Some(CategorizationSource::Fallback),
+            _ => None,
+        }
+    }
+}
+
+/// How an app name/URL was categorized and why
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CategorizationExplanation {
+    pub category: ActivityCategory,
+    pub source: CategorizationSource,
+    pub matched_mapping: Option<MappingRef>,
+    pub precedence_rank: Option<usize>, // 1-based position of the mapping in precedence order
+    pub overridden: Vec<String>,        // Why each other matching mapping lost
+    pub fallback_path: Vec<String>,     // Every stage that was tried, in order
+}
+
+/// Result of reapplying the current mappings to past activities
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecategorizationReport {
+    pub run_id: Option<Uuid>, // None when nothing changed, so there is nothing to undo
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    pub changed: usize,
+    pub unchanged: usize,
+    pub skipped_manual: usize, // Activities the user recategorized by hand are left alone
+    pub transitions: Vec<CategoryTransition>,
+}
+
+/// How many activities moved from one category to another
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CategoryTransition {
+    pub from: ActivityCategory,
+    pub to: ActivityCategory,
+    pub activities: usize,
+}
+
+/// A recorded recategorization that can be undone
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecategorizationRun {
+    pub id: Uuid,
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub undone_at: Option<DateTime<Utc>>,
+    pub changed: usize,
+}
+
+/// Rows each step of a category deletion reassigned to the target category, or would
+/// reassign for a dry run
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct CategoryDeletionReport {
+    pub category_id: String,
+    pub target_category_id: String,
+    pub dry_run: bool,
+    pub app_mappings: u64,
+    pub url_mappings: u64,
+    pub title_mappings: u64,
+    pub activities: u64,
+    pub recategorization_changes: u64,
+    pub focus_mode_categories: u64,
+    pub subcategories: u64,
+    pub deleted_mappings: u64, // Mappings removed rather than moved when the target is Unknown
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ActivitySummary {
     pub date: String,
@@ -88,6 +344,38 @@ pub struct ActivitySummary {
     pub categories: Vec<CategorySummary>,
     pub top_apps: Vec<AppSummary>,
     pub detailed_activities: Vec<DetailedActivity>, // New: includes segments
+    pub corrections: Vec<CategoryCorrection>, // Time the user moved out of its computed category
+    #[serde(default)]
+    pub productivity: ProductivityScore,
+}
+
+/// Time split by the productivity of its category, and the score it adds up to
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct ProductivityScore {
+    /// 0 when all time was very distracting, 50 when neutral, 100 when very productive;
+    /// None without tracked time
+    pub score: Option<f64>,
+    pub productive_seconds: i64,
+    pub neutral_seconds: i64,
+    pub distracting_seconds: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailyProductivity {
+    pub date: String,
+    pub productivity: ProductivityScore,
+}
+
+/// Productivity per day, ending with the day being judged
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProductivityTrend {
+    pub start_date: String,
+    pub end_date: String,
+    pub days: Vec<DailyProductivity>,
+    /// Mean score of the earlier days that have tracked time
+    pub average_score: Option<f64>,
+    /// Whether the last day scored at least the earlier days' average
+    pub good_day: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -146,6 +434,23 @@ pub struct CategorySummary {
     pub category: ActivityCategory,
     pub duration_seconds: i64,
     pub percentage: f64,
+    /// Parent category id for subcategories
+    #[serde(default)]
+    pub parent_id: Option<String>,
+    /// Own time plus the time of all subcategories
+    #[serde(default)]
+    pub total_duration_seconds: i64,
+    #[serde(default)]
+    pub total_percentage: f64,
+}
+
+/// Time computed as one category that the user recategorized as another
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CategoryCorrection {
+    pub computed: ActivityCategory,
+    pub corrected: ActivityCategory,
+    pub activities: usize,
+    pub duration_seconds: i64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -155,6 +460,50 @@ pub struct AppSummary {
     pub percentage: f64,
 }
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportPeriod {
+    Week,  // Monday through Sunday
+    Month, // Calendar month
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityReport {
+    pub period: ReportPeriod,
+    pub start_date: String, // YYYY-MM-DD, first day of the period
+    pub end_date: String,   // YYYY-MM-DD, last day of the period (inclusive)
+    pub total_active_time: i64,
+    pub previous_total_active_time: i64, // The previous week or calendar month, up to the same point
+    pub days: Vec<DailyCategoryTotals>,
+    pub categories: Vec<CategoryTrend>,
+    pub top_apps: Vec<AppSummary>,
+    pub focus_streaks: Vec<FocusStreak>,      // Longest first
+    pub corrections: Vec<CategoryCorrection>, // Largest first
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailyCategoryTotals {
+    pub date: String, // YYYY-MM-DD format
+    pub total_active_time: i64,
+    pub categories: Vec<CategorySummary>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CategoryTrend {
+    pub category: ActivityCategory,
+    pub duration_seconds: i64,
+    pub previous_duration_seconds: i64,
+    pub change_percentage: Option<f64>, // None when the category is new this period
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FocusStreak {
+    pub start_time: DateTime<Utc>,
+    pub end_time: DateTime<Utc>,
+    pub duration_seconds: i64,
+    pub category: ActivityCategory,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PomodoroSession {
     pub id: Uuid,
@@ -206,114 +555,98 @@ pub struct PomodoroDateSummary {
     pub total_break_time_seconds: i64,
 }
 
-impl ActivityCategory {
-    #[allow(dead_code)]
-    pub fn from_app_name(app_name: &str, _bundle_id: Option<&str>) -> Self {
-        Self::from_app_name_and_url(app_name, _bundle_id, None)
+/// A weekly window during which focus mode turns on by itself
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FocusSchedule {
+    pub id: Uuid,
+    pub name: String,
+    pub days: Vec<Weekday>, // Days the window starts on
+    pub start_time: NaiveTime,
+    pub end_time: NaiveTime, // At or before start_time means the window ends the next day
+    #[serde(default)]
+    pub allowed_categories: Vec<String>, // Empty keeps the usual focus mode categories
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// The next time a schedule turns focus mode on or off
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FocusTransition {
+    pub at: DateTime<Utc>,
+    pub local_time: NaiveDateTime,
+    pub enables_focus_mode: bool,
+    pub schedule_id: Uuid,
+    pub schedule_name: String,
+}
+
+impl MatchKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MatchKind::Exact => "exact",
+            MatchKind::Substring => "substring",
+            MatchKind::Glob => "glob",
+            MatchKind::Regex => "regex",
+            MatchKind::DomainSuffix => "domain_suffix",
+        }
     }
 
-    #[allow(dead_code)]
-    pub fn from_app_name_and_url(
-        app_name: &str,
-        _bundle_id: Option<&str>,
-        url: Option<&str>,
-    ) -> Self {
-        // First, try URL-based categorization if URL is available
-        if let Some(url_str) = url {
-            if let Some(category) = Self::from_url(url_str) {
-                return category;
-            }
+    /// Parse the stored column value, treating anything unrecognised as a substring match
+    pub fn from_db(value: &str) -> Self {
+        match value {
+            "exact" => MatchKind::Exact,
+            "glob" => MatchKind::Glob,
+            "regex" => MatchKind::Regex,
+            "domain_suffix" => MatchKind::DomainSuffix,
+            _ => MatchKind::Substring,
         }
+    }
+}
 
-        // Fall back to app-based categorization
-        match app_name.to_lowercase().as_str() {
-            name if name.contains("xcode")
-                || name.contains("vscode")
-                || name.contains("intellij")
-                || name.contains("terminal")
-                || name.contains("iterm") =>
-            {
-                Self::Development
-            }
-            name if name.contains("chrome")
-                || name.contains("safari")
-                || name.contains("firefox")
-                || name.contains("edge") =>
-            {
-                // For browsers, if no URL-specific category was found, default to Productive
-                Self::Productive
-            }
-            name if name.contains("slack")
-                || name.contains("discord")
-                || name.contains("zoom")
-                || name.contains("teams")
-                || name.contains("mail") =>
-            {
-                Self::Communication
-            }
-            name if name.contains("twitter")
-                || name.contains("facebook")
-                || name.contains("instagram")
-                || name.contains("linkedin") =>
-            {
-                Self::Social
+impl ReportPeriod {
+    /// First and last day (inclusive) of the period containing `date`
+    pub fn dates_containing(self, date: NaiveDate) -> (NaiveDate, NaiveDate) {
+        match self {
+            ReportPeriod::Week => {
+                let first = date - Duration::days(date.weekday().num_days_from_monday() as i64);
+                (first, first + Duration::days(6))
             }
-            name if name.contains("youtube")
-                || name.contains("netflix")
-                || name.contains("spotify")
-                || name.contains("music")
-                || name.contains("vlc") =>
-            {
-                Self::Entertainment
+            ReportPeriod::Month => {
+                let first = date.with_day(1).unwrap();
+                let next = first
+                    .checked_add_months(chrono::Months::new(1))
+                    .unwrap_or(first);
+                (first, next.pred_opt().unwrap_or(first))
             }
-            _ => Self::Unknown,
         }
     }
 
-    #[allow(dead_code)]
-    fn from_url(url: &str) -> Option<Self> {
-        let url_lower = url.to_lowercase();
-
-        // Check for common URL patterns and categorize accordingly
-        if url_lower.contains("github.com")
-            || url_lower.contains("gitlab.com")
-            || url_lower.contains("stackoverflow.com")
-            || url_lower.contains("docs.")
-            || url_lower.contains("developer.")
-        {
-            Some(Self::Development)
-        } else if url_lower.contains("gmail.com")
-            || url_lower.contains("outlook.com")
-            || url_lower.contains("mail.")
-            || url_lower.contains("slack.com")
-            || url_lower.contains("discord.com")
-            || url_lower.contains("teams.microsoft.com")
-        {
-            Some(Self::Communication)
-        } else if url_lower.contains("twitter.com")
-            || url_lower.contains("facebook.com")
-            || url_lower.contains("instagram.com")
-            || url_lower.contains("linkedin.com")
-            || url_lower.contains("reddit.com")
-            || url_lower.contains("tiktok.com")
-        {
-            Some(Self::Social)
-        } else if url_lower.contains("youtube.com")
-            || url_lower.contains("netflix.com")
-            || url_lower.contains("spotify.com")
-            || url_lower.contains("twitch.tv")
-            || url_lower.contains("hulu.com")
-            || url_lower.contains("disney.com")
-        {
-            Some(Self::Entertainment)
-        } else if url_lower.contains("amazon.com")
-            || url_lower.contains("ebay.com")
-            || url_lower.contains("shopping")
-            || url_lower.contains("store")
-        {
-            Some(Self::Productive) // Shopping can be considered productive in some contexts
-        } else {
-            None // No specific category found, will fall back to app-based categorization
-        }
+    /// The period right before the one containing `date`
+    pub fn previous_dates(self, date: NaiveDate) -> (NaiveDate, NaiveDate) {
+        let (first, _) = self.dates_containing(date);
+        self.dates_containing(first.pred_opt().unwrap_or(first))
+    }
+}
+
+impl ActivityCategory {
+    pub fn new(id: impl Into<String>) -> Self {
+        ActivityCategory(id.into())
+    }
+
+    pub fn unknown() -> Self {
+        ActivityCategory(UNKNOWN_CATEGORY_ID.to_string())
+    }
+
+    pub fn id(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_unknown(&self) -> bool {
+        self.0 == UNKNOWN_CATEGORY_ID
+    }
+
+    /// The `category_id` mappings use for this category; Unknown has none
+    pub fn category_id(&self) -> Option<&str> {
+        (!self.is_unknown()).then_some(self.id())
     }
 }
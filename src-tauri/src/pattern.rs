@@ -0,0 +1,277 @@
+//! Matching and validation of app, URL and window-title mapping patterns.
+//!
+//! Every mode is case-insensitive. Exact, substring, glob and domain suffix patterns may
+//! list alternatives separated by `|`; for regex patterns `|` is ordinary regex syntax.
+//!
+//! App mappings can also list platform identifiers: a macOS bundle id, a Windows executable
+//! or a Linux WM_CLASS / desktop file id. These are compared whole, executables and desktop
+//! files by file name, so `Code.exe` matches `C:\Program Files\Microsoft VS Code\Code.exe`.
+
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex, PoisonError};
+
+use regex::{Regex, RegexBuilder};
+
+use crate::models::{AppMapping, MatchKind};
+
+/// Substring alternatives shorter than this match too much ("X" is in "Xcode")
+const MIN_SUBSTRING_LEN: usize = 3;
+
+/// Compiled glob and regex patterns kept before the cache starts over. Patterns come from
+/// the mapping tables, so this is only reached after many edits.
+const WILDCARD_CACHE_LIMIT: usize = 4096;
+
+/// Glob and regex patterns compiled by [`compile_wildcards`], so checking one mapping at a
+/// time (conflict detection, focus mode) doesn't compile the same pattern on every call
+static WILDCARD_CACHE: LazyLock<Mutex<HashMap<(MatchKind, String), Option<Regex>>>> =
+    LazyLock::new(Default::default);
+
+/// Whether an app name matches `pattern`
+pub fn app_matches(kind: MatchKind, pattern: &str, app_name: &str) -> bool {
+    value_matches(kind, pattern, app_name)
+}
+
+/// Whether an app mapping matches the app by name or by one of its identifiers
+pub fn app_mapping_matches(mapping: &AppMapping, app_name: &str, bundle_id: Option<&str>) -> bool {
+    app_matches(mapping.match_kind, &mapping.app_pattern, app_name)
+        || mapping
+            .identifiers
+            .as_deref()
+            .zip(bundle_id)
+            .is_some_and(|(identifiers, bundle_id)| identifier_matches(identifiers, bundle_id))
+}
+
+/// Whether the platform identifier of a window is one of `identifiers`
+pub fn identifier_matches(identifiers: &str, bundle_id: &str) -> bool {
+    let bundle_id = normalize_identifier(bundle_id);
+    !bundle_id.is_empty()
+        && identifiers
+            .split('|')
+            .any(|identifier| normalize_identifier(identifier) == bundle_id)
+}
+
+/// Whether a URL matches `pattern`
+pub fn url_matches(kind: MatchKind, pattern: &str, url: &str) -> bool {
+    match kind {
+        MatchKind::DomainSuffix => match parse_url(url) {
+            Some(url) => alternatives(pattern).any(|p| domain_suffix_matches(&p, &url)),
+            None => false,
+        },
+        _ => value_matches(kind, pattern, url),
+    }
+}
+
+/// Whether a window title matches `pattern`, and `app_scope` (if any) names the app exactly
+pub fn title_matches(
+    kind: MatchKind,
+    pattern: &str,
+    app_scope: Option<&str>,
+    app_name: &str,
+    title: &str,
+) -> bool {
+    let in_scope = app_scope.is_none_or(|scope| value_matches(MatchKind::Exact, scope, app_name));
+    in_scope && value_matches(kind, pattern, title)
+}
+
+/// Check an app mapping pattern before it is saved
+pub fn validate_app_pattern(kind: MatchKind, pattern: &str) -> Result<(), String> {
+    if kind == MatchKind::DomainSuffix {
+        return Err("Domain suffix matching only applies to URL mappings".to_string());
+    }
+    validate_pattern(kind, pattern)
+}
+
+/// Check the `|`-separated identifiers of an app mapping before they are saved
+pub fn validate_identifiers(identifiers: &str) -> Result<(), String> {
+    validate_pattern(MatchKind::Exact, identifiers)
+}
+
+/// Check a URL mapping pattern before it is saved
+pub fn validate_url_pattern(kind: MatchKind, pattern: &str) -> Result<(), String> {
+    if kind == MatchKind::DomainSuffix {
+        for alternative in alternatives(pattern) {
+            let domain = alternative.split('/').next().unwrap_or_default();
+            let domain = domain.trim_start_matches('.');
+            if alternative.contains("://") || alternative.contains('*') {
+                return Err(format!(
+                    "'{}' should be a bare domain like example.com or example.com/path",
+                    alternative
+                ));
+            }
+            if !domain.contains('.') || domain.contains(char::is_whitespace) {
+                return Err(format!("'{}' is not a domain", alternative));
+            }
+        }
+    }
+    validate_pattern(kind, pattern)
+}
+
+/// Check a window-title rule and its optional app scope before they are saved
+pub fn validate_title_pattern(
+    kind: MatchKind,
+    pattern: &str,
+    app_scope: Option<&str>,
+) -> Result<(), String> {
+    if kind == MatchKind::DomainSuffix {
+        return Err("Domain suffix matching only applies to URL mappings".to_string());
+    }
+    if let Some(scope) = app_scope {
+        validate_pattern(MatchKind::Exact, scope)?;
+    }
+    validate_pattern(kind, pattern)
+}
+
+fn validate_pattern(kind: MatchKind, pattern: &str) -> Result<(), String> {
+    if pattern.trim().is_empty() {
+        return Err("Pattern cannot be empty".to_string());
+    }
+
+    match kind {
+        MatchKind::Regex => {
+            let regex = build_regex(pattern).map_err(|e| format!("Invalid regex: {}", e))?;
+            if regex.is_match("") {
+                return Err(format!(
+                    "Regex '{}' matches an empty name and would match everything",
+                    pattern
+                ));
+            }
+        }
+        _ => {
+            for alternative in pattern.split('|').map(str::trim) {
+                if alternative.is_empty() {
+                    return Err(format!("Pattern '{}' has an empty alternative", pattern));
+                }
+                if kind == MatchKind::Glob && alternative.chars().all(|c| c == '*' || c == '?') {
+                    return Err(format!("Glob '{}' would match everything", alternative));
+                }
+                if kind == MatchKind::Substring && alternative.chars().count() < MIN_SUBSTRING_LEN {
+                    return Err(format!(
+                        "'{}' is too short for a substring match, use an exact match instead",
+                        alternative
+                    ));
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn value_matches(kind: MatchKind, pattern: &str, value: &str) -> bool {
+    let value = value.to_lowercase();
+    match kind {
+        MatchKind::Exact => alternatives(pattern).any(|p| value == p),
+        MatchKind::Substring => alternatives(pattern).any(|p| value.contains(&p)),
+        MatchKind::Glob | MatchKind::Regex => {
+            cached_wildcards(kind, pattern).is_some_and(|regex| regex.is_match(&value))
+        }
+        // Only meaningful for URLs; treat the domain as a plain substring elsewhere
+        MatchKind::DomainSuffix => alternatives(pattern).any(|p| value.contains(&p)),
+    }
+}
+
+/// Lowercased, trimmed, non-empty `|` alternatives
+pub fn alternatives(pattern: &str) -> impl Iterator<Item = String> + '_ {
+    pattern
+        .split('|')
+        .map(|p| p.trim().to_lowercase())
+        .filter(|p| !p.is_empty())
+}
+
+/// Lowercased file name without `.exe`/`.desktop`; bundle ids and WM_CLASS stay as they are
+pub fn normalize_identifier(identifier: &str) -> String {
+    let identifier = identifier.trim().to_lowercase();
+    let file_name = identifier.rsplit(['/', '\\']).next().unwrap_or_default();
+    file_name
+        .strip_suffix(".exe")
+        .or_else(|| file_name.strip_suffix(".desktop"))
+        .unwrap_or(file_name)
+        .to_string()
+}
+
+fn build_regex(pattern: &str) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(pattern).case_insensitive(true).build()
+}
+
+/// One regex for a glob or regex pattern, to be run against lowercased values.
+///
+/// None for the other kinds, and for regexes that don't compile (those never match).
+pub fn compile_wildcards(kind: MatchKind, pattern: &str) -> Option<Regex> {
+    match kind {
+        MatchKind::Glob => {
+            let globs: Vec<String> = alternatives(pattern).map(|p| glob_to_regex(&p)).collect();
+            if globs.is_empty() {
+                return None;
+            }
+            Regex::new(&globs.join("|")).ok()
+        }
+        MatchKind::Regex => build_regex(pattern).ok(),
+        _ => None,
+    }
+}
+
+/// [`compile_wildcards`] through the process-wide cache
+fn cached_wildcards(kind: MatchKind, pattern: &str) -> Option<Regex> {
+    let key = (kind, pattern.to_string());
+    let mut cache = WILDCARD_CACHE
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner);
+    if let Some(regex) = cache.get(&key) {
+        return regex.clone();
+    }
+
+    if cache.len() >= WILDCARD_CACHE_LIMIT {
+        cache.clear();
+    }
+    let regex = compile_wildcards(kind, pattern);
+    cache.insert(key, regex.clone());
+    regex
+}
+
+fn glob_to_regex(glob: &str) -> String {
+    let mut regex = String::from("^(?:");
+    for c in glob.chars() {
+        match c {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push_str(")$");
+    regex
+}
+
+/// Browsers sometimes report URLs without a scheme
+pub fn parse_url(url: &str) -> Option<url::Url> {
+    url::Url::parse(url)
+        .ok()
+        .filter(|u| u.host_str().is_some())
+        .or_else(|| url::Url::parse(&format!("https://{}", url)).ok())
+}
+
+/// `example.com` matches example.com and *.example.com; `example.com/music` also needs
+/// the path to start with /music
+pub fn domain_suffix_matches(pattern: &str, url: &url::Url) -> bool {
+    let (domain, path) = match pattern.split_once('/') {
+        Some((domain, path)) => (domain, Some(path.trim_end_matches('/'))),
+        None => (pattern, None),
+    };
+    let domain = domain.trim_start_matches('.');
+
+    let Some(host) = url.host_str() else {
+        return false;
+    };
+    let host = host.to_lowercase();
+    let host_matches = host == domain || host.ends_with(&format!(".{}", domain));
+
+    let path_matches = match path {
+        Some(path) if !path.is_empty() => {
+            let url_path = url.path().to_lowercase();
+            let prefix = format!("/{}", path);
+            url_path == prefix || url_path.starts_with(&format!("{}/", prefix))
+        }
+        _ => true,
+    };
+
+    host_matches && path_matches
+}
@@ -1,5 +1,5 @@
 #[cfg(test)]
-mod proxy_integration_tests {
+mod tests {
     use crate::local_proxy_blocker::LocalProxyBlocker;
     use tokio::io::{AsyncReadExt, AsyncWriteExt};
     use tokio::net::TcpStream;
@@ -67,7 +67,6 @@ mod proxy_integration_tests {
     async fn test_proxy_server_creation() {
         let _proxy = create_test_proxy().await;
         // If we get here without panicking, proxy creation succeeded
-        assert!(true);
     }
 
     #[tokio::test]
@@ -94,15 +93,13 @@ mod proxy_integration_tests {
         match result {
             Ok(Ok(_)) => {
                 println!("Successfully connected to proxy server");
-                assert!(true);
             }
             Ok(Err(e)) => {
+                // Connection might fail in test environment
                 println!("Failed to connect to proxy server: {}", e);
-                assert!(true); // Connection might fail in test environment
             }
             Err(_) => {
                 println!("Connection attempt timed out as expected");
-                assert!(true);
             }
         }
     }
@@ -0,0 +1,232 @@
+//! Weekly focus schedules: which window is active, when focus mode next turns on or off,
+//! and the background loop that applies them.
+//!
+//! Windows are local times in the user's timezone. The loop looks at the wall clock on every
+//! check instead of sleeping until the next transition, so restarts and sleep/wake simply
+//! pick up whatever window is active at that moment.
+
+use chrono::{Datelike, Duration, NaiveDateTime, Utc};
+use chrono_tz::Tz;
+use tauri::{AppHandle, Manager, State};
+use tokio::time::{interval, MissedTickBehavior};
+use uuid::Uuid;
+
+use crate::commands;
+use crate::models::{FocusSchedule, FocusTransition};
+use crate::timezone::local_to_utc;
+use crate::AppState;
+
+const CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// How far ahead to look for the next transition; every weekly window shows up within it
+const HORIZON_DAYS: i64 = 8;
+
+/// A schedule's local `[start, end)` occurrence
+struct Window<'a> {
+    start: NaiveDateTime,
+    end: NaiveDateTime,
+    schedule: &'a FocusSchedule,
+}
+
+/// Occurrences of the enabled schedules starting from the day before `now` up to the
+/// horizon, by start time
+fn windows(schedules: &[FocusSchedule], now: NaiveDateTime) -> Vec<Window<'_>> {
+    let mut windows = Vec::new();
+    for offset in -1..=HORIZON_DAYS {
+        let date = now.date() + Duration::days(offset);
+        for schedule in schedules {
+            if !schedule.enabled || !schedule.days.contains(&date.weekday()) {
+                continue;
+            }
+            let start = date.and_time(schedule.start_time);
+            let mut end = date.and_time(schedule.end_time);
+            if end <= start {
+                end += Duration::days(1);
+            }
+            windows.push(Window {
+                start,
+                end,
+                schedule,
+            });
+        }
+    }
+    windows.sort_by_key(|window| window.start);
+    windows
+}
+
+/// The schedule whose window contains `now`; the earliest started one if windows overlap
+pub fn active_schedule(schedules: &[FocusSchedule], now: NaiveDateTime) -> Option<&FocusSchedule> {
+    windows(schedules, now)
+        .into_iter()
+        .find(|window| window.start <= now && now < window.end)
+        .map(|window| window.schedule)
+}
+
+/// When focus mode next turns on or off. Overlapping or back-to-back windows keep it on, so
+/// only the edges of their union count.
+pub fn next_transition(
+    schedules: &[FocusSchedule],
+    now: NaiveDateTime,
+    tz: &Tz,
+) -> Option<FocusTransition> {
+    let mut blocks: Vec<(Window, &FocusSchedule)> = Vec::new();
+    for window in windows(schedules, now) {
+        match blocks.last_mut() {
+            Some((block, last)) if window.start <= block.end => {
+                if window.end > block.end {
+                    block.end = window.end;
+                    *last = window.schedule;
+                }
+            }
+            _ => {
+                let schedule = window.schedule;
+                blocks.push((window, schedule));
+            }
+        }
+    }
+
+    let (block, last) = blocks.into_iter().find(|(block, _)| block.end > now)?;
+    let (local_time, enables_focus_mode, schedule) = if block.start <= now {
+        (block.end, false, last)
+    } else {
+        (block.start, true, block.schedule)
+    };
+
+    Some(FocusTransition {
+        at: local_to_utc(local_time, tz),
+        local_time,
+        enables_focus_mode,
+        schedule_id: schedule.id,
+        schedule_name: schedule.name.clone(),
+    })
+}
+
+/// What the loop last applied
+#[derive(Default)]
+struct SchedulerState {
+    /// The schedule whose window is in progress
+    schedule: Option<Uuid>,
+    /// Whether the loop turned focus mode on, so it's only turned off again in that case
+    enabled_focus_mode: bool,
+}
+
+/// Check the schedules periodically and turn focus mode on and off as windows start and end
+pub async fn start_focus_scheduler(app_handle: AppHandle) {
+    println!("Starting focus schedule loop...");
+
+    let mut scheduler = SchedulerState::default();
+    let mut interval = interval(CHECK_INTERVAL);
+    // After sleep, check once instead of catching up on every missed tick
+    interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+    loop {
+        interval.tick().await;
+        if let Err(e) = apply_schedules(&app_handle, &mut scheduler).await {
+            eprintln!("Failed to apply focus schedules: {}", e);
+        }
+    }
+}
+
+/// Start or end scheduled focus mode and refresh the tray's next transition.
+///
+/// Focus mode is only switched when a window starts or ends: turning it off by hand during
+/// a window sticks until the next one, and focus mode turned on by hand is left on.
+async fn apply_schedules(
+    app_handle: &AppHandle,
+    scheduler: &mut SchedulerState,
+) -> Result<(), String> {
+    let state: State<'_, AppState> = app_handle.state();
+    let schedules = state
+        .db
+        .get_focus_schedules()
+        .await
+        .map_err(|e| e.to_string())?;
+    let tz = state.db.get_timezone().await.map_err(|e| e.to_string())?;
+    let now = Utc::now().with_timezone(&tz).naive_local();
+
+    let focus_enabled = {
+        let focus_enabled = state.focus_mode_enabled.lock().map_err(|e| e.to_string())?;
+        *focus_enabled
+    };
+
+    match active_schedule(&schedules, now) {
+        Some(schedule) if scheduler.schedule != Some(schedule.id) => {
+            println!("⏰ Focus schedule '{}' started", schedule.name);
+            scheduler.schedule = Some(schedule.id);
+            set_active_schedule(&state, Some(schedule.clone()))?;
+            commands::focus_mode::notify_allowed_categories_changed(&state, app_handle).await?;
+            if !focus_enabled {
+                commands::enable_focus_mode(state.clone(), app_handle.clone()).await?;
+                scheduler.enabled_focus_mode = true;
+            }
+        }
+        Some(schedule) => {
+            // Edits to the schedule in progress, such as its categories, apply right away
+            if refresh_active_schedule(&state, schedule)? {
+                println!("⏰ Focus schedule '{}' changed", schedule.name);
+                commands::focus_mode::notify_allowed_categories_changed(&state, app_handle).await?;
+            }
+        }
+        None if scheduler.schedule.is_some() => {
+            println!("⏰ Focus schedule ended");
+            scheduler.schedule = None;
+            // Back to the usual allowed categories
+            set_active_schedule(&state, None)?;
+            commands::focus_mode::notify_allowed_categories_changed(&state, app_handle).await?;
+            if std::mem::take(&mut scheduler.enabled_focus_mode) && focus_enabled {
+                commands::disable_focus_mode(state.clone(), app_handle.clone()).await?;
+            }
+        }
+        _ => {}
+    }
+
+    let transition = next_transition(&schedules, now, &tz);
+    let changed = {
+        let mut next = state
+            .next_focus_transition
+            .lock()
+            .map_err(|e| e.to_string())?;
+        let changed = *next != transition;
+        *next = transition;
+        changed
+    };
+    if changed {
+        let is_tracking = {
+            let tracking = state.is_tracking.lock().map_err(|e| e.to_string())?;
+            *tracking
+        };
+        commands::tracking::update_tray_with_state(app_handle, is_tracking).await?;
+    }
+
+    Ok(())
+}
+
+/// Keep the schedule in progress in the app state, so category changes during its window
+/// still allow its categories
+fn set_active_schedule(state: &AppState, schedule: Option<FocusSchedule>) -> Result<(), String> {
+    let mut active = state
+        .active_focus_schedule
+        .lock()
+        .map_err(|e| e.to_string())?;
+    *active = schedule;
+    Ok(())
+}
+
+/// Replace the stored schedule in progress with `schedule` if it is a newer version of it.
+/// Returns whether it changed, so the allowed categories are only refreshed then.
+pub(crate) fn refresh_active_schedule(
+    state: &AppState,
+    schedule: &FocusSchedule,
+) -> Result<bool, String> {
+    let mut active = state
+        .active_focus_schedule
+        .lock()
+        .map_err(|e| e.to_string())?;
+    match active.as_mut() {
+        Some(active) if active.id == schedule.id && active != schedule => {
+            *active = schedule.clone();
+            Ok(true)
+        }
+        _ => Ok(false),
+    }
+}
@@ -36,6 +36,8 @@ pub mod test_utils {
             app_mappings_cache: Arc::new(Mutex::new(None)),
             recently_hidden_apps: Arc::new(Mutex::new(HashMap::new())),
             website_blocker: Arc::new(Mutex::new(None)),
+            next_focus_transition: Arc::new(Mutex::new(None)),
+            active_focus_schedule: Arc::new(Mutex::new(None)),
         }
     }
 
@@ -56,6 +58,7 @@ pub mod test_utils {
             name: name.to_string(),
             color: color.to_string(),
             parent_id: None,
+            productivity: Productivity::default(),
             created_at: Utc::now(),
             updated_at: Utc::now(),
         }
@@ -74,7 +77,8 @@ pub mod test_utils {
             )),
             window_title: window_title.to_string(),
             url: None,
-            category: ActivityCategory::Unknown,
+            category: ActivityCategory::unknown(),
+            provenance: CategoryProvenance::default(),
             segments: vec![],
         }
     }
@@ -86,6 +90,9 @@ pub mod test_utils {
             app_pattern: app_pattern.to_string(),
             category_id: category_id.to_string(),
             is_custom: true,
+            match_kind: MatchKind::Substring,
+            priority: 0,
+            identifiers: None,
             created_at: Utc::now(),
             updated_at: Utc::now(),
         }
@@ -98,6 +105,8 @@ pub mod test_utils {
             url_pattern: url_pattern.to_string(),
             category_id: category_id.to_string(),
             is_custom: true,
+            match_kind: MatchKind::Substring,
+            priority: 0,
             created_at: Utc::now(),
             updated_at: Utc::now(),
         }
@@ -233,7 +242,7 @@ pub mod test_utils {
             for (i, (app_name, _)) in test_data::SAMPLE_APPS.iter().enumerate() {
                 if let Some(category) = categories.get(i % categories.len()) {
                     let mapping = create_sample_app_mapping(&category.id, app_name);
-                    db.add_simple_app_mapping(&category.id, app_name, true)
+                    db.add_simple_app_mapping(&category.id, app_name, true, MatchKind::Substring)
                         .await
                         .unwrap();
                     mappings.push(mapping);
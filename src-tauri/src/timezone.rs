@@ -0,0 +1,53 @@
+//! Local-day arithmetic for date-based queries.
+//!
+//! Timestamps are stored in UTC; "a day" always means a calendar day in the user's
+//! timezone, which defaults to the system zone and can be overridden in settings.
+
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+/// The system's IANA timezone, or UTC when it can't be determined
+pub fn system_timezone() -> chrono_tz::Tz {
+    iana_time_zone::get_timezone()
+        .ok()
+        .and_then(|name| name.parse().ok())
+        .unwrap_or(chrono_tz::UTC)
+}
+
+/// `[start, end)` in UTC for a calendar day in `tz`.
+///
+/// Days are 23 or 25 hours long across DST changes. When local midnight doesn't exist
+/// (zones that switch at 00:00), the day starts at the first valid local time.
+pub fn day_bounds<Tz: TimeZone>(date: NaiveDate, tz: &Tz) -> (DateTime<Utc>, DateTime<Utc>) {
+    let start = local_midnight(date, tz);
+    let end = local_midnight(date.succ_opt().unwrap_or(date), tz);
+    (start, end)
+}
+
+/// `[start, end)` in UTC covering the calendar days `first..=last` in `tz`
+pub fn date_range_bounds<Tz: TimeZone>(
+    first: NaiveDate,
+    last: NaiveDate,
+    tz: &Tz,
+) -> (DateTime<Utc>, DateTime<Utc>) {
+    (day_bounds(first, tz).0, day_bounds(last, tz).1)
+}
+
+/// Calendar day in `tz` that `instant` falls on
+pub fn local_date<Tz: TimeZone>(instant: DateTime<Utc>, tz: &Tz) -> NaiveDate {
+    instant.with_timezone(tz).date_naive()
+}
+
+/// UTC instant of a local time in `tz`; times skipped by a DST change move to the first
+/// valid local time after them
+pub fn local_to_utc<Tz: TimeZone>(local: NaiveDateTime, tz: &Tz) -> DateTime<Utc> {
+    // DST gaps are at most a couple of hours; step forward until we leave the gap
+    (0..=8)
+        .map(|step| local + Duration::minutes(30 * step))
+        .find_map(|local| tz.from_local_datetime(&local).earliest())
+        .map(|dt| dt.with_timezone(&Utc))
+        .unwrap_or_else(|| local.and_utc())
+}
+
+fn local_midnight<Tz: TimeZone>(date: NaiveDate, tz: &Tz) -> DateTime<Utc> {
+    local_to_utc(date.and_hms_opt(0, 0, 0).unwrap(), tz)
+}
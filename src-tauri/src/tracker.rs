@@ -1,6 +1,7 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::time::{Duration, SystemTime};
+#[cfg(any(target_os = "macos", target_os = "linux"))]
 use url::Url;
 
 #[cfg(target_os = "macos")]
@@ -21,7 +22,11 @@ use winapi::um::winnt::PROCESS_QUERY_INFORMATION;
 #[cfg(target_os = "windows")]
 use winapi::um::winuser::{GetForegroundWindow, GetWindowTextA, GetWindowThreadProcessId};
 
+#[cfg(target_os = "linux")]
+use crate::linux_window::{self, LinuxWindow};
+
 use crate::models::SegmentType;
+use crate::window_source::{WindowInfo, WindowSource};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CurrentActivity {
@@ -48,19 +53,32 @@ pub struct UserActivity {
     pub is_active: bool,
 }
 
+/// Default idle threshold, overridden by the `idle_threshold_seconds` setting
+pub const DEFAULT_IDLE_THRESHOLD_SECONDS: u64 = 5 * 60;
+
 pub struct ActivityTracker {
     user_activity: UserActivity,
     inactive_threshold: Duration,
+    /// Idle time reported by the source at the last check
+    last_idle: Duration,
+    source: Box<dyn WindowSource>,
 }
 
 impl ActivityTracker {
     pub fn new() -> Self {
+        Self::with_source(Box::new(SystemWindowSource))
+    }
+
+    /// Create a tracker that reads windows from `source` instead of the OS.
+    pub fn with_source(source: Box<dyn WindowSource>) -> Self {
         Self {
             user_activity: UserActivity {
                 last_input_time: SystemTime::now(),
                 is_active: true,
             },
-            inactive_threshold: Duration::from_secs(30 * 60), // 30 minutes
+            inactive_threshold: Duration::from_secs(DEFAULT_IDLE_THRESHOLD_SECONDS),
+            last_idle: Duration::ZERO,
+            source,
         }
     }
 
@@ -71,26 +89,55 @@ impl ActivityTracker {
             return None;
         }
 
+        let window = self.source.active_window()?;
+        Some(CurrentActivity {
+            app_name: window.app_name,
+            app_bundle_id: window.bundle_id,
+            window_title: window.title,
+            url: window.url,
+            timestamp: Utc::now(),
+            segment_info: window.segment_info,
+        })
+    }
+}
+
+/// Reads the foreground window from the operating system.
+pub struct SystemWindowSource;
+
+impl WindowSource for SystemWindowSource {
+    fn active_window(&mut self) -> Option<WindowInfo> {
         // Use platform-specific implementation to get real activity
         #[cfg(target_os = "macos")]
         {
             println!("Getting real macOS activity");
-            self.get_current_activity_macos()
+            self.get_active_window_macos()
         }
 
         #[cfg(target_os = "windows")]
         {
             println!("Getting real Windows activity");
-            self.get_current_activity_windows()
+            self.get_active_window_windows()
         }
 
-        #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+        #[cfg(target_os = "linux")]
+        {
+            println!("Getting real Linux activity");
+            self.get_active_window_linux()
+        }
+
+        #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
         {
             println!("Platform not supported for activity tracking");
             None
         }
     }
 
+    fn idle_time(&mut self) -> Option<Duration> {
+        crate::idle::system_idle_time()
+    }
+}
+
+impl SystemWindowSource {
     #[cfg(target_os = "macos")]
     pub fn check_accessibility_permissions(&self) -> bool {
         // Simple test to see if we have accessibility permissions
@@ -139,7 +186,7 @@ impl ActivityTracker {
     }
 
     #[cfg(target_os = "macos")]
-    fn get_current_activity_macos(&self) -> Option<CurrentActivity> {
+    fn get_active_window_macos(&self) -> Option<WindowInfo> {
         // Check accessibility permissions first
         if !self.check_accessibility_permissions() {
             println!("⚠️  Accessibility permissions not granted!");
@@ -150,13 +197,13 @@ impl ActivityTracker {
             println!("4. Restart the application");
 
             // Return a fallback activity entry
-            return Some(CurrentActivity {
+            return Some(WindowInfo {
                 app_name: "Permission Required".to_string(),
-                app_bundle_id: None,
-                window_title: "Accessibility permission needed - check console for instructions"
+                bundle_id: None,
+                title: "Accessibility permission needed - check console for instructions"
                     .to_string(),
                 url: None,
-                timestamp: Utc::now(),
+                pid: None,
                 segment_info: None,
             });
         }
@@ -175,7 +222,12 @@ impl ActivityTracker {
                 on error
                     set bundleID to ""
                 end try
-                return appName & "|" & bundleID
+                try
+                    set appPID to unix id of frontApp
+                on error
+                    set appPID to ""
+                end try
+                return appName & "|" & bundleID & "|" & appPID
             end tell
         "#;
 
@@ -208,6 +260,7 @@ impl ActivityTracker {
         } else {
             None
         };
+        let pid = parts.get(2).and_then(|p| p.trim().parse::<u32>().ok());
 
         // Improve app name recognition based on bundle ID
         let app_name = self.get_better_app_name(&raw_app_name, &bundle_id);
@@ -226,12 +279,12 @@ impl ActivityTracker {
         // Extract segment information
         let segment_info = self.extract_segment_info(&app_name, &bundle_id, &window_title, &url);
 
-        Some(CurrentActivity {
+        Some(WindowInfo {
             app_name,
-            app_bundle_id: bundle_id,
-            window_title,
+            bundle_id,
+            title: window_title,
             url,
-            timestamp: Utc::now(),
+            pid,
             segment_info,
         })
     }
@@ -313,7 +366,7 @@ impl ActivityTracker {
         let script = if app_name.contains("Chrome")
             || bundle_id
                 .as_ref()
-                .map_or(false, |id| id.contains("com.google.Chrome"))
+                .is_some_and(|id| id.contains("com.google.Chrome"))
         {
             r#"
                 tell application "Google Chrome"
@@ -338,7 +391,7 @@ impl ActivityTracker {
         } else if app_name.contains("Safari")
             || bundle_id
                 .as_ref()
-                .map_or(false, |id| id.contains("com.apple.Safari"))
+                .is_some_and(|id| id.contains("com.apple.Safari"))
         {
             r#"
                 tell application "Safari"
@@ -363,7 +416,7 @@ impl ActivityTracker {
         } else if app_name.contains("Firefox")
             || bundle_id
                 .as_ref()
-                .map_or(false, |id| id.contains("org.mozilla.firefox"))
+                .is_some_and(|id| id.contains("org.mozilla.firefox"))
         {
             // Firefox doesn't have good AppleScript support, so we'll use window title
             return Some("Firefox: Check window title for tab info".to_string());
@@ -466,7 +519,7 @@ impl ActivityTracker {
         raw_name.to_string()
     }
 
-    #[cfg(target_os = "macos")]
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
     fn is_browser_app(&self, app_name: &str, bundle_id: &Option<String>) -> bool {
         // Check by app name
         if app_name.contains("Chrome")
@@ -491,7 +544,7 @@ impl ActivityTracker {
         }
     }
 
-    #[cfg(target_os = "macos")]
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
     fn extract_segment_info(
         &self,
         app_name: &str,
@@ -530,7 +583,7 @@ impl ActivityTracker {
         if app_name.contains("Visual Studio Code")
             || bundle_id
                 .as_ref()
-                .map_or(false, |id| id.contains("com.microsoft.VSCode"))
+                .is_some_and(|id| id.contains("com.microsoft.VSCode"))
         {
             // Extract file path from window title
             // VS Code window titles often look like: "filename.ext - folder - Visual Studio Code"
@@ -566,7 +619,7 @@ impl ActivityTracker {
         })
     }
 
-    #[cfg(target_os = "macos")]
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
     fn extract_vscode_file_info(&self, window_title: &str) -> Option<String> {
         // Extract file path/name from VS Code window title
         // Patterns: "file.ext - folder" or "● file.ext - folder" (● indicates unsaved)
@@ -589,7 +642,7 @@ impl ActivityTracker {
 
     // Windows implementation
     #[cfg(target_os = "windows")]
-    fn get_current_activity_windows(&self) -> Option<CurrentActivity> {
+    fn get_active_window_windows(&self) -> Option<WindowInfo> {
         unsafe {
             let hwnd = GetForegroundWindow();
             if hwnd.is_null() {
@@ -615,14 +668,13 @@ impl ActivityTracker {
             if process_handle.is_null() {
                 // Clone the window title and compute segment info from a reference
                 let wt_clone = window_title_str.clone();
-                let seg = self
-                    .extract_segment_info_windows(&"Unknown Application".to_string(), &wt_clone);
-                return Some(CurrentActivity {
+                let seg = self.extract_segment_info_windows("Unknown Application", &wt_clone);
+                return Some(WindowInfo {
                     app_name: "Unknown Application".to_string(),
-                    app_bundle_id: Some(process_id.to_string()),
-                    window_title: wt_clone,
+                    bundle_id: None,
+                    title: wt_clone,
                     url: None,
-                    timestamp: Utc::now(),
+                    pid: Some(process_id),
                     segment_info: seg,
                 });
             }
@@ -636,19 +688,20 @@ impl ActivityTracker {
             );
             CloseHandle(process_handle);
 
-            let app_name = if path_len > 0 {
-                let full_path = CStr::from_ptr(exe_path.as_ptr() as *const i8)
+            let full_path = (path_len > 0).then(|| {
+                CStr::from_ptr(exe_path.as_ptr() as *const i8)
                     .to_string_lossy()
-                    .to_string();
+                    .to_string()
+            });
 
+            let app_name = match &full_path {
                 // Extract just the executable name from the full path
-                std::path::Path::new(&full_path)
+                Some(full_path) => std::path::Path::new(full_path)
                     .file_stem()
                     .and_then(|s| s.to_str())
                     .unwrap_or("Unknown")
-                    .to_string()
-            } else {
-                "Unknown Application".to_string()
+                    .to_string(),
+                None => "Unknown Application".to_string(),
             };
 
             // Improve app name recognition
@@ -665,13 +718,14 @@ impl ActivityTracker {
             let segment_info =
                 self.extract_segment_info_windows(&better_app_name, &window_title_str);
 
-            // Build the CurrentActivity struct by cloning values as needed to avoid moves
-            Some(CurrentActivity {
+            // Build the WindowInfo struct by cloning values as needed to avoid moves
+            // The executable path plays the role of the macOS bundle id
+            Some(WindowInfo {
                 app_name: better_app_name,
-                app_bundle_id: Some(process_id.to_string()),
-                window_title: window_title_str.clone(),
+                bundle_id: full_path,
+                title: window_title_str.clone(),
                 url,
-                timestamp: Utc::now(),
+                pid: Some(process_id),
                 segment_info,
             })
         }
@@ -834,64 +888,164 @@ impl ActivityTracker {
         None
     }
 
-    // Fallback implementations for non-macOS, non-Windows platforms
-    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
-    fn get_active_window_title(&self) -> Option<String> {
-        None
+    // Linux (X11 / Wayland) implementation
+    #[cfg(target_os = "linux")]
+    fn get_active_window_linux(&self) -> Option<WindowInfo> {
+        let window = linux_window::active_window()?;
+        Some(self.window_info_from_linux_window(window))
     }
 
-    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
-    fn get_browser_url(&self, _app_name: &str, _bundle_id: &Option<String>) -> Option<String> {
-        None
+    /// Build a `WindowInfo` from the focused Linux window.
+    ///
+    /// The WM_CLASS class name (or Wayland app_id) plays the role of the macOS bundle id.
+    #[cfg(target_os = "linux")]
+    pub fn window_info_from_linux_window(&self, window: LinuxWindow) -> WindowInfo {
+        let process_name = window.pid.and_then(linux_window::process_name);
+        let app_name = self.get_better_app_name_linux(
+            window.class.as_deref(),
+            window.instance.as_deref(),
+            process_name.as_deref(),
+        );
+        let bundle_id = window.class.clone();
+
+        let window_title = window.title.unwrap_or_else(|| "Unknown Window".to_string());
+
+        let url = if self.is_browser_app(&app_name, &bundle_id) {
+            self.get_browser_url_linux(&window_title)
+        } else {
+            None
+        };
+
+        let segment_info =
+            self.extract_segment_info_linux(&app_name, &bundle_id, &window_title, &url);
+
+        WindowInfo {
+            app_name,
+            bundle_id,
+            title: window_title,
+            url,
+            pid: window.pid,
+            segment_info,
+        }
     }
 
-    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
-    fn get_better_app_name(&self, raw_name: &str, _bundle_id: &Option<String>) -> String {
-        raw_name.to_string()
+    #[cfg(target_os = "linux")]
+    pub fn get_better_app_name_linux(
+        &self,
+        class: Option<&str>,
+        instance: Option<&str>,
+        process_name: Option<&str>,
+    ) -> String {
+        let known = [class, instance, process_name]
+            .into_iter()
+            .flatten()
+            .find_map(|name| match name.to_lowercase().as_str() {
+                "google-chrome" | "chrome" => Some("Google Chrome"),
+                "chromium" | "chromium-browser" => Some("Chromium"),
+                "firefox" | "navigator" | "firefox-esr" => Some("Firefox"),
+                "microsoft-edge" | "msedge" => Some("Microsoft Edge"),
+                "brave-browser" | "brave" => Some("Brave Browser"),
+                "code" | "code-oss" => Some("Visual Studio Code"),
+                "gnome-terminal-server" | "gnome-terminal" => Some("Terminal"),
+                "org.gnome.nautilus" | "nautilus" => Some("Files"),
+                "slack" => Some("Slack"),
+                "discord" => Some("Discord"),
+                "spotify" => Some("Spotify"),
+                "zoom" => Some("Zoom"),
+                "figma-linux" => Some("Figma"),
+                "notion-app" | "notion" => Some("Notion"),
+                _ => None,
+            });
+
+        if let Some(name) = known {
+            return name.to_string();
+        }
+
+        class
+            .or(process_name)
+            .or(instance)
+            .unwrap_or("Unknown Application")
+            .to_string()
     }
 
-    #[cfg(not(target_os = "macos"))]
-    fn is_browser_app(&self, _app_name: &str, _bundle_id: &Option<String>) -> bool {
-        false
+    #[cfg(target_os = "linux")]
+    fn get_browser_url_linux(&self, window_title: &str) -> Option<String> {
+        // X11 only exposes the window title, so the URL is only known when the
+        // browser (or an extension) puts it there
+        window_title
+            .split(" - ")
+            .map(str::trim)
+            .find(|part| part.starts_with("http://") || part.starts_with("https://"))
+            .map(|part| part.to_string())
     }
 
-    #[cfg(not(target_os = "macos"))]
-    fn extract_segment_info(
+    #[cfg(target_os = "linux")]
+    pub fn extract_segment_info_linux(
         &self,
-        _app_name: &str,
-        _bundle_id: &Option<String>,
+        app_name: &str,
+        bundle_id: &Option<String>,
         window_title: &str,
-        _url: &Option<String>,
+        url: &Option<String>,
     ) -> Option<SegmentInfo> {
-        Some(SegmentInfo {
-            segment_type: SegmentType::AppWindow,
-            title: window_title.to_string(),
-            url: None,
-            file_path: None,
-            metadata: None,
-        })
+        // Without a URL, fall back to the tab title so browser time still splits by tab
+        if url.is_none() && self.is_browser_app(app_name, bundle_id) {
+            let tab_title = window_title
+                .rsplit_once(" - ")
+                .map(|(title, _browser)| title)
+                .unwrap_or(window_title);
+
+            return Some(SegmentInfo {
+                segment_type: SegmentType::BrowserTab,
+                title: tab_title.to_string(),
+                url: None,
+                file_path: None,
+                metadata: Some(format!("Browser: {}", app_name)),
+            });
+        }
+
+        self.extract_segment_info(app_name, bundle_id, window_title, url)
     }
+}
 
+impl ActivityTracker {
     pub fn check_user_activity(&mut self) -> bool {
-        // For now, we'll be more permissive and assume user is active if we're checking
-        // In a real implementation, you'd want to check for actual user input
-        self.user_activity.last_input_time = SystemTime::now();
-        self.user_activity.is_active = true;
-
-        // Only return false if we haven't updated for the threshold time
-        if let Ok(elapsed) = self.user_activity.last_input_time.elapsed() {
-            if elapsed > self.inactive_threshold {
-                self.user_activity.is_active = false;
-                return false;
+        let now = SystemTime::now();
+
+        match self.source.idle_time() {
+            Some(idle) => {
+                self.last_idle = idle;
+                self.user_activity.last_input_time = now.checked_sub(idle).unwrap_or(now);
+                self.user_activity.is_active = idle < self.inactive_threshold;
+            }
+            None => {
+                // Idle time unavailable on this system, assume the user is present
+                self.last_idle = Duration::ZERO;
+                self.user_activity.last_input_time = now;
+                self.user_activity.is_active = true;
             }
         }
-        true
+
+        self.user_activity.is_active
     }
 
-    #[allow(dead_code)]
-    pub fn update_last_input_time(&mut self) {
-        self.user_activity.last_input_time = SystemTime::now();
-        self.user_activity.is_active = true;
+    /// How long the user has been idle, as of the last `check_user_activity`.
+    ///
+    /// Only `Some` once the inactivity threshold has been crossed.
+    pub fn idle_duration(&self) -> Option<Duration> {
+        if self.user_activity.is_active {
+            None
+        } else {
+            Some(self.last_idle)
+        }
+    }
+
+    /// Whether the user was active at the last `check_user_activity`.
+    pub fn is_user_active(&self) -> bool {
+        self.user_activity.is_active
+    }
+
+    pub fn set_inactive_threshold(&mut self, threshold: Duration) {
+        self.inactive_threshold = threshold;
     }
 
     pub fn should_track(&mut self) -> bool {
@@ -1,5 +1,5 @@
 #[cfg(test)]
-mod tracker_tests {
+mod tests {
     use crate::models::*;
     use crate::tracker::*;
     use chrono::Utc;
@@ -327,4 +327,1512 @@ mod tracker_tests {
         assert!(segment.url.unwrap().contains("🚀"));
         assert!(segment.metadata.unwrap().contains("unicode"));
     }
+
+    #[cfg(target_os = "linux")]
+    mod linux_x11 {
+        use crate::linux_window::*;
+        use crate::models::SegmentType;
+        use crate::tracker::SystemWindowSource;
+
+        const XPROP_OUTPUT: &str = r#"_NET_WM_NAME(UTF8_STRING) = "main.rs - velosi - Visual Studio Code"
+WM_NAME(STRING) = "main.rs - velosi"
+WM_CLASS(STRING) = "code", "Code"
+_NET_WM_PID(CARDINAL) = 4242
+"#;
+
+        #[test]
+        fn test_parse_active_window_id() {
+            assert_eq!(
+                parse_active_window_id("_NET_ACTIVE_WINDOW(WINDOW): window id # 0x3a00007\n"),
+                Some("0x3a00007".to_string())
+            );
+            assert_eq!(
+                parse_active_window_id("_NET_ACTIVE_WINDOW(WINDOW): window id # 0x3a00007, 0x0"),
+                Some("0x3a00007".to_string())
+            );
+            assert_eq!(
+                parse_active_window_id("_NET_ACTIVE_WINDOW(INTEGER) = 1234"),
+                Some("0x4d2".to_string())
+            );
+            assert_eq!(
+                parse_active_window_id("_NET_ACTIVE_WINDOW(WINDOW): window id # 0x0"),
+                None
+            );
+            assert_eq!(
+                parse_active_window_id("_NET_ACTIVE_WINDOW:  not found."),
+                None
+            );
+        }
+
+        #[test]
+        fn test_parse_window_properties() {
+            assert_eq!(
+                parse_string_property(XPROP_OUTPUT, "_NET_WM_NAME"),
+                Some("main.rs - velosi - Visual Studio Code".to_string())
+            );
+            assert_eq!(
+                parse_string_property(XPROP_OUTPUT, "WM_NAME"),
+                Some("main.rs - velosi".to_string())
+            );
+            assert_eq!(
+                parse_wm_class(XPROP_OUTPUT),
+                Some(("code".to_string(), "Code".to_string()))
+            );
+            assert_eq!(
+                parse_cardinal_property(XPROP_OUTPUT, "_NET_WM_PID"),
+                Some(4242)
+            );
+
+            let missing = "_NET_WM_NAME:  not found.\nWM_CLASS:  not found.\n";
+            assert_eq!(parse_string_property(missing, "_NET_WM_NAME"), None);
+            assert_eq!(parse_wm_class(missing), None);
+            assert_eq!(parse_cardinal_property(missing, "_NET_WM_PID"), None);
+        }
+
+        #[test]
+        fn test_parse_escaped_title() {
+            let output = r#"_NET_WM_NAME(UTF8_STRING) = "Say \"hi\" \\ bye""#;
+            assert_eq!(
+                parse_string_property(output, "_NET_WM_NAME"),
+                Some(r#"Say "hi" \ bye"#.to_string())
+            );
+        }
+
+        #[test]
+        fn test_window_info_from_linux_window() {
+            let source = SystemWindowSource;
+
+            let editor = source.window_info_from_linux_window(LinuxWindow {
+                id: "0x3a00007".to_string(),
+                title: Some("main.rs - velosi - Visual Studio Code".to_string()),
+                instance: Some("code".to_string()),
+                class: Some("Code".to_string()),
+                pid: None,
+            });
+            assert_eq!(editor.app_name, "Visual Studio Code");
+            assert_eq!(editor.bundle_id, Some("Code".to_string()));
+            assert!(editor.url.is_none());
+            let segment = editor.segment_info.unwrap();
+            assert_eq!(segment.segment_type, SegmentType::EditorFile);
+            assert_eq!(segment.file_path, Some("main.rs".to_string()));
+
+            let browser = source.window_info_from_linux_window(LinuxWindow {
+                id: "0x1e00003".to_string(),
+                title: Some("Pull requests - Mozilla Firefox".to_string()),
+                instance: Some("Navigator".to_string()),
+                class: Some("firefox".to_string()),
+                pid: None,
+            });
+            assert_eq!(browser.app_name, "Firefox");
+            let segment = browser.segment_info.unwrap();
+            assert_eq!(segment.segment_type, SegmentType::BrowserTab);
+            assert_eq!(segment.title, "Pull requests");
+
+            let untitled = source.window_info_from_linux_window(LinuxWindow {
+                id: "0x2200001".to_string(),
+                title: None,
+                instance: Some("obscure".to_string()),
+                class: Some("Obscure".to_string()),
+                pid: None,
+            });
+            assert_eq!(untitled.app_name, "Obscure");
+            assert_eq!(untitled.title, "Unknown Window");
+        }
+
+        /// Runs against a real X server: starts Xvfb and scripts the root window's
+        /// properties with xprop, so no window manager is needed.
+        /// Run with `cargo test -- --ignored test_x11_backend_with_xvfb`.
+        #[test]
+        #[ignore]
+        fn test_x11_backend_with_xvfb() {
+            use std::process::Command;
+
+            let display = ":99";
+            let mut xvfb = Command::new("Xvfb")
+                .arg(display)
+                .spawn()
+                .expect("Xvfb must be installed");
+            std::thread::sleep(std::time::Duration::from_millis(500));
+
+            let xwininfo = Command::new("xwininfo")
+                .args(["-display", display, "-root"])
+                .output()
+                .expect("xwininfo must be installed");
+            let root_id = String::from_utf8_lossy(&xwininfo.stdout)
+                .lines()
+                .find_map(|l| l.split("Window id: ").nth(1))
+                .and_then(|rest| rest.split_whitespace().next())
+                .expect("root window id")
+                .to_string();
+
+            let pid = std::process::id().to_string();
+            let set = |name: &str, format: &str, value: &str| {
+                let status = Command::new("xprop")
+                    .args([
+                        "-display", display, "-root", "-f", name, format, "-set", name, value,
+                    ])
+                    .status()
+                    .expect("xprop must be installed");
+                assert!(status.success(), "failed to set {}", name);
+            };
+            set("_NET_WM_NAME", "8u", "Scripted Window");
+            set("WM_CLASS", "8s", "velosi-xvfb");
+            set("_NET_WM_PID", "32c", &pid);
+            set("_NET_ACTIVE_WINDOW", "32x", &root_id);
+
+            let window = active_x11_window(Some(display));
+            let _ = xvfb.kill();
+            let _ = xvfb.wait();
+
+            let window = window.expect("active window");
+            assert_eq!(window.title, Some("Scripted Window".to_string()));
+            assert_eq!(window.class, Some("velosi-xvfb".to_string()));
+            assert_eq!(window.pid, Some(std::process::id()));
+            assert!(process_name(std::process::id()).is_some());
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    mod linux_wayland {
+        use crate::linux_window::{detect_provider, WindowProvider};
+        use crate::wayland_window::*;
+
+        #[test]
+        fn test_detect_provider() {
+            assert_eq!(detect_provider(None, None), WindowProvider::X11);
+            assert_eq!(
+                detect_provider(Some("x11"), Some("GNOME")),
+                WindowProvider::X11
+            );
+            assert_eq!(
+                detect_provider(Some("wayland"), Some("ubuntu:GNOME")),
+                WindowProvider::Gnome
+            );
+            assert_eq!(
+                detect_provider(Some("wayland"), Some("sway")),
+                WindowProvider::Wlroots
+            );
+            assert_eq!(
+                detect_provider(Some("wayland"), None),
+                WindowProvider::Wlroots
+            );
+        }
+
+        #[test]
+        fn test_parse_gdbus_string() {
+            let output = r#"('[{"id":1,"title":"It\'s \\"quoted\\""}]',)"#;
+            assert_eq!(
+                parse_gdbus_string(output),
+                Some(r#"[{"id":1,"title":"It's \"quoted\""}]"#.to_string())
+            );
+            assert_eq!(
+                parse_gdbus_string("('main.rs - Visual Studio Code',)\n"),
+                Some("main.rs - Visual Studio Code".to_string())
+            );
+            assert_eq!(parse_gdbus_string("('unterminated"), None);
+            assert_eq!(parse_gdbus_string("()"), None);
+        }
+
+        #[test]
+        fn test_parse_gnome_windows() {
+            let json = r#"[
+                {"in_current_workspace":true,"wm_class":"firefox","wm_class_instance":"Navigator","pid":1200,"id":3107,"frame_type":0,"window_type":0,"focus":false,"title":"Mozilla Firefox"},
+                {"in_current_workspace":true,"wm_class":"Code","wm_class_instance":"code","pid":4242,"id":3110,"frame_type":0,"window_type":0,"focus":true}
+            ]"#;
+
+            let windows = parse_gnome_windows(json).unwrap();
+            assert_eq!(windows.len(), 2);
+
+            let focused = windows.iter().find(|w| w.focus).unwrap();
+            assert_eq!(focused.id, 3110);
+            assert_eq!(focused.wm_class, Some("Code".to_string()));
+            assert_eq!(focused.pid, Some(4242));
+            assert!(focused.title.is_none());
+
+            assert!(parse_gnome_windows("not json").is_none());
+        }
+
+        #[test]
+        fn test_parse_gdbus_idle_time() {
+            use crate::idle::parse_gdbus_uint;
+
+            assert_eq!(parse_gdbus_uint("(uint64 12345,)\n"), Some(12345));
+            assert_eq!(parse_gdbus_uint("(uint32 0,)"), Some(0));
+            assert_eq!(parse_gdbus_uint("()"), None);
+            assert_eq!(parse_gdbus_uint("Error: no such method"), None);
+        }
+
+        #[test]
+        fn test_is_activated() {
+            let encode = |states: &[u32]| -> Vec<u8> {
+                states.iter().flat_map(|s| s.to_ne_bytes()).collect()
+            };
+
+            // maximized = 0, minimized = 1, activated = 2, fullscreen = 3
+            assert!(is_activated(&encode(&[2])));
+            assert!(is_activated(&encode(&[0, 2])));
+            assert!(!is_activated(&encode(&[0, 1])));
+            assert!(!is_activated(&[]));
+        }
+
+        /// Runs against a headless sway instance with a `foot` window.
+        /// Run with `cargo test -- --ignored test_wlroots_backend_with_headless_sway`.
+        #[test]
+        #[ignore]
+        fn test_wlroots_backend_with_headless_sway() {
+            use std::process::Command;
+            use std::time::{Duration, Instant};
+
+            let runtime_dir = tempfile::tempdir().unwrap();
+            let config = runtime_dir.path().join("sway.conf");
+            std::fs::write(&config, "exec foot --app-id velosi-sway-test\n").unwrap();
+
+            let mut sway = Command::new("sway")
+                .arg("-c")
+                .arg(&config)
+                .env("XDG_RUNTIME_DIR", runtime_dir.path())
+                .env("WLR_BACKENDS", "headless")
+                .env("WLR_RENDERER", "pixman")
+                .env("WLR_LIBINPUT_NO_DEVICES", "1")
+                .env_remove("WAYLAND_DISPLAY")
+                .env_remove("DISPLAY")
+                .spawn()
+                .expect("sway must be installed");
+
+            // Wait for the compositor socket and the scripted window to gain focus
+            let deadline = Instant::now() + Duration::from_secs(10);
+            let mut window = None;
+            while Instant::now() < deadline && window.is_none() {
+                std::thread::sleep(Duration::from_millis(200));
+
+                let socket = std::fs::read_dir(runtime_dir.path())
+                    .unwrap()
+                    .filter_map(|entry| entry.ok().map(|e| e.path()))
+                    .find(|path| {
+                        path.file_name()
+                            .and_then(|n| n.to_str())
+                            .is_some_and(|n| n.starts_with("wayland-") && !n.ends_with(".lock"))
+                    });
+
+                if let Some(socket) = socket {
+                    window = active_wlroots_window(Some(&socket))
+                        .filter(|w| w.class.as_deref() == Some("velosi-sway-test"));
+                }
+            }
+
+            let _ = sway.kill();
+            let _ = sway.wait();
+
+            let window = window.expect("focused foot window");
+            assert_eq!(window.class, Some("velosi-sway-test".to_string()));
+            assert!(window.title.is_some());
+        }
+    }
+
+    mod scripted_tracking {
+        use crate::activity::{
+            poll_current_activity, reapply_mappings, track_activity, TickOutcome,
+        };
+        use crate::models::{ActivityCategory, MatchKind, SegmentType};
+        use crate::test_config::test_utils::{create_test_app_state, create_test_database};
+        use crate::tracker::{ActivityTracker, SegmentInfo};
+        use crate::window_source::{ScriptedWindowSource, WindowInfo, WindowSource};
+        use chrono::{Duration, TimeZone, Utc};
+        use std::time::Duration as StdDuration;
+
+        fn window(app_name: &str, title: &str, url: Option<&str>) -> Option<WindowInfo> {
+            Some(WindowInfo {
+                app_name: app_name.to_string(),
+                bundle_id: None,
+                title: title.to_string(),
+                url: url.map(|u| u.to_string()),
+                pid: Some(42),
+                segment_info: None,
+            })
+        }
+
+        #[test]
+        fn test_scripted_source_replays_frames_in_order() {
+            let mut source = ScriptedWindowSource::new(vec![
+                window("Terminal", "zsh", None),
+                None,
+                window("Slack", "general", None),
+            ]);
+
+            assert_eq!(source.remaining(), 3);
+            assert_eq!(source.active_window().unwrap().app_name, "Terminal");
+            assert!(source.active_window().is_none());
+            assert_eq!(source.active_window().unwrap().app_name, "Slack");
+            // Exhausted scripts behave like "nothing in front"
+            assert!(source.active_window().is_none());
+        }
+
+        #[test]
+        fn test_scripted_source_from_recording() {
+            let recording = r#"[
+                {"app_name": "Firefox", "bundle_id": "firefox", "title": "Docs", "url": "https://docs.rs", "pid": 7},
+                null
+            ]"#;
+            let mut source = ScriptedWindowSource::from_json(recording).unwrap();
+
+            let first = source.active_window().unwrap();
+            assert_eq!(first.bundle_id, Some("firefox".to_string()));
+            assert_eq!(first.url, Some("https://docs.rs".to_string()));
+            assert_eq!(first.pid, Some(7));
+            assert!(first.segment_info.is_none());
+            assert!(source.active_window().is_none());
+        }
+
+        #[tokio::test]
+        async fn test_tracking_loop_with_scripted_source() {
+            let db = create_test_database().await;
+            db.add_simple_app_mapping(
+                "development",
+                "Visual Studio Code",
+                true,
+                MatchKind::Substring,
+            )
+            .await
+            .unwrap();
+            let state = create_test_app_state(db);
+
+            let script = ScriptedWindowSource::new(vec![
+                window("Visual Studio Code", "main.rs - velosi", None),
+                window("Visual Studio Code", "main.rs - velosi", None),
+                window(
+                    "Google Chrome",
+                    "Velosi",
+                    Some("https://github.com/faiyaz26/velosi"),
+                ),
+                None,
+                window("Slack", "general", None),
+            ]);
+            *state.tracker.lock().unwrap() = ActivityTracker::with_source(Box::new(script));
+
+            let start = Utc.with_ymd_and_hms(2024, 3, 10, 10, 0, 0).unwrap();
+            let expected = [
+                TickOutcome::Started,
+                TickOutcome::Continued,
+                TickOutcome::Switched,
+                TickOutcome::Ended,
+                TickOutcome::Started,
+            ];
+
+            for (tick, expected_outcome) in expected.iter().enumerate() {
+                let now = start + Duration::seconds(5 * tick as i64);
+                let current = poll_current_activity(&state);
+                let outcome = track_activity(&state, current, now).await;
+                assert_eq!(outcome, *expected_outcome, "tick {}", tick);
+            }
+
+            let activities = state
+                .db
+                .get_activities_by_date(start.date_naive(), &chrono_tz::UTC)
+                .await
+                .unwrap();
+            assert_eq!(activities.len(), 3);
+
+            let vscode = activities
+                .iter()
+                .find(|a| a.app_name == "Visual Studio Code")
+                .unwrap();
+            assert_eq!(vscode.start_time, start);
+            assert_eq!(vscode.end_time, Some(start + Duration::seconds(10)));
+            assert_eq!(vscode.category, ActivityCategory::new("development"));
+
+            let chrome = activities
+                .iter()
+                .find(|a| a.app_name == "Google Chrome")
+                .unwrap();
+            assert_eq!(chrome.end_time, Some(start + Duration::seconds(15)));
+
+            let slack = activities.iter().find(|a| a.app_name == "Slack").unwrap();
+            assert_eq!(slack.start_time, start + Duration::seconds(20));
+            assert!(slack.end_time.is_none());
+        }
+
+        #[tokio::test]
+        async fn test_override_survives_the_next_tick() {
+            let db = create_test_database().await;
+            db.add_simple_app_mapping(
+                "development",
+                "Visual Studio Code",
+                true,
+                MatchKind::Substring,
+            )
+            .await
+            .unwrap();
+            let state = create_test_app_state(db);
+
+            let script = ScriptedWindowSource::new(vec![
+                window("Visual Studio Code", "main.rs - velosi", None),
+                window("Visual Studio Code", "main.rs - velosi", None),
+            ]);
+            *state.tracker.lock().unwrap() = ActivityTracker::with_source(Box::new(script));
+
+            let start = Utc.with_ymd_and_hms(2024, 3, 10, 10, 0, 0).unwrap();
+            let current = poll_current_activity(&state);
+            assert_eq!(
+                track_activity(&state, current, start).await,
+                TickOutcome::Started
+            );
+
+            // The user recategorizes the activity that's still in front
+            let ongoing = state.db.get_current_activity().await.unwrap().unwrap();
+            let social = ActivityCategory::new("social");
+            state
+                .db
+                .update_activity_category(&ongoing.id.to_string(), &social)
+                .await
+                .unwrap();
+
+            let current = poll_current_activity(&state);
+            assert_eq!(
+                track_activity(&state, current, start + Duration::seconds(5)).await,
+                TickOutcome::Continued
+            );
+
+            let still_ongoing = state.db.get_current_activity().await.unwrap().unwrap();
+            assert_eq!(still_ongoing.id, ongoing.id);
+            assert_eq!(still_ongoing.category, social);
+            assert_eq!(still_ongoing.provenance.override_category, Some(social));
+            assert_eq!(
+                still_ongoing.provenance.computed_category,
+                Some(ActivityCategory::new("development"))
+            );
+        }
+
+        #[tokio::test]
+        async fn test_tab_and_file_changes_become_segments() {
+            let db = create_test_database().await;
+            db.add_simple_app_mapping(
+                "development",
+                "Visual Studio Code",
+                true,
+                MatchKind::Substring,
+            )
+            .await
+            .unwrap();
+            let state = create_test_app_state(db);
+
+            let editor_file = |file: &str| {
+                let mut window = window("Visual Studio Code", &format!("{} - velosi", file), None);
+                if let Some(window) = window.as_mut() {
+                    window.segment_info = Some(SegmentInfo {
+                        segment_type: SegmentType::EditorFile,
+                        title: file.to_string(),
+                        url: None,
+                        file_path: Some(file.to_string()),
+                        metadata: None,
+                    });
+                }
+                window
+            };
+
+            let script = ScriptedWindowSource::new(vec![
+                editor_file("main.rs"),
+                editor_file("main.rs"),
+                editor_file("lib.rs"),
+                window("Slack", "general", None),
+                window("Slack", "random", None),
+                None,
+            ]);
+            *state.tracker.lock().unwrap() = ActivityTracker::with_source(Box::new(script));
+
+            let start = Utc.with_ymd_and_hms(2024, 3, 11, 14, 0, 0).unwrap();
+            let expected = [
+                TickOutcome::Started,
+                TickOutcome::Continued,
+                TickOutcome::SegmentChanged,
+                TickOutcome::Switched,
+                TickOutcome::SegmentChanged,
+                TickOutcome::Ended,
+            ];
+
+            for (tick, expected_outcome) in expected.iter().enumerate() {
+                let now = start + Duration::seconds(5 * tick as i64);
+                let current = poll_current_activity(&state);
+                let outcome = track_activity(&state, current, now).await;
+                assert_eq!(outcome, *expected_outcome, "tick {}", tick);
+            }
+
+            let activities = state
+                .db
+                .get_activities_by_date(start.date_naive(), &chrono_tz::UTC)
+                .await
+                .unwrap();
+            assert_eq!(activities.len(), 2);
+
+            let vscode = &activities[0];
+            assert_eq!(vscode.app_name, "Visual Studio Code");
+            assert_eq!(vscode.segments.len(), 2);
+            assert_eq!(vscode.segments[0].segment_type, SegmentType::EditorFile);
+            assert_eq!(vscode.segments[0].file_path, Some("main.rs".to_string()));
+            assert_eq!(vscode.segments[0].start_time, start);
+            assert_eq!(
+                vscode.segments[0].end_time,
+                Some(start + Duration::seconds(10))
+            );
+            assert_eq!(vscode.segments[1].file_path, Some("lib.rs".to_string()));
+            assert_eq!(vscode.segments[1].end_time, vscode.end_time);
+
+            // Without backend segment info the window itself is the segment
+            let slack = &activities[1];
+            let titles: Vec<&str> = slack.segments.iter().map(|s| s.title.as_str()).collect();
+            assert_eq!(titles, ["general", "random"]);
+            assert_eq!(slack.segments[0].segment_type, SegmentType::AppWindow);
+            assert_eq!(
+                slack.segments[1].end_time,
+                Some(start + Duration::seconds(25))
+            );
+        }
+
+        #[tokio::test]
+        async fn test_tab_changes_in_the_same_category_update_the_activity() {
+            let db = create_test_database().await;
+            db.add_simple_url_mapping("development", "acme.test", true, MatchKind::DomainSuffix)
+                .await
+                .unwrap();
+            let state = create_test_app_state(db);
+
+            let script = ScriptedWindowSource::new(vec![
+                window("Acme Browser", "Docs", Some("https://docs.acme.test/start")),
+                window("Acme Browser", "Wiki", Some("https://wiki.acme.test/home")),
+            ]);
+            *state.tracker.lock().unwrap() = ActivityTracker::with_source(Box::new(script));
+
+            let start = Utc.with_ymd_and_hms(2024, 3, 12, 9, 0, 0).unwrap();
+            let expected = [TickOutcome::Started, TickOutcome::SegmentChanged];
+            for (tick, expected_outcome) in expected.iter().enumerate() {
+                let now = start + Duration::seconds(5 * tick as i64);
+                let current = poll_current_activity(&state);
+                let outcome = track_activity(&state, current, now).await;
+                assert_eq!(outcome, *expected_outcome, "tick {}", tick);
+            }
+
+            // The activity follows the tab in front, not the one it started with
+            let activity = state.db.get_current_activity().await.unwrap().unwrap();
+            assert_eq!(activity.category, ActivityCategory::new("development"));
+            assert_eq!(activity.window_title, "Wiki");
+            assert_eq!(
+                activity.url,
+                Some("https://wiki.acme.test/home".to_string())
+            );
+
+            // So reapplying mappings looks at the latest tab too
+            state
+                .db
+                .add_simple_url_mapping(
+                    "productive",
+                    "wiki.acme.test",
+                    true,
+                    MatchKind::DomainSuffix,
+                )
+                .await
+                .unwrap();
+            let end = start + Duration::minutes(1);
+            let report = reapply_mappings(&state.db, start, end).await.unwrap();
+            assert_eq!(report.changed, 1);
+            let activity = state.db.get_current_activity().await.unwrap().unwrap();
+            assert_eq!(activity.category, ActivityCategory::new("productive"));
+        }
+
+        #[test]
+        fn test_idle_detection_uses_source_idle_time() {
+            let script = ScriptedWindowSource::new(vec![]).with_idle_times(vec![
+                StdDuration::from_secs(10),
+                StdDuration::from_secs(600),
+            ]);
+            let mut tracker = ActivityTracker::with_source(Box::new(script));
+            tracker.set_inactive_threshold(StdDuration::from_secs(300));
+
+            assert!(tracker.check_user_activity());
+            assert!(tracker.idle_duration().is_none());
+
+            assert!(!tracker.check_user_activity());
+            assert_eq!(tracker.idle_duration(), Some(StdDuration::from_secs(600)));
+        }
+
+        #[tokio::test]
+        async fn test_going_idle_ends_activity_when_input_stopped() {
+            let db = create_test_database().await;
+            let state = create_test_app_state(db);
+
+            let script = ScriptedWindowSource::new(vec![
+                window("Terminal", "zsh", None),
+                window("Terminal", "zsh", None),
+                window("Terminal", "zsh", None),
+                window("Terminal", "zsh", None),
+            ])
+            .with_idle_times(vec![
+                StdDuration::ZERO,
+                StdDuration::from_secs(400),
+                StdDuration::from_secs(405),
+                StdDuration::ZERO,
+            ]);
+            let mut tracker = ActivityTracker::with_source(Box::new(script));
+            tracker.set_inactive_threshold(StdDuration::from_secs(300));
+            *state.tracker.lock().unwrap() = tracker;
+
+            let start = Utc.with_ymd_and_hms(2024, 3, 10, 9, 0, 0).unwrap();
+            let ticks = [
+                (start, TickOutcome::Started),
+                // Walked away 400s ago: end the activity then, not now
+                (start + Duration::seconds(600), TickOutcome::WentIdle),
+                (start + Duration::seconds(605), TickOutcome::Skipped),
+                // Back at the keyboard
+                (start + Duration::seconds(610), TickOutcome::Started),
+            ];
+
+            for (now, expected_outcome) in ticks {
+                let current = poll_current_activity(&state);
+                assert_eq!(track_activity(&state, current, now).await, expected_outcome);
+            }
+
+            let mut activities = state
+                .db
+                .get_activities_by_date(start.date_naive(), &chrono_tz::UTC)
+                .await
+                .unwrap();
+            activities.sort_by_key(|a| a.start_time);
+            assert_eq!(activities.len(), 2);
+            assert_eq!(activities[0].end_time, Some(start + Duration::seconds(200)));
+            assert_eq!(activities[1].start_time, start + Duration::seconds(610));
+        }
+
+        #[tokio::test]
+        async fn test_tracking_disabled_skips_recording() {
+            let db = create_test_database().await;
+            let state = create_test_app_state(db);
+            *state.is_tracking.lock().unwrap() = false;
+
+            let script = ScriptedWindowSource::new(vec![window("Terminal", "zsh", None)]);
+            *state.tracker.lock().unwrap() = ActivityTracker::with_source(Box::new(script));
+
+            let current = poll_current_activity(&state);
+            assert!(current.is_some());
+            let outcome = track_activity(&state, current, Utc::now()).await;
+
+            assert_eq!(outcome, TickOutcome::Skipped);
+            assert!(state.db.get_current_activity().await.unwrap().is_none());
+        }
+    }
+
+    mod categorization {
+        use crate::activity::{
+            categorize, categorize_activity, explain_categorization, reapply_mappings,
+        };
+        use crate::matcher::CompiledMappings;
+        use crate::models::{
+            ActivityCategory, CategorizationSource, CategoryTransition, MappingSuggestion,
+            MappingTarget, MatchKind, TitleMapping, UrlMapping,
+        };
+        use crate::pattern::{
+            self, app_matches, identifier_matches, url_matches, validate_app_pattern,
+            validate_identifiers, validate_title_pattern, validate_url_pattern,
+        };
+        use crate::test_config::test_utils::performance::{assert_performance, measure_async};
+        use crate::test_config::test_utils::{create_sample_activity, create_test_database};
+        use chrono::Utc;
+        use uuid::Uuid;
+
+        #[test]
+        fn test_app_match_kinds() {
+            assert!(app_matches(
+                MatchKind::Exact,
+                "Visual Studio Code|Code",
+                "code"
+            ));
+            assert!(!app_matches(
+                MatchKind::Exact,
+                "Visual Studio Code|Code",
+                "Xcode"
+            ));
+            assert!(app_matches(MatchKind::Substring, "Code", "Xcode"));
+            assert!(app_matches(
+                MatchKind::Glob,
+                "JetBrains *",
+                "JetBrains Rider"
+            ));
+            assert!(!app_matches(
+                MatchKind::Glob,
+                "JetBrains *",
+                "Rider by JetBrains"
+            ));
+            assert!(!app_matches(
+                MatchKind::Regex,
+                r"^(py|web)storm$",
+                "PyCharm"
+            ));
+            assert!(app_matches(
+                MatchKind::Regex,
+                r"^(py|web)storm$",
+                "WebStorm"
+            ));
+        }
+
+        #[test]
+        fn test_url_match_kinds() {
+            let kind = MatchKind::DomainSuffix;
+            assert!(url_matches(
+                kind,
+                "github.com",
+                "https://github.com/faiyaz26/velosi"
+            ));
+            assert!(url_matches(kind, "github.com", "https://gist.github.com/x"));
+            assert!(url_matches(kind, "github.com", "github.com/faiyaz26"));
+            assert!(!url_matches(kind, "github.com", "https://notgithub.com"));
+            assert!(!url_matches(kind, "store", "https://app-store.example.com"));
+
+            assert!(url_matches(
+                kind,
+                "apple.com/music",
+                "https://www.apple.com/music/"
+            ));
+            assert!(!url_matches(
+                kind,
+                "apple.com/music",
+                "https://apple.com/musicals"
+            ));
+
+            assert!(url_matches(
+                MatchKind::Glob,
+                "https://*.atlassian.net/jira/*",
+                "https://acme.atlassian.net/jira/board"
+            ));
+        }
+
+        #[test]
+        fn test_pattern_validation() {
+            assert!(validate_app_pattern(MatchKind::Exact, "Code").is_ok());
+            assert!(validate_app_pattern(MatchKind::Substring, "X").is_err());
+            assert!(validate_app_pattern(MatchKind::Substring, "Code||Xcode").is_err());
+            assert!(validate_app_pattern(MatchKind::Glob, "*").is_err());
+            assert!(validate_app_pattern(MatchKind::Regex, "(unclosed").is_err());
+            assert!(validate_app_pattern(MatchKind::Regex, ".*").is_err());
+            assert!(validate_app_pattern(MatchKind::DomainSuffix, "github.com").is_err());
+
+            assert!(validate_url_pattern(MatchKind::DomainSuffix, "github.com|gitlab.com").is_ok());
+            assert!(validate_url_pattern(MatchKind::DomainSuffix, "https://github.com").is_err());
+            assert!(validate_url_pattern(MatchKind::DomainSuffix, "localhost").is_err());
+            assert!(validate_url_pattern(MatchKind::Substring, "   ").is_err());
+        }
+
+        #[tokio::test]
+        async fn test_seeded_mappings_match_exact_app_names() {
+            let db = create_test_database().await;
+
+            assert_eq!(
+                categorize_activity(&db, "Code", None, "", None).await,
+                ActivityCategory::new("development")
+            );
+            // Substring matching used to put every app containing "X" under social
+            assert_eq!(
+                categorize_activity(&db, "Xcode", None, "", None).await,
+                ActivityCategory::new("development")
+            );
+            assert_eq!(
+                categorize_activity(
+                    &db,
+                    "Google Chrome",
+                    None,
+                    "",
+                    Some("https://docs.rs/regex")
+                )
+                .await,
+                ActivityCategory::new("development")
+            );
+        }
+
+        #[tokio::test]
+        async fn test_custom_mappings_beat_seeded_ones() {
+            let db = create_test_database().await;
+            db.add_simple_app_mapping("entertainment", "Slack", true, MatchKind::Substring)
+                .await
+                .unwrap();
+
+            assert_eq!(
+                categorize_activity(&db, "Slack", None, "", None).await,
+                ActivityCategory::new("entertainment")
+            );
+
+            // Priority only orders mappings of the same origin, even when it's negative
+            let mut custom = db
+                .get_app_mappings()
+                .await
+                .unwrap()
+                .into_iter()
+                .find(|m| m.is_custom && m.app_pattern == "Slack")
+                .unwrap();
+            custom.priority = -5;
+            db.update_app_mapping(&custom).await.unwrap();
+
+            let explanation = explain_categorization(&db, "Slack", None, None, None).await;
+            assert_eq!(explanation.category, ActivityCategory::new("entertainment"));
+            assert!(explanation.overridden[0].ends_with("custom mappings beat seeded ones"));
+        }
+
+        #[tokio::test]
+        async fn test_specific_patterns_beat_generic_ones() {
+            let db = create_test_database().await;
+            db.add_simple_app_mapping("social", "Acme Studio", true, MatchKind::Substring)
+                .await
+                .unwrap();
+            db.add_simple_app_mapping("productive", "Acme Studio Pro", true, MatchKind::Exact)
+                .await
+                .unwrap();
+            db.add_simple_app_mapping("development", "Acme*", true, MatchKind::Glob)
+                .await
+                .unwrap();
+
+            assert_eq!(
+                categorize_activity(&db, "Acme Studio Pro", None, "", None).await,
+                ActivityCategory::new("productive")
+            );
+            assert_eq!(
+                categorize_activity(&db, "Acme Studio Lite", None, "", None).await,
+                ActivityCategory::new("development")
+            );
+            assert_eq!(
+                categorize_activity(&db, "The Acme Studio", None, "", None).await,
+                ActivityCategory::new("social")
+            );
+
+            // An explicit priority outranks specificity
+            let mut generic = db
+                .get_app_mappings()
+                .await
+                .unwrap()
+                .into_iter()
+                .find(|m| m.app_pattern == "Acme Studio")
+                .unwrap();
+            generic.priority = 10;
+            db.update_app_mapping(&generic).await.unwrap();
+
+            assert_eq!(
+                categorize_activity(&db, "Acme Studio Pro", None, "", None).await,
+                ActivityCategory::new("social")
+            );
+        }
+
+        #[tokio::test]
+        async fn test_mapping_conflicts_explain_the_winner() {
+            let db = create_test_database().await;
+            db.add_simple_app_mapping("social", "Acme Studio", true, MatchKind::Substring)
+                .await
+                .unwrap();
+            db.add_simple_app_mapping("productive", "Acme Studio Pro", true, MatchKind::Exact)
+                .await
+                .unwrap();
+
+            let conflicts = db.get_mapping_conflicts().await.unwrap();
+            let conflict = conflicts
+                .iter()
+                .find(|c| c.target == MappingTarget::App && c.sample == "Acme Studio Pro")
+                .expect("overlapping mappings should be reported");
+
+            assert_eq!(conflict.winner.pattern, "Acme Studio Pro");
+            assert_eq!(conflict.overridden.len(), 1);
+            assert_eq!(conflict.overridden[0].pattern, "Acme Studio");
+            assert!(!conflict.same_category);
+            assert!(conflict
+                .explanation
+                .contains("exact matches are more specific than substring matches"));
+        }
+
+        #[tokio::test]
+        async fn test_explain_categorization() {
+            let db = create_test_database().await;
+            db.add_simple_app_mapping("social", "Acme Studio", true, MatchKind::Substring)
+                .await
+                .unwrap();
+            db.add_simple_app_mapping("productive", "Acme Studio Pro", true, MatchKind::Exact)
+                .await
+                .unwrap();
+
+            let explanation =
+                explain_categorization(&db, "Acme Studio Pro", None, Some("Untitled"), None).await;
+
+            // The per-tick path decides the same way without the explanation
+            let decided = categorize(&db, "Acme Studio Pro", None, "Untitled", None).await;
+            assert_eq!(decided.category, explanation.category);
+            assert_eq!(decided.source, explanation.source);
+            assert_eq!(
+                decided.mapping_id,
+                explanation
+                    .matched_mapping
+                    .as_ref()
+                    .map(|mapping| mapping.id)
+            );
+
+            assert_eq!(explanation.category, ActivityCategory::new("productive"));
+            assert_eq!(explanation.source, CategorizationSource::AppMapping);
+            assert_eq!(
+                explanation.matched_mapping.unwrap().pattern,
+                "Acme Studio Pro"
+            );
+            assert_eq!(explanation.precedence_rank, Some(1));
+            assert_eq!(explanation.overridden.len(), 1);
+            assert!(explanation
+                .fallback_path
+                .iter()
+                .any(|step| step == "No URL, URL mappings skipped"));
+
+            let fallback = explain_categorization(
+                &db,
+                "Some Unmapped App",
+                None,
+                None,
+                Some("https://unmapped.example"),
+            )
+            .await;
+            assert_eq!(fallback.category, ActivityCategory::unknown());
+            assert_eq!(fallback.source, CategorizationSource::Fallback);
+            let decided = categorize(
+                &db,
+                "Some Unmapped App",
+                None,
+                "",
+                Some("https://unmapped.example"),
+            )
+            .await;
+            assert_eq!(decided.source, CategorizationSource::Fallback);
+            assert!(decided.mapping_id.is_none());
+            assert!(fallback.matched_mapping.is_none());
+            assert_eq!(
+                fallback.fallback_path,
+                vec![
+                    "No URL mapping matched https://unmapped.example".to_string(),
+                    "No window title, title rules skipped".to_string(),
+                    "No app mapping matched 'Some Unmapped App'".to_string(),
+                    "Fell back to Unknown".to_string(),
+                ]
+            );
+        }
+
+        #[test]
+        fn test_identifier_matching() {
+            let ids = "com.microsoft.VSCode|Code.exe|code";
+            assert!(identifier_matches(ids, "com.microsoft.vscode"));
+            assert!(identifier_matches(
+                ids,
+                r"C:\Users\me\AppData\Local\Programs\Microsoft VS Code\Code.exe"
+            ));
+            assert!(identifier_matches(ids, "code"));
+            assert!(identifier_matches(
+                ids,
+                "/usr/share/applications/code.desktop"
+            ));
+            assert!(!identifier_matches(ids, "com.microsoft.VSCodeInsiders"));
+            assert!(!identifier_matches(ids, ""));
+            assert!(validate_identifiers("com.apple.Safari||").is_err());
+        }
+
+        #[tokio::test]
+        async fn test_seeded_identifiers_categorize_on_every_platform() {
+            let db = create_test_database().await;
+
+            for (app_name, bundle_id) in [
+                ("Code - Insiders", "com.microsoft.VSCode"),
+                (
+                    "Code (Benutzer)",
+                    r"C:\Program Files\Microsoft VS Code\Code.exe",
+                ),
+                ("code-oss", "code-oss"),
+            ] {
+                assert_eq!(
+                    categorize_activity(&db, app_name, Some(bundle_id), "", None).await,
+                    ActivityCategory::new("development"),
+                    "{} / {}",
+                    app_name,
+                    bundle_id
+                );
+            }
+            assert_eq!(
+                categorize_activity(&db, "Unbekannt", Some("Unknown.exe"), "", None).await,
+                ActivityCategory::unknown()
+            );
+        }
+
+        fn title_rule(
+            title_pattern: &str,
+            app_pattern: Option<&str>,
+            category: &str,
+        ) -> TitleMapping {
+            TitleMapping {
+                id: Uuid::new_v4(),
+                title_pattern: title_pattern.to_string(),
+                app_pattern: app_pattern.map(str::to_string),
+                category_id: category.to_string(),
+                is_custom: true,
+                match_kind: MatchKind::Substring,
+                priority: 0,
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            }
+        }
+
+        #[tokio::test]
+        async fn test_title_rule_conflicts_respect_app_scopes() {
+            let db = create_test_database().await;
+            for rule in [
+                title_rule("Standup", None, "productive"),
+                title_rule("Standup", Some("Slack"), "communication"),
+                title_rule("Planning", Some("Zoom"), "communication"),
+                title_rule("Planning", Some("Slack"), "productive"),
+                title_rule("Review", None, "development"),
+            ] {
+                db.add_title_mapping(&rule).await.unwrap();
+            }
+            let mut activity = create_sample_activity("Notes", "Standup Review");
+            activity.category = ActivityCategory::new("productive");
+            db.start_activity(&activity).await.unwrap();
+
+            let conflicts = db.get_mapping_conflicts().await.unwrap();
+            let titles: Vec<&str> = conflicts
+                .iter()
+                .filter(|c| c.target == MappingTarget::Title)
+                .map(|c| c.sample.as_str())
+                .collect();
+            // Rules limited to different apps never meet
+            assert_eq!(titles, ["Slack: Standup", "Notes: Standup Review"]);
+
+            let scoped = conflicts
+                .iter()
+                .find(|c| c.sample == "Slack: Standup")
+                .unwrap();
+            assert_eq!(scoped.winner.app_scope.as_deref(), Some("Slack"));
+            assert!(scoped
+                .explanation
+                .contains("rules limited to an app beat ones for every app"));
+        }
+
+        #[tokio::test]
+        async fn test_title_rules_beat_app_mappings() {
+            let db = create_test_database().await;
+            db.add_title_mapping(&title_rule("#incidents", Some("Slack"), "productive"))
+                .await
+                .unwrap();
+            db.add_title_mapping(&title_rule("velosi", None, "development"))
+                .await
+                .unwrap();
+
+            assert_eq!(
+                categorize_activity(&db, "Slack", None, "Slack | #incidents | Acme", None).await,
+                ActivityCategory::new("productive")
+            );
+            // The rule is limited to Slack
+            assert_eq!(
+                categorize_activity(&db, "Discord", None, "#incidents", None).await,
+                ActivityCategory::new("communication")
+            );
+            assert_eq!(
+                categorize_activity(&db, "Terminal", None, "~/src/velosi — zsh", None).await,
+                ActivityCategory::new("development")
+            );
+            // URL mappings still come first
+            assert_eq!(
+                categorize_activity(
+                    &db,
+                    "Google Chrome",
+                    None,
+                    "velosi - YouTube",
+                    Some("https://www.youtube.com/watch?v=1")
+                )
+                .await,
+                ActivityCategory::new("entertainment")
+            );
+        }
+
+        #[tokio::test]
+        async fn test_seeded_title_rules_cover_browsers_without_urls() {
+            let db = create_test_database().await;
+
+            assert_eq!(
+                categorize_activity(&db, "Firefox", None, "Rust in 100 Seconds - YouTube", None)
+                    .await,
+                ActivityCategory::new("entertainment")
+            );
+            // Seeded rules are limited to browsers
+            assert_eq!(
+                categorize_activity(&db, "Xcode", None, "YouTubePlayer.swift - YouTube", None)
+                    .await,
+                ActivityCategory::new("development")
+            );
+            // X pages end in "/ X"; other titles containing it fall through to the app mapping
+            assert_eq!(
+                categorize_activity(&db, "Safari", None, "Home / X", None).await,
+                ActivityCategory::new("social")
+            );
+            assert_eq!(
+                categorize_activity(&db, "Safari", None, "docs / Xcode release notes", None).await,
+                ActivityCategory::new("productive")
+            );
+            assert!(validate_title_pattern(MatchKind::DomainSuffix, "x.com", None).is_err());
+            assert!(validate_title_pattern(MatchKind::Substring, "velosi", Some("")).is_err());
+        }
+
+        #[tokio::test]
+        async fn test_compiled_mappings_agree_with_patterns() {
+            let db = create_test_database().await;
+            db.add_simple_app_mapping("development", "JetBrains *", true, MatchKind::Glob)
+                .await
+                .unwrap();
+            db.add_simple_app_mapping("productive", r"^(py|web)storm$", true, MatchKind::Regex)
+                .await
+                .unwrap();
+            db.add_simple_url_mapping("productive", "docs.rs/tokio", true, MatchKind::DomainSuffix)
+                .await
+                .unwrap();
+            db.add_simple_url_mapping("social", "*://*.reddit.com/*", true, MatchKind::Glob)
+                .await
+                .unwrap();
+            db.add_title_mapping(&title_rule(
+                "pull request",
+                Some("Firefox|Safari"),
+                "development",
+            ))
+            .await
+            .unwrap();
+
+            let urls = db.get_url_mappings().await.unwrap();
+            let titles = db.get_title_mappings().await.unwrap();
+            let apps = db.get_app_mappings().await.unwrap();
+            let compiled = CompiledMappings::new(&urls, &titles, &apps);
+
+            let first_index = |found: Vec<(usize, _)>| found.first().map(|(index, _)| *index);
+
+            for url in [
+                "https://www.youtube.com/watch?v=1",
+                "https://docs.rs/tokio/latest/tokio/",
+                "https://docs.rs/serde",
+                "https://old.reddit.com/r/rust/",
+                "github.com/faiyaz26/velosi",
+                "https://notyoutube.com/",
+                "about:blank",
+            ] {
+                let expected = urls
+                    .iter()
+                    .position(|m| url_matches(m.match_kind, &m.url_pattern, url));
+                assert_eq!(first_index(compiled.match_url(url)), expected, "{}", url);
+            }
+
+            for (app_name, title) in [
+                ("Firefox", "Fix parser by someone · Pull Request #12"),
+                ("Terminal", "Fix parser by someone · Pull Request #12"),
+                ("Safari", "Rust in 100 Seconds - YouTube"),
+                ("Xcode", "YouTubePlayer.swift - YouTube"),
+            ] {
+                let expected = titles.iter().position(|m| {
+                    pattern::title_matches(
+                        m.match_kind,
+                        &m.title_pattern,
+                        m.app_pattern.as_deref(),
+                        app_name,
+                        title,
+                    )
+                });
+                assert_eq!(
+                    first_index(compiled.match_title(app_name, title)),
+                    expected,
+                    "{} / {}",
+                    app_name,
+                    title
+                );
+            }
+
+            for (app_name, bundle_id) in [
+                ("JetBrains Rider", None),
+                ("PyStorm", None),
+                ("Visual Studio Code", None),
+                (
+                    "Code (Benutzer)",
+                    Some(r"C:\Program Files\Microsoft VS Code\Code.exe"),
+                ),
+                ("Slack", Some("com.tinyspeck.slackmacgap")),
+                ("Some Unmapped App", Some("unmapped")),
+            ] {
+                let expected = apps
+                    .iter()
+                    .position(|m| pattern::app_mapping_matches(m, app_name, bundle_id));
+                assert_eq!(
+                    first_index(compiled.match_app(app_name, bundle_id)),
+                    expected,
+                    "{}",
+                    app_name
+                );
+            }
+        }
+
+        /// Per-tick categorization cost with thousands of mappings, printed with
+        /// `cargo test test_categorization_with_thousands_of_mappings -- --nocapture`
+        #[tokio::test]
+        async fn test_categorization_with_thousands_of_mappings() {
+            const MAPPINGS_PER_TABLE: usize = 2_000;
+            const TICKS: u32 = 1_000;
+
+            let db = create_test_database().await;
+            for i in 0..MAPPINGS_PER_TABLE {
+                let kind = [MatchKind::Exact, MatchKind::Substring, MatchKind::Glob][i % 3];
+                db.add_simple_app_mapping("productive", &format!("Bench App {:04}", i), true, kind)
+                    .await
+                    .unwrap();
+                db.add_url_mapping(&UrlMapping {
+                    id: Uuid::new_v4(),
+                    url_pattern: format!("bench{}.example.com", i),
+                    category_id: "productive".to_string(),
+                    is_custom: true,
+                    match_kind: MatchKind::DomainSuffix,
+                    priority: 0,
+                    created_at: Utc::now(),
+                    updated_at: Utc::now(),
+                })
+                .await
+                .unwrap();
+                db.add_title_mapping(&title_rule(
+                    &format!("bench ticket {}", i),
+                    None,
+                    "productive",
+                ))
+                .await
+                .unwrap();
+            }
+
+            // The first tick compiles the mappings
+            let (_, first) =
+                measure_async(|| categorize_activity(&db, "Unmapped", None, "Untitled", None))
+                    .await;
+
+            let category = assert_performance(
+                measure_async(|| async {
+                    let mut category = ActivityCategory::unknown();
+                    for _ in 0..TICKS {
+                        category = categorize_activity(
+                            &db,
+                            "Google Chrome",
+                            Some("com.google.Chrome"),
+                            "Issue tracker - Google Chrome",
+                            Some("https://app.bench1999.example.com/board"),
+                        )
+                        .await;
+                    }
+                    category
+                })
+                .await,
+                std::time::Duration::from_secs(5),
+                "categorizing with thousands of mappings",
+            );
+            assert_eq!(category, ActivityCategory::new("productive"));
+
+            let (_, cached) = measure_async(|| async {
+                for _ in 0..TICKS {
+                    categorize_activity(&db, "Unmapped", None, "Untitled", None).await;
+                }
+            })
+            .await;
+            println!(
+                "{} mappings: first tick {:?}, cached miss {:?} per tick",
+                MAPPINGS_PER_TABLE * 3,
+                first,
+                cached / TICKS
+            );
+
+            // Writing a mapping invalidates the compiled set
+            db.add_simple_app_mapping("social", "Unmapped", true, MatchKind::Exact)
+                .await
+                .unwrap();
+            assert_eq!(
+                categorize_activity(&db, "Unmapped", None, "Untitled", None).await,
+                ActivityCategory::new("social")
+            );
+        }
+
+        #[tokio::test]
+        async fn test_reapply_mappings_skips_manual_changes_and_can_be_undone() {
+            let db = create_test_database().await;
+            let mapped_later = create_sample_activity("Acme Studio", "Untitled");
+            let corrected = create_sample_activity("Acme Studio", "Untitled");
+            let mut unchanged = create_sample_activity("Visual Studio Code", "main.rs");
+            unchanged.category = ActivityCategory::new("development");
+            for activity in [&mapped_later, &corrected, &unchanged] {
+                db.start_activity(activity).await.unwrap();
+            }
+            db.update_activity_category(
+                &corrected.id.to_string(),
+                &ActivityCategory::new("social"),
+            )
+            .await
+            .unwrap();
+
+            db.add_simple_app_mapping("productive", "Acme Studio", true, MatchKind::Exact)
+                .await
+                .unwrap();
+
+            let (start, end) = (
+                Utc::now() - chrono::Duration::hours(1),
+                Utc::now() + chrono::Duration::hours(1),
+            );
+            let category_of = |id: Uuid| {
+                let db = &db;
+                async move {
+                    db.get_activities_for_recategorization(start, end)
+                        .await
+                        .unwrap()
+                        .into_iter()
+                        .find(|activity| activity.id == id)
+                        .unwrap()
+                        .category
+                }
+            };
+
+            let report = reapply_mappings(&db, start, end).await.unwrap();
+            assert_eq!(report.changed, 1);
+            assert_eq!(report.unchanged, 1);
+            assert_eq!(report.skipped_manual, 1);
+            assert_eq!(
+                report.transitions,
+                vec![CategoryTransition {
+                    from: ActivityCategory::unknown(),
+                    to: ActivityCategory::new("productive"),
+                    activities: 1,
+                }]
+            );
+            assert_eq!(
+                category_of(mapped_later.id).await,
+                ActivityCategory::new("productive")
+            );
+            assert_eq!(
+                category_of(corrected.id).await,
+                ActivityCategory::new("social")
+            );
+
+            let run_id = report.run_id.expect("changes should be recorded");
+            let runs = db.get_recategorization_runs().await.unwrap();
+            assert_eq!(runs.len(), 1);
+            assert_eq!(runs[0].changed, 1);
+
+            assert_eq!(db.undo_recategorization(&run_id).await.unwrap(), 1);
+            assert_eq!(
+                category_of(mapped_later.id).await,
+                ActivityCategory::unknown()
+            );
+            assert!(db.undo_recategorization(&run_id).await.is_err());
+        }
+
+        #[tokio::test]
+        async fn test_repeated_corrections_suggest_mappings() {
+            let db = create_test_database().await;
+            let corrections = [
+                (
+                    "Acme Studio",
+                    None,
+                    ActivityCategory::unknown(),
+                    3,
+                    ActivityCategory::new("productive"),
+                ),
+                (
+                    "Google Chrome",
+                    Some("https://www.youtube.com/watch?v=talk"),
+                    ActivityCategory::new("entertainment"),
+                    4,
+                    ActivityCategory::new("productive"),
+                ),
+                (
+                    "Figma",
+                    None,
+                    ActivityCategory::unknown(),
+                    2,
+                    ActivityCategory::new("development"),
+                ),
+                // The mappings already give Slack this category
+                (
+                    "Slack",
+                    None,
+                    ActivityCategory::new("social"),
+                    3,
+                    ActivityCategory::new("communication"),
+                ),
+            ];
+            for (app_name, url, computed, times, corrected) in corrections {
+                for _ in 0..times {
+                    let mut activity = create_sample_activity(app_name, "Untitled");
+                    activity.url = url.map(str::to_string);
+                    activity.category = computed.clone();
+                    db.start_activity(&activity).await.unwrap();
+                    db.update_activity_category(&activity.id.to_string(), &corrected)
+                        .await
+                        .unwrap();
+                }
+            }
+
+            let suggestions = db.get_mapping_suggestions(3).await.unwrap();
+            assert_eq!(suggestions.len(), 2, "{:?}", suggestions);
+            assert_eq!(suggestions[0].target, MappingTarget::Url);
+            assert_eq!(suggestions[0].pattern, "youtube.com");
+            assert_eq!(suggestions[0].match_kind, MatchKind::DomainSuffix);
+            assert_eq!(suggestions[0].corrections, 4);
+            assert_eq!(
+                suggestions[0].current_category_id.as_deref(),
+                Some("entertainment")
+            );
+            assert_eq!(suggestions[1].target, MappingTarget::App);
+            assert_eq!(suggestions[1].pattern, "Acme Studio");
+            assert_eq!(suggestions[1].current_category_id, None);
+
+            // Title rules aren't suggested, so a title suggestion creates nothing
+            let mappings_before = db.get_app_mappings().await.unwrap().len();
+            let title_suggestion = MappingSuggestion {
+                target: MappingTarget::Title,
+                ..suggestions[1].clone()
+            };
+            assert!(db
+                .accept_mapping_suggestion(&title_suggestion)
+                .await
+                .is_err());
+            assert_eq!(db.get_app_mappings().await.unwrap().len(), mappings_before);
+
+            db.accept_mapping_suggestion(&suggestions[1]).await.unwrap();
+            assert_eq!(
+                categorize_activity(&db, "Acme Studio", None, "", None).await,
+                ActivityCategory::new("productive")
+            );
+            let remaining = db.get_mapping_suggestions(3).await.unwrap();
+            assert_eq!(remaining.len(), 1);
+            assert_eq!(remaining[0].pattern, "youtube.com");
+        }
+
+        #[tokio::test]
+        async fn test_accepted_suggestions_beat_the_custom_mapping_they_correct() {
+            let db = create_test_database().await;
+            // Same pattern and priority as the suggestion, and a category id that sorts first
+            db.add_simple_app_mapping("development", "Acme Studio", true, MatchKind::Exact)
+                .await
+                .unwrap();
+            for _ in 0..3 {
+                let mut activity = create_sample_activity("Acme Studio", "Untitled");
+                activity.category = ActivityCategory::new("development");
+                db.start_activity(&activity).await.unwrap();
+                db.update_activity_category(
+                    &activity.id.to_string(),
+                    &ActivityCategory::new("productive"),
+                )
+                .await
+                .unwrap();
+            }
+
+            let suggestions = db.get_mapping_suggestions(3).await.unwrap();
+            assert_eq!(suggestions.len(), 1, "{:?}", suggestions);
+            assert_eq!(
+                suggestions[0].current_category_id.as_deref(),
+                Some("development")
+            );
+            let accepted = db.accept_mapping_suggestion(&suggestions[0]).await.unwrap();
+            assert_eq!(accepted.priority, 1);
+
+            let explanation = explain_categorization(&db, "Acme Studio", None, None, None).await;
+            assert_eq!(explanation.category, ActivityCategory::new("productive"));
+            assert_eq!(explanation.matched_mapping.unwrap().id, accepted.id);
+            assert!(db.get_mapping_suggestions(3).await.unwrap().is_empty());
+        }
+    }
 }
@@ -42,6 +42,23 @@ impl TrayManager {
             "🎯 Focus Mode: Unknown".to_string()
         };
 
+        // When a focus schedule next turns focus mode on or off
+        let next_transition = app.try_state::<AppState>().and_then(|state| {
+            state
+                .next_focus_transition
+                .lock()
+                .ok()
+                .and_then(|next| next.clone())
+        });
+        let schedule_status_text = next_transition.map(|next| {
+            format!(
+                "⏰ Focus Mode {} at {} ({})",
+                if next.enables_focus_mode { "ON" } else { "OFF" },
+                next.local_time.format("%a %H:%M"),
+                next.schedule_name
+            )
+        });
+
         let tracking_toggle_text = if is_tracking {
             "Pause Tracking"
         } else {
@@ -66,6 +83,10 @@ impl TrayManager {
         let focus_status_item =
             MenuItem::with_id(app, "focus_status", focus_status_text, false, None::<&str>)
                 .map_err(|e| e.to_string())?;
+        let schedule_status_item = schedule_status_text
+            .map(|text| MenuItem::with_id(app, "schedule_status", text, false, None::<&str>))
+            .transpose()
+            .map_err(|e| e.to_string())?;
         let tracking_toggle_item =
             MenuItem::with_id(app, "toggle", tracking_toggle_text, true, None::<&str>)
                 .map_err(|e| e.to_string())?;
@@ -109,6 +130,12 @@ impl TrayManager {
             .map_err(|e| e.to_string())?
         };
 
+        // Under the focus mode status
+        if let Some(schedule_status_item) = schedule_status_item {
+            menu.insert(&schedule_status_item, 2)
+                .map_err(|e| e.to_string())?;
+        }
+
         Ok(menu)
     }
 
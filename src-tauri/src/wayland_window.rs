@@ -0,0 +1,273 @@
+//! Foreground-window lookup for Wayland sessions.
+//!
+//! Wayland has no global "active window" query, so each compositor family needs its own route:
+//! - wlroots compositors expose `zwlr_foreign_toplevel_manager_v1`, which lists every
+//!   toplevel with its title, app_id and an `activated` state.
+//! - GNOME Shell doesn't implement that protocol; we call the Window Calls extension
+//!   (`org.gnome.Shell.Extensions.Windows`) over D-Bus with `gdbus`, the same way the
+//!   macOS backend shells out to `osascript`.
+
+use std::collections::HashMap;
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+use std::process::Command;
+
+use serde::Deserialize;
+use wayland_client::backend::ObjectId;
+use wayland_client::protocol::wl_registry;
+use wayland_client::{event_created_child, Connection, Dispatch, Proxy, QueueHandle};
+use wayland_protocols_wlr::foreign_toplevel::v1::client::{
+    zwlr_foreign_toplevel_handle_v1::{self, ZwlrForeignToplevelHandleV1},
+    zwlr_foreign_toplevel_manager_v1::{self, ZwlrForeignToplevelManagerV1},
+};
+
+use crate::linux_window::LinuxWindow;
+
+#[derive(Debug, Default)]
+struct Toplevel {
+    title: Option<String>,
+    app_id: Option<String>,
+    activated: bool,
+}
+
+#[derive(Default)]
+struct ToplevelState {
+    manager: Option<ZwlrForeignToplevelManagerV1>,
+    toplevels: HashMap<ObjectId, Toplevel>,
+}
+
+/// Read the activated toplevel from a wlroots compositor.
+///
+/// `socket` overrides `$WAYLAND_DISPLAY` (used by the headless sway test).
+pub fn active_wlroots_window(socket: Option<&Path>) -> Option<LinuxWindow> {
+    let connection = match socket {
+        Some(path) => UnixStream::connect(path)
+            .ok()
+            .and_then(|stream| Connection::from_socket(stream).ok()),
+        None => Connection::connect_to_env().ok(),
+    };
+    let Some(connection) = connection else {
+        println!("Failed to connect to the Wayland compositor");
+        return None;
+    };
+
+    let mut queue = connection.new_event_queue();
+    let qh = queue.handle();
+    connection.display().get_registry(&qh, ());
+
+    let mut state = ToplevelState::default();
+    // First roundtrip binds the manager, the next ones deliver the toplevels and their state
+    for _ in 0..3 {
+        if let Err(e) = queue.roundtrip(&mut state) {
+            println!("Wayland roundtrip failed: {}", e);
+            return None;
+        }
+        if state.manager.is_none() {
+            println!("Compositor does not support wlr-foreign-toplevel-management");
+            return None;
+        }
+    }
+
+    if let Some(manager) = state.manager.take() {
+        manager.stop();
+        let _ = connection.flush();
+    }
+
+    state
+        .toplevels
+        .into_iter()
+        .find(|(_, toplevel)| toplevel.activated)
+        .map(|(id, toplevel)| LinuxWindow {
+            id: id.protocol_id().to_string(),
+            title: toplevel.title.filter(|t| !t.is_empty()),
+            instance: None,
+            class: toplevel.app_id.filter(|a| !a.is_empty()),
+            pid: None,
+        })
+}
+
+impl Dispatch<wl_registry::WlRegistry, ()> for ToplevelState {
+    fn event(
+        state: &mut Self,
+        registry: &wl_registry::WlRegistry,
+        event: wl_registry::Event,
+        _: &(),
+        _: &Connection,
+        qh: &QueueHandle<Self>,
+    ) {
+        if let wl_registry::Event::Global {
+            name,
+            interface,
+            version,
+        } = event
+        {
+            if interface == ZwlrForeignToplevelManagerV1::interface().name {
+                state.manager = Some(registry.bind(name, version.min(3), qh, ()));
+            }
+        }
+    }
+}
+
+impl Dispatch<ZwlrForeignToplevelManagerV1, ()> for ToplevelState {
+    fn event(
+        state: &mut Self,
+        _: &ZwlrForeignToplevelManagerV1,
+        event: zwlr_foreign_toplevel_manager_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let zwlr_foreign_toplevel_manager_v1::Event::Toplevel { toplevel } = event {
+            state.toplevels.entry(toplevel.id()).or_default();
+        }
+    }
+
+    event_created_child!(ToplevelState, ZwlrForeignToplevelManagerV1, [
+        zwlr_foreign_toplevel_manager_v1::EVT_TOPLEVEL_OPCODE => (ZwlrForeignToplevelHandleV1, ()),
+    ]);
+}
+
+impl Dispatch<ZwlrForeignToplevelHandleV1, ()> for ToplevelState {
+    fn event(
+        state: &mut Self,
+        handle: &ZwlrForeignToplevelHandleV1,
+        event: zwlr_foreign_toplevel_handle_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        use zwlr_foreign_toplevel_handle_v1::Event;
+
+        if let Event::Closed = event {
+            state.toplevels.remove(&handle.id());
+            handle.destroy();
+            return;
+        }
+
+        let toplevel = state.toplevels.entry(handle.id()).or_default();
+        match event {
+            Event::Title { title } => toplevel.title = Some(title),
+            Event::AppId { app_id } => toplevel.app_id = Some(app_id),
+            Event::State { state } => toplevel.activated = is_activated(&state),
+            _ => {}
+        }
+    }
+}
+
+/// The `state` event carries a wl_array of native-endian u32 state values.
+pub fn is_activated(states: &[u8]) -> bool {
+    let activated = zwlr_foreign_toplevel_handle_v1::State::Activated as u32;
+    states
+        .chunks_exact(4)
+        .map(|chunk| u32::from_ne_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .any(|state| state == activated)
+}
+
+/// A window as listed by the Window Calls GNOME Shell extension.
+#[derive(Debug, Clone, Deserialize)]
+pub struct GnomeWindow {
+    pub id: u64,
+    #[serde(default)]
+    pub wm_class: Option<String>,
+    #[serde(default)]
+    pub wm_class_instance: Option<String>,
+    #[serde(default)]
+    pub pid: Option<u32>,
+    #[serde(default)]
+    pub focus: bool,
+    /// Older extension versions include the title in `List`; newer ones need `GetTitle`
+    #[serde(default)]
+    pub title: Option<String>,
+}
+
+const GNOME_WINDOWS_DEST: &str = "org.gnome.Shell";
+const GNOME_WINDOWS_PATH: &str = "/org/gnome/Shell/Extensions/Windows";
+const GNOME_WINDOWS_IFACE: &str = "org.gnome.Shell.Extensions.Windows";
+
+/// Read the focused window from GNOME Shell through the Window Calls extension.
+pub fn active_gnome_window() -> Option<LinuxWindow> {
+    let list = run_gdbus("List", &[])?;
+    let json = parse_gdbus_string(&list)?;
+    let window = parse_gnome_windows(&json)?.into_iter().find(|w| w.focus)?;
+
+    let title = match window.title.clone() {
+        Some(title) => Some(title),
+        None => run_gdbus("GetTitle", &[&window.id.to_string()])
+            .and_then(|output| parse_gdbus_string(&output)),
+    };
+
+    Some(LinuxWindow {
+        id: window.id.to_string(),
+        title: title.filter(|t| !t.is_empty()),
+        instance: window.wm_class_instance,
+        class: window.wm_class,
+        pid: window.pid,
+    })
+}
+
+fn run_gdbus(method: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new("gdbus")
+        .args([
+            "call",
+            "--session",
+            "--dest",
+            GNOME_WINDOWS_DEST,
+            "--object-path",
+            GNOME_WINDOWS_PATH,
+            "--method",
+        ])
+        .arg(format!("{}.{}", GNOME_WINDOWS_IFACE, method))
+        .args(args)
+        .output();
+
+    match output {
+        Ok(output) if output.status.success() => {
+            Some(String::from_utf8_lossy(&output.stdout).to_string())
+        }
+        Ok(output) => {
+            println!(
+                "gdbus {} failed (is the Window Calls extension installed?): {}",
+                method,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+            None
+        }
+        Err(e) => {
+            println!("Failed to execute gdbus: {}", e);
+            None
+        }
+    }
+}
+
+/// Extract the first string from a printed GVariant tuple such as `('[{...}]',)`.
+pub fn parse_gdbus_string(output: &str) -> Option<String> {
+    let mut chars = output.chars();
+    let quote = chars.find(|c| *c == '\'' || *c == '"')?;
+
+    let mut value = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next()? {
+                'n' => value.push('\n'),
+                't' => value.push('\t'),
+                escaped => value.push(escaped),
+            },
+            c if c == quote => return Some(value),
+            c => value.push(c),
+        }
+    }
+
+    // Unterminated string
+    None
+}
+
+/// Parse the JSON array returned by the extension's `List` method.
+pub fn parse_gnome_windows(json: &str) -> Option<Vec<GnomeWindow>> {
+    match serde_json::from_str(json) {
+        Ok(windows) => Some(windows),
+        Err(e) => {
+            println!("Failed to parse GNOME window list: {}", e);
+            None
+        }
+    }
+}
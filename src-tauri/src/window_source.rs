@@ -0,0 +1,82 @@
+//! Abstraction over "what window is in front right now".
+//!
+//! `ActivityTracker` only talks to a `WindowSource`, so the OS backends (AppleScript on
+//! macOS, Win32 on Windows, X11/Wayland on Linux) are interchangeable with the scripted
+//! source used by tests.
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::tracker::SegmentInfo;
+
+/// Snapshot of the foreground window as reported by a `WindowSource`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WindowInfo {
+    pub app_name: String,
+    /// macOS bundle id, Windows process id, or Linux WM_CLASS/app_id
+    pub bundle_id: Option<String>,
+    pub title: String,
+    pub url: Option<String>,
+    pub pid: Option<u32>,
+    /// Backends that understand the app (browser tab, editor file, ...) fill this in
+    #[serde(default)]
+    pub segment_info: Option<SegmentInfo>,
+}
+
+pub trait WindowSource: Send {
+    /// Return the current foreground window, or `None` if nothing trackable is focused.
+    fn active_window(&mut self) -> Option<WindowInfo>;
+
+    /// Time since the user last touched keyboard or mouse, if known.
+    ///
+    /// Returning `None` means the source can't tell, and the user is treated as active.
+    fn idle_time(&mut self) -> Option<Duration> {
+        None
+    }
+}
+
+/// Replays a fixed sequence of windows, one per call, for deterministic tests.
+///
+/// `None` frames simulate "no foreground window". Once the script is exhausted the
+/// source keeps returning `None`. Idle times are scripted separately, one per idle check,
+/// and default to zero (user present).
+#[cfg(test)]
+pub struct ScriptedWindowSource {
+    frames: std::collections::VecDeque<Option<WindowInfo>>,
+    idle_times: std::collections::VecDeque<Duration>,
+}
+
+#[cfg(test)]
+impl ScriptedWindowSource {
+    pub fn new(frames: Vec<Option<WindowInfo>>) -> Self {
+        Self {
+            frames: frames.into(),
+            idle_times: Default::default(),
+        }
+    }
+
+    pub fn with_idle_times(mut self, idle_times: Vec<Duration>) -> Self {
+        self.idle_times = idle_times.into();
+        self
+    }
+
+    /// Load a recorded session: a JSON array of `WindowInfo` objects or `null`.
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        Ok(Self::new(serde_json::from_str(json)?))
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.frames.len()
+    }
+}
+
+#[cfg(test)]
+impl WindowSource for ScriptedWindowSource {
+    fn active_window(&mut self) -> Option<WindowInfo> {
+        self.frames.pop_front().flatten()
+    }
+
+    fn idle_time(&mut self) -> Option<Duration> {
+        Some(self.idle_times.pop_front().unwrap_or_default())
+    }
+}
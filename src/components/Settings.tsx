@@ -19,6 +19,7 @@ import {
   Moon,
   Sun,
   Download,
+  Timer,
 } from "lucide-react";
 import { updateService } from "@/lib/updateService";
 
@@ -27,6 +28,8 @@ export function Settings() {
   const [checkingPermissions, setCheckingPermissions] = useState(false);
   const [proxyPort, setProxyPort] = useState<string>("62828");
   const [savingProxyPort, setSavingProxyPort] = useState(false);
+  const [idleMinutes, setIdleMinutes] = useState<string>("5");
+  const [savingIdleThreshold, setSavingIdleThreshold] = useState(false);
   const [checkingForUpdates, setCheckingForUpdates] = useState(false);
   const [openingAutomation, setOpeningAutomation] = useState(false);
   const [theme, setTheme] = useState<"light" | "dark">(() => {
@@ -68,6 +71,7 @@ export function Settings() {
   useEffect(() => {
     checkPermissionStatus();
     loadProxyPort();
+    loadIdleThreshold();
   }, []);
 
   useEffect(() => {
@@ -120,6 +124,33 @@ export function Settings() {
     }
   };
 
+  const loadIdleThreshold = async () => {
+    try {
+      const seconds = await invoke<number>("get_idle_threshold");
+      setIdleMinutes(Math.round(seconds / 60).toString());
+    } catch (error) {
+      console.error("Failed to load idle threshold:", error);
+    }
+  };
+
+  const saveIdleThreshold = async () => {
+    setSavingIdleThreshold(true);
+    try {
+      const minutes = parseInt(idleMinutes);
+      if (isNaN(minutes) || minutes < 1 || minutes > 240) {
+        alert("Please enter a number of minutes between 1 and 240");
+        return;
+      }
+      await invoke("set_idle_threshold", { threshold_seconds: minutes * 60 });
+      alert("Idle threshold saved successfully!");
+    } catch (error) {
+      console.error("Failed to save idle threshold:", error);
+      alert("Failed to save idle threshold");
+    } finally {
+      setSavingIdleThreshold(false);
+    }
+  };
+
   return (
     <div className="space-y-6">
       <div>
@@ -339,6 +370,47 @@ export function Settings() {
           </CardContent>
         </Card>
 
+        {/* Idle Detection */}
+        <Card>
+          <CardHeader>
+            <CardTitle className="flex items-center gap-2">
+              <Timer className="h-5 w-5" />
+              Idle Detection
+            </CardTitle>
+            <CardDescription>
+              Stop counting time when you step away from the computer
+            </CardDescription>
+          </CardHeader>
+          <CardContent className="space-y-4">
+            <div className="space-y-2">
+              <div className="text-sm font-medium">Idle After (minutes)</div>
+              <div className="flex items-center gap-2">
+                <Input
+                  id="idle-threshold"
+                  type="number"
+                  placeholder="5"
+                  value={idleMinutes}
+                  onChange={(e) => setIdleMinutes(e.target.value)}
+                  min="1"
+                  max="240"
+                  className="w-32"
+                />
+                <Button
+                  onClick={saveIdleThreshold}
+                  disabled={savingIdleThreshold}
+                  size="sm"
+                >
+                  {savingIdleThreshold ? "Saving..." : "Save"}
+                </Button>
+              </div>
+              <p className="text-xs text-muted-foreground">
+                With no keyboard or mouse input for this long, the current
+                activity ends at the moment you stopped. Default: 5 minutes
+              </p>
+            </div>
+          </CardContent>
+        </Card>
+
         {/* Appearance */}
         <Card>
           <CardHeader>
@@ -49,7 +49,7 @@ const getCategoryColor = (
   categoryService: any
 ): string => {
   const key =
-    typeof cat === "string" ? cat : Object.keys(cat || {})[0] || "Unknown";
+    typeof cat === "string" ? cat : Object.keys(cat || {})[0] || "unknown";
 
   if (isInitialized && categoryService) {
     const categoryInfo = categoryService.getCategoryById(key.toLowerCase());
@@ -59,16 +59,16 @@ const getCategoryColor = (
   }
 
   // Fallback colors if category service hasn't loaded
-  switch (key) {
-    case "Productive":
+  switch (key.toLowerCase()) {
+    case "productive":
       return "#22d3ee"; // cyan
-    case "Development":
+    case "development":
       return "#a78bfa"; // violet
-    case "Communication":
+    case "communication":
       return "#60a5fa"; // blue
-    case "Social":
+    case "social":
       return "#f472b6"; // pink
-    case "Entertainment":
+    case "entertainment":
       return "#34d399"; // emerald
     default:
       return "#94a3b8"; // slate
@@ -41,15 +41,15 @@ export function getCategoryColor(
 
   // Fallback colors (hex values for charts)
   const fallbackColors: { [key: string]: string } = {
-    Development: "#3B82F6",     // Blue
-    Productive: "#10B981",      // Green
-    Communication: "#F59E0B",   // Yellow
-    Social: "#EF4444",          // Red
-    Entertainment: "#8B5CF6",   // Purple
-    Unknown: "#64748b",         // Gray
+    development: "#3B82F6",     // Blue
+    productive: "#10B981",      // Green
+    communication: "#F59E0B",   // Yellow
+    social: "#EF4444",          // Red
+    entertainment: "#8B5CF6",   // Purple
+    unknown: "#64748b",         // Gray
   };
 
-  return fallbackColors[categoryKey] || fallbackColors.Unknown;
+  return fallbackColors[categoryKey.toLowerCase()] || fallbackColors.unknown;
 }
 
 export function getCategoryName(
@@ -76,7 +76,7 @@ export function getCategoryName(
     }
   }
 
-  // Fallback to the original enum name (e.g., "Social", "Development")
+  // Fall back to the category id (e.g., "social", "development")
   return categoryKey;
 }
 